@@ -0,0 +1,199 @@
+//! Reading of AUTOSAR system descriptions (`.arxml`), mapping the CAN
+//! frames, their I-PDUs and I-signals to messages and signals
+
+use crate::{
+    convert::{Message, Network, Signal},
+    xml::{self, Element},
+};
+
+/// The elements of a type, which references name by the path of
+/// `SHORT-NAME`s of their packages; only the last name is compared,
+/// so elements of a type must have distinct names
+struct Elements<'a>(Vec<&'a Element>);
+
+impl<'a> Elements<'a> {
+    fn new(root: &'a Element, name: &str) -> Self {
+        let mut out = vec![];
+        root.descendants(name, &mut out);
+        Self(out)
+    }
+
+    /// The element named by a reference within another element
+    fn get(&self, from: &Element, reference: &str) -> Option<&'a Element> {
+        let mut refs = vec![];
+        from.descendants(reference, &mut refs);
+        let name = refs.first()?.text.rsplit('/').next()?;
+        self.0
+            .iter()
+            .find(|e| e.text("SHORT-NAME") == Some(name))
+            .copied()
+    }
+}
+
+/// The number at a path, which may be `0x`-prefixed hexadecimal
+#[allow(clippy::cast_precision_loss)]
+fn number(e: &Element, path: &str) -> Result<Option<f64>, String> {
+    e.text(path)
+        .map(|t| {
+            let parsed = match t.strip_prefix("0x").or(t.strip_prefix("0X")) {
+                Some(hex) => {
+                    u64::from_str_radix(hex, 16).map(|n| n as f64).ok()
+                }
+                None => t.parse().ok(),
+            };
+            parsed.ok_or_else(|| format!("Invalid {path} {t}"))
+        })
+        .transpose()
+}
+
+/// The scaling, unit and value descriptions of a signal from its
+/// `COMPU-METHOD`, either linear or a text table (or both)
+fn compu_method(signal: &mut Signal, method: &Element) -> Result<(), String> {
+    let mut scales = vec![];
+    method.descendants("COMPU-SCALE", &mut scales);
+    for scale in scales {
+        if let Some(label) = scale.text("COMPU-CONST/VT") {
+            let value = number(scale, "LOWER-LIMIT")?
+                .ok_or_else(|| format!("Missing LOWER-LIMIT of {label}"))?;
+            signal.labels.push((value.to_string(), label.to_string()));
+        }
+        let Some(coeffs) = scale.find("COMPU-RATIONAL-COEFFS") else {
+            continue;
+        };
+        let values = |path: &str| -> Result<Vec<f64>, String> {
+            coeffs.find(path).map_or(Ok(vec![]), |e| {
+                e.children("V")
+                    .map(|v| {
+                        v.text.parse().map_err(|_| {
+                            format!("Invalid coefficient {}", v.text)
+                        })
+                    })
+                    .collect()
+            })
+        };
+        let numerator = values("COMPU-NUMERATOR")?;
+        let denominator =
+            values("COMPU-DENOMINATOR")?.first().copied().unwrap_or(1.0);
+        let offset = numerator.first().copied().unwrap_or(0.0);
+        let factor = numerator.get(1).copied().unwrap_or(1.0);
+        signal.factor = (factor / denominator).to_string();
+        signal.offset = (offset / denominator).to_string();
+        if let Some(min) = number(scale, "LOWER-LIMIT")? {
+            signal.min =
+                (min * factor / denominator + offset / denominator).to_string();
+        }
+        if let Some(max) = number(scale, "UPPER-LIMIT")? {
+            signal.max =
+                (max * factor / denominator + offset / denominator).to_string();
+        }
+    }
+    Ok(())
+}
+
+/// Convert an AUTOSAR system description to the equivalent DBC text
+pub fn to_dbc(text: &str) -> Result<String, String> {
+    let document = xml::parse(text)?;
+    let frames = Elements::new(&document, "CAN-FRAME");
+    let pdus = Elements::new(&document, "I-SIGNAL-I-PDU");
+    let signals = Elements::new(&document, "I-SIGNAL");
+    let system_signals = Elements::new(&document, "SYSTEM-SIGNAL");
+    let methods = Elements::new(&document, "COMPU-METHOD");
+    let base_types = Elements::new(&document, "SW-BASE-TYPE");
+    let units = Elements::new(&document, "UNIT");
+
+    let mut network = Network::default();
+    let mut ecus = vec![];
+    document.descendants("ECU-INSTANCE", &mut ecus);
+    network.nodes = ecus
+        .iter()
+        .filter_map(|e| e.text("SHORT-NAME"))
+        .map(str::to_string)
+        .collect();
+
+    let mut triggerings = vec![];
+    document.descendants("CAN-FRAME-TRIGGERING", &mut triggerings);
+    for triggering in triggerings {
+        let frame = frames
+            .get(triggering, "FRAME-REF")
+            .ok_or("Missing CAN-FRAME of CAN-FRAME-TRIGGERING")?;
+        let name = frame.text("SHORT-NAME").ok_or("Missing SHORT-NAME")?;
+        let id = number(triggering, "IDENTIFIER")?
+            .ok_or_else(|| format!("Missing IDENTIFIER of {name}"))?;
+        let extended =
+            triggering.text("CAN-ADDRESSING-MODE") == Some("EXTENDED");
+        let mut message = Message {
+            id: if extended {
+                id as u32 | 0x8000_0000
+            } else {
+                id as u32
+            },
+            name: name.to_string(),
+            length: number(frame, "FRAME-LENGTH")?.map(|l| l as usize),
+            ..Message::default()
+        };
+
+        let mut mappings = vec![];
+        frame.descendants("PDU-TO-FRAME-MAPPING", &mut mappings);
+        for mapping in mappings {
+            let Some(pdu) = pdus.get(mapping, "PDU-REF") else {
+                continue;
+            };
+            let pdu_start =
+                number(mapping, "START-POSITION")?.unwrap_or(0.0) as usize;
+            let mut periods = vec![];
+            pdu.descendants("CYCLIC-TIMING", &mut periods);
+            if let Some(period) = periods.first() {
+                let seconds = number(period, "TIME-PERIOD/VALUE")?
+                    .or(number(period, "REPEATING-TIME/VALUE")?);
+                message.cycle_time = seconds
+                    .map(|s| (s * 1000.0).round() as usize)
+                    .filter(|t| *t > 0);
+            }
+
+            let mut signal_mappings = vec![];
+            pdu.descendants("I-SIGNAL-TO-I-PDU-MAPPING", &mut signal_mappings);
+            for m in signal_mappings {
+                // signal groups are mapped along with their signals
+                let Some(isignal) = signals.get(m, "I-SIGNAL-REF") else {
+                    continue;
+                };
+                let name =
+                    isignal.text("SHORT-NAME").ok_or("Missing SHORT-NAME")?;
+                let start = number(m, "START-POSITION")?.ok_or_else(|| {
+                    format!("Missing START-POSITION of {name}")
+                })? as usize;
+                let length = number(isignal, "LENGTH")?
+                    .ok_or_else(|| format!("Missing LENGTH of {name}"))?
+                    as usize;
+                let mut signal = Signal {
+                    big_endian: m.text("PACKING-BYTE-ORDER")
+                        == Some("MOST-SIGNIFICANT-BYTE-FIRST"),
+                    ..Signal::new(name, pdu_start + start, length)
+                };
+                signal.signed = base_types
+                    .get(isignal, "BASE-TYPE-REF")
+                    .and_then(|t| t.text("BASE-TYPE-ENCODING"))
+                    == Some("2C");
+                let method =
+                    methods.get(isignal, "COMPU-METHOD-REF").or_else(|| {
+                        system_signals
+                            .get(isignal, "SYSTEM-SIGNAL-REF")
+                            .and_then(|s| methods.get(s, "COMPU-METHOD-REF"))
+                    });
+                if let Some(method) = method {
+                    compu_method(&mut signal, method)?;
+                    if let Some(unit) = units.get(method, "UNIT-REF") {
+                        signal.unit = unit
+                            .text("DISPLAY-NAME")
+                            .or(unit.text("SHORT-NAME"))
+                            .unwrap_or_default()
+                            .to_string();
+                    }
+                }
+                message.signals.push(signal);
+            }
+        }
+        network.messages.push(message);
+    }
+    Ok(network.to_dbc())
+}
@@ -0,0 +1,180 @@
+//! Parsing of the attributes given to derives
+
+use syn::{
+    punctuated::Punctuated, Attribute, Expr, ExprLit, Lit, LitStr, Meta,
+    MetaNameValue, Path, Result, Token,
+};
+
+/// Whether a marker attribute such as `#[name]` is present
+pub fn has_attr(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|a| {
+        a.path().segments.len() == 1 && a.path().segments[0].ident == name
+    })
+}
+
+/// Parse all string values of a repeatable attribute, which may also
+/// hold a list, e.g. `#[name = "a"] #[name("b", "c")]`; the literals
+/// are returned so that errors can refer to them
+pub fn parse_attrs(attrs: &[Attribute], name: &str) -> Result<Vec<LitStr>> {
+    let mut values = vec![];
+    for attr in attrs.iter().filter(|a| {
+        a.path().segments.len() == 1 && a.path().segments[0].ident == name
+    }) {
+        match &attr.meta {
+            Meta::List(_) => {
+                let list = attr.parse_args_with(
+                    Punctuated::<LitStr, Token![,]>::parse_terminated,
+                )?;
+                values.extend(list);
+            }
+            Meta::NameValue(MetaNameValue {
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(s), ..
+                    }),
+                ..
+            }) => values.push(s.clone()),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    format!("Expected #[{name} = \"...\"]"),
+                ))
+            }
+        }
+    }
+    Ok(values)
+}
+
+pub fn parse_attr(attrs: &[Attribute], name: &str) -> Option<String> {
+    let attr = attrs.iter().find(|a| {
+        a.path().segments.len() == 1 && a.path().segments[0].ident == name
+    })?;
+
+    let expr = match &attr.meta {
+        Meta::NameValue(n) => Some(&n.value),
+        _ => None,
+    };
+
+    match &expr {
+        Some(Expr::Lit(e)) => match &e.lit {
+            Lit::Str(s) => Some(s.value()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parse a value from a list attribute such as `#[name(key = "value")]`
+pub fn parse_list_attr(
+    attrs: &[Attribute],
+    name: &str,
+    key: &str,
+) -> Result<Option<String>> {
+    let Some(attr) = attrs.iter().find(|a| {
+        a.path().segments.len() == 1 && a.path().segments[0].ident == name
+    }) else {
+        return Ok(None);
+    };
+
+    let mut value = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident(key) {
+            let s: LitStr = meta.value()?.parse()?;
+            value = Some(s.value());
+            Ok(())
+        } else {
+            Err(meta.error(format!("Expected #[{name}({key} = \"...\")]")))
+        }
+    })?;
+    Ok(value)
+}
+
+/// Parse a list of paths from an attribute such as
+/// `#[name(Debug, serde::Serialize)]`
+pub fn parse_path_list_attr(
+    attrs: &[Attribute],
+    name: &str,
+) -> Result<Option<Vec<Path>>> {
+    let Some(attr) = attrs.iter().find(|a| {
+        a.path().segments.len() == 1 && a.path().segments[0].ident == name
+    }) else {
+        return Ok(None);
+    };
+    let paths =
+        attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+    Ok(Some(paths.into_iter().collect()))
+}
+
+/// Find an attribute by name, e.g. to report an error at it
+pub fn find_attr<'a>(
+    attrs: &'a [Attribute],
+    name: &str,
+) -> Option<&'a Attribute> {
+    attrs.iter().find(|a| {
+        a.path().segments.len() == 1 && a.path().segments[0].ident == name
+    })
+}
+
+/// Parse a `cfg` style predicate from an attribute such as
+/// `#[name(feature = "a")]` or `#[name(any(feature = "a", test))]`
+pub fn parse_meta_attr(
+    attrs: &[Attribute],
+    name: &str,
+) -> Result<Option<Meta>> {
+    attrs
+        .iter()
+        .find(|a| {
+            a.path().segments.len() == 1 && a.path().segments[0].ident == name
+        })
+        .map(Attribute::parse_args)
+        .transpose()
+}
+
+/// Parse an integer attribute such as `#[name = 0xAA]`, also
+/// accepting the value as a string, e.g. `#[name = "0xAA"]`
+pub fn parse_int_attr(attrs: &[Attribute], name: &str) -> Result<Option<u64>> {
+    let Some(attr) = attrs.iter().find(|a| {
+        a.path().segments.len() == 1 && a.path().segments[0].ident == name
+    }) else {
+        return Ok(None);
+    };
+
+    let invalid = || {
+        syn::Error::new_spanned(attr, format!("Expected #[{name} = <integer>]"))
+    };
+    let Meta::NameValue(n) = &attr.meta else {
+        return Err(invalid());
+    };
+    match &n.value {
+        Expr::Lit(e) => match &e.lit {
+            Lit::Int(i) => i.base10_parse().map(Some),
+            Lit::Str(s) => parse_int(&s.value()).map(Some).ok_or_else(invalid),
+            _ => Err(invalid()),
+        },
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse a decimal or `0x` prefixed hexadecimal integer
+pub fn parse_int(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        value.parse().ok()
+    }
+}
+
+/// Parse an integer range such as `0x300..=0x30F` or `0x300..0x310`,
+/// as its inclusive bounds
+pub fn parse_range(value: &str) -> Option<(u64, u64)> {
+    if let Some((start, end)) = value.split_once("..=") {
+        Some((parse_int(start)?, parse_int(end)?))
+    } else {
+        let (start, end) = value.split_once("..")?;
+        Some((parse_int(start)?, parse_int(end)?.checked_sub(1)?))
+    }
+}
@@ -0,0 +1,154 @@
+//! Compatibility shims for code migrating from `dbc-codegen`
+
+use crate::{
+    naming::{pascal_case, snake_case},
+    signal::SignalInfo,
+    MessageInfo,
+};
+use proc_macro2::TokenStream;
+use quote::{quote, TokenStreamExt};
+use syn::{parse_str, Ident};
+
+/// An identifier for a converted name, as a raw identifier if the
+/// name is a keyword
+fn converted_ident(name: &str, from: &Ident) -> Ident {
+    if parse_str::<Ident>(name).is_ok() {
+        Ident::new(name, from.span())
+    } else {
+        Ident::new_raw(name, from.span())
+    }
+}
+
+/// Generate `dbc-codegen` style accessors for a message: a
+/// `PascalCase` type alias, `raw()`/`set_raw()` for the PDU and
+/// `snake_case` getters and setters for each signal, with `error` the
+/// type generated by `gen_messages()`; the setters of `#[dbc_dirty]`
+/// messages mark the signals changed
+pub fn gen_message(
+    message: &MessageInfo,
+    infos: &[SignalInfo],
+    error: &Ident,
+) -> TokenStream {
+    let ident = &message.ident;
+    let mut accessors = TokenStream::new();
+    for info in infos {
+        let field = &info.ident;
+        let ntype = &info.ntype;
+        let getter = converted_ident(&snake_case(&field.to_string()), field);
+        let setter = converted_ident(&format!("set_{getter}"), field);
+        // private fields already have accessors, with the same names
+        // when the signal is named in `snake_case`
+        let private = message.private || message.dirty;
+        if !(private && getter == *field) {
+            accessors.append_all(quote! {
+                pub fn #getter(&self) -> #ntype {
+                    self.#field
+                }
+            });
+        }
+        if private && setter == format!("set_{field}") {
+            continue;
+        }
+        let set = if message.dirty {
+            let dirty = Ident::new(&format!("set_{field}"), field.span());
+            quote! { self.#dirty(value); }
+        } else {
+            quote! { self.#field = value; }
+        };
+        accessors.append_all(quote! {
+            pub fn #setter(&mut self, value: #ntype) {
+                #set
+            }
+        });
+    }
+
+    let pascal = converted_ident(&pascal_case(&ident.to_string()), ident);
+    let alias = if pascal == *ident {
+        quote! {}
+    } else {
+        quote! {
+            pub type #pascal = #ident;
+        }
+    };
+
+    quote! {
+        #alias
+
+        #[automatically_derived]
+        impl #ident {
+            /// The encoded PDU
+            pub fn raw(&self) -> [u8; Self::DLC as usize] {
+                let mut pdu = [0u8; Self::DLC as usize];
+                self.encode(&mut pdu);
+                pdu
+            }
+
+            /// Decode the signals from a PDU
+            pub fn set_raw(&mut self, pdu: &[u8]) -> Result<(), #error> {
+                if self.decode(pdu) {
+                    Ok(())
+                } else {
+                    Err(#error::InvalidPayloadSize)
+                }
+            }
+
+            #accessors
+        }
+    }
+}
+
+/// Generate the `<Name>Messages` enum, with `from_can_message()`, and
+/// the `<Name>CanError` type it returns, given those names
+pub fn gen_messages(
+    messages: &[&MessageInfo],
+    kind: &Ident,
+    error: &Ident,
+) -> TokenStream {
+    let mut variants = TokenStream::new();
+    let mut arms = TokenStream::new();
+    let mut seen = vec![];
+    for message in messages {
+        let ident = &message.ident;
+        variants.append_all(quote! { #ident(#ident), });
+        // as in dbc-codegen, only the ID value is matched and the
+        // first message declared for an ID takes precedence
+        let id = message.id;
+        if seen.contains(&id) {
+            continue;
+        }
+        seen.push(id);
+        arms.append_all(quote! {
+            #id => #kind::#ident(#ident::try_from(payload)
+                .map_err(|()| #error::InvalidPayloadSize)?),
+        });
+    }
+
+    quote! {
+        #[automatically_derived]
+        #[allow(non_camel_case_types)]
+        #[allow(clippy::large_enum_variant)]
+        #[allow(dead_code)]
+        pub enum #kind {
+            #variants
+        }
+
+        #[automatically_derived]
+        impl #kind {
+            /// Decode a frame into the message with the matching ID
+            pub fn from_can_message(id: u32, payload: &[u8])
+                                    -> Result<Self, #error> {
+                Ok(match id {
+                    #arms
+                    _ => return Err(#error::UnknownMessageId(id)),
+                })
+            }
+        }
+
+        /// Errors returned by the `dbc-codegen` compatible interfaces
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum #error {
+            UnknownMessageId(u32),
+            InvalidPayloadSize,
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! A network read from another file format, written as DBC text so it
+//! is loaded by the same code as DBC files
+
+use std::fmt::Write;
+
+#[derive(Clone, Default)]
+pub struct Signal {
+    pub name: String,
+    /// The DBC start bit: the least significant bit of little-endian
+    /// signals, or the most significant of big-endian ones
+    pub start: usize,
+    pub length: usize,
+    pub big_endian: bool,
+    pub signed: bool,
+    pub factor: String,
+    pub offset: String,
+    pub min: String,
+    pub max: String,
+    pub unit: String,
+    pub receivers: Vec<String>,
+    /// Value descriptions, by raw value
+    pub labels: Vec<(String, String)>,
+    /// The raw start value, if any
+    pub initial: Option<u64>,
+}
+
+impl Signal {
+    /// An unsigned little-endian signal with no scaling
+    pub fn new(name: &str, start: usize, length: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            start,
+            length,
+            factor: "1".into(),
+            offset: "0".into(),
+            min: "0".into(),
+            max: "0".into(),
+            ..Self::default()
+        }
+    }
+
+    /// The DBC start bit of a big-endian signal, given a bit offset
+    /// counted from the most significant bit of each byte, as used by
+    /// the KCD and SYM formats (and read by `cantools`)
+    pub fn msb_start(offset: usize) -> usize {
+        8 * (offset / 8) + 7 - offset % 8
+    }
+
+    /// The last byte holding part of the signal
+    fn last_byte(&self) -> usize {
+        if self.big_endian {
+            self.start / 8
+                + (self.length.saturating_sub(self.start % 8 + 1)).div_ceil(8)
+        } else {
+            (self.start + self.length).saturating_sub(1) / 8
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Message {
+    /// The DBC message ID, with bit 31 set for extended IDs
+    pub id: u32,
+    pub name: String,
+    /// The length in bytes, or else that needed by the signals
+    pub length: Option<usize>,
+    pub cycle_time: Option<usize>,
+    pub transmitter: Option<String>,
+    pub signals: Vec<Signal>,
+}
+
+#[derive(Default)]
+pub struct Network {
+    pub version: String,
+    pub nodes: Vec<String>,
+    pub messages: Vec<Message>,
+}
+
+impl Network {
+    /// The equivalent DBC text
+    pub fn to_dbc(&self) -> String {
+        let quoted = |s: &str| s.replace('"', "'");
+        let mut dbc = format!(
+            "VERSION \"{}\"\n\nNS_ :\n\nBS_:\n\nBU_:",
+            quoted(&self.version)
+        );
+        for name in &self.nodes {
+            let _ = write!(dbc, " {name}");
+        }
+        dbc.push_str("\n\n");
+        for m in &self.messages {
+            let length = m.length.unwrap_or_else(|| {
+                m.signals
+                    .iter()
+                    .map(|s| s.last_byte() + 1)
+                    .max()
+                    .unwrap_or(0)
+            });
+            let transmitter = m.transmitter.as_deref().unwrap_or("Vector__XXX");
+            let _ = writeln!(
+                dbc,
+                "BO_ {} {}: {length} {transmitter}",
+                m.id, m.name
+            );
+            for s in &m.signals {
+                let receivers = if s.receivers.is_empty() {
+                    "Vector__XXX".to_string()
+                } else {
+                    s.receivers.join(",")
+                };
+                let _ = writeln!(
+                    dbc,
+                    " SG_ {} : {}|{}@{}{} ({},{}) [{}|{}] \"{}\" {receivers}",
+                    s.name,
+                    s.start,
+                    s.length,
+                    if s.big_endian { '0' } else { '1' },
+                    if s.signed { '-' } else { '+' },
+                    s.factor,
+                    s.offset,
+                    s.min,
+                    s.max,
+                    quoted(&s.unit),
+                );
+            }
+            dbc.push('\n');
+        }
+
+        // attribute definitions, then defaults, then values
+        let cycle_times = self.messages.iter().any(|m| m.cycle_time.is_some());
+        let initials = self
+            .messages
+            .iter()
+            .any(|m| m.signals.iter().any(|s| s.initial.is_some()));
+        if cycle_times {
+            dbc.push_str("BA_DEF_ BO_ \"GenMsgCycleTime\" INT 0 65535;\n");
+        }
+        if initials {
+            dbc.push_str(
+                "BA_DEF_ SG_ \"GenSigStartValue\" INT 0 2147483647;\n",
+            );
+        }
+        if cycle_times {
+            dbc.push_str("BA_DEF_DEF_ \"GenMsgCycleTime\" 0;\n");
+        }
+        if initials {
+            dbc.push_str("BA_DEF_DEF_ \"GenSigStartValue\" 0;\n");
+        }
+        for m in &self.messages {
+            if let Some(cycle_time) = m.cycle_time {
+                let _ = writeln!(
+                    dbc,
+                    "BA_ \"GenMsgCycleTime\" BO_ {} {cycle_time};",
+                    m.id
+                );
+            }
+            for s in &m.signals {
+                if let Some(initial) = s.initial {
+                    let _ = writeln!(
+                        dbc,
+                        "BA_ \"GenSigStartValue\" SG_ {} {} {initial};",
+                        m.id, s.name
+                    );
+                }
+            }
+        }
+        dbc.push('\n');
+        for m in &self.messages {
+            for s in m.signals.iter().filter(|s| !s.labels.is_empty()) {
+                let _ = write!(dbc, "VAL_ {} {}", m.id, s.name);
+                for (value, name) in &s.labels {
+                    let _ = write!(dbc, " {value} \"{}\"", quoted(name));
+                }
+                dbc.push_str(" ;\n");
+            }
+        }
+        dbc
+    }
+}
@@ -0,0 +1,354 @@
+//! DBC loading and databases shared between derives
+
+use crate::derive::DeriveData;
+use can_dbc::DBC;
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    fs::read,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote, DeriveInput, Ident, LitStr, Result, Token,
+};
+
+/// A parsed DBC file
+pub struct Database {
+    /// The DBC file name
+    pub file: String,
+    /// The parsed DBC contents
+    pub dbc: Rc<DBC>,
+    /// The path the file was read from, if not given inline
+    pub path: Option<PathBuf>,
+}
+
+/// The contents of a DBC file, and the DBC parsed from them
+type Parsed = (Vec<u8>, Rc<DBC>);
+
+thread_local! {
+    /// The DBC files parsed by this compiler process, by canonical path
+    static PARSED: RefCell<HashMap<PathBuf, Parsed>> = RefCell::default();
+}
+
+impl Database {
+    /// Load and parse a DBC file, which is only parsed again by later
+    /// derives if its contents have changed
+    pub fn load(file: &str) -> std::result::Result<Self, String> {
+        let path = Self::path(file)?;
+        let contents = read(&path).map_err(|e| {
+            format!("Could not read {file} ({}): {e}", path.display())
+        })?;
+        let path = path.canonicalize().unwrap_or(path);
+        let parsed = PARSED.with_borrow(|parsed| {
+            parsed
+                .get(&path)
+                .filter(|(parsed, _)| *parsed == contents)
+                .map(|(_, dbc)| Rc::clone(dbc))
+        });
+        let dbc = if let Some(dbc) = parsed {
+            dbc
+        } else {
+            let dbc = Self::parse(file, &contents)?.dbc;
+            PARSED.with_borrow_mut(|parsed| {
+                parsed.insert(path.clone(), (contents, Rc::clone(&dbc)));
+            });
+            dbc
+        };
+        Ok(Self {
+            file: file.to_string(),
+            dbc,
+            path: Some(path),
+        })
+    }
+
+    /// Parse DBC text, e.g. given inline by `#[dbc_inline]`; `file`
+    /// names its source in messages
+    pub fn parse(
+        file: &str,
+        contents: &[u8],
+    ) -> std::result::Result<Self, String> {
+        let contents: &[u8] = &Self::transcode(contents);
+        let converted = Self::convert(file, contents)?;
+        let contents = converted.as_ref().map_or(contents, String::as_bytes);
+        let dbc = match DBC::from_slice(contents) {
+            Ok(dbc) => dbc,
+            Err(can_dbc::Error::Incomplete(dbc, remaining)) => {
+                let (line, text) = Self::location(contents, remaining);
+                // TODO: emit an actual compiler warning
+                eprintln!(
+                    "Warning: DBC load of {file} incomplete at line {line} \
+                     (`{text}`); some data may be missing"
+                );
+                dbc
+            }
+            Err(can_dbc::Error::Nom(
+                nom::Err::Error(e) | nom::Err::Failure(e),
+            )) => {
+                let (line, text) = Self::location(contents, e.input);
+                return Err(format!(
+                    "Unable to parse {file}, line {line}: `{text}`"
+                ));
+            }
+            Err(_) => return Err(format!("Unable to parse {file}")),
+        };
+        Ok(Self {
+            file: file.to_string(),
+            dbc: Rc::new(dbc),
+            path: None,
+        })
+    }
+
+    /// Convert a file in another format to DBC text, by its extension,
+    /// if it is not a DBC file
+    fn convert(
+        file: &str,
+        contents: &[u8],
+    ) -> std::result::Result<Option<String>, String> {
+        /// The supported formats, each enabled by the feature of the
+        /// same name as its extension
+        const FORMATS: [&str; 5] = ["arxml", "dbf", "kcd", "ldf", "sym"];
+        type Convert = fn(&str) -> std::result::Result<String, String>;
+        let extension = Path::new(file)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let convert: Option<Convert> = match extension.as_deref() {
+            #[cfg(feature = "arxml")]
+            Some("arxml") => Some(crate::arxml::to_dbc),
+            #[cfg(feature = "dbf")]
+            Some("dbf") => Some(crate::dbf::to_dbc),
+            #[cfg(feature = "kcd")]
+            Some("kcd") => Some(crate::kcd::to_dbc),
+            #[cfg(feature = "ldf")]
+            Some("ldf") => Some(crate::ldf::to_dbc),
+            #[cfg(feature = "sym")]
+            Some("sym") => Some(crate::sym::to_dbc),
+            Some(ext) if FORMATS.contains(&ext) => {
+                return Err(format!(
+                    "{file} requires the `{ext}` feature of dbc-data"
+                ));
+            }
+            _ => None,
+        };
+        let Some(convert) = convert else {
+            return Ok(None);
+        };
+        let text = std::str::from_utf8(contents)
+            .map_err(|e| format!("Unable to parse {file}: {e}"))?;
+        convert(text)
+            .map(Some)
+            .map_err(|e| format!("Unable to parse {file}: {e}"))
+    }
+
+    /// The contents as UTF-8; files which are not are taken to be
+    /// Windows-1252 (a superset of Latin-1), as written by many tools
+    fn transcode(contents: &[u8]) -> Cow<'_, [u8]> {
+        /// The characters of bytes 0x80 to 0x9F, which differ from
+        /// Latin-1; undefined bytes are kept as control characters
+        const CP1252: [char; 32] = [
+            '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹',
+            'Œ', '\u{8D}', 'Ž', '\u{8F}', '\u{90}', '‘', '’', '“', '”', '•',
+            '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+        ];
+        if std::str::from_utf8(contents).is_ok() {
+            return Cow::Borrowed(contents);
+        }
+        let text: String = contents
+            .iter()
+            .map(|&b| match b {
+                0x80..=0x9F => CP1252[usize::from(b - 0x80)],
+                _ => char::from(b),
+            })
+            .collect();
+        Cow::Owned(text.into_bytes())
+    }
+
+    /// The line number and text at which parsing stopped, given the
+    /// unparsed remainder of the contents
+    fn location(contents: &[u8], remaining: &str) -> (usize, String) {
+        let remaining = remaining.trim_start();
+        let parsed = &contents[..contents.len() - remaining.len()];
+        let line = parsed.split(|&b| b == b'\n').count();
+        let text = remaining.lines().next().unwrap_or_default().trim();
+        (line, text.to_string())
+    }
+
+    /// Expand the `$NAME` and `${NAME}` environment variables in a
+    /// file name
+    fn expand(file: &str) -> std::result::Result<String, String> {
+        let mut out = String::new();
+        let mut chars = file.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                out.push(c);
+                continue;
+            }
+            let braced = chars.next_if_eq(&'{').is_some();
+            let mut name = String::new();
+            while let Some(c) =
+                chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                name.push(c);
+            }
+            if braced && chars.next() != Some('}') {
+                return Err(format!("Unterminated ${{...}} in {file}"));
+            }
+            if name.is_empty() {
+                return Err(format!(
+                    "Missing environment variable name in {file}"
+                ));
+            }
+            let value = env::var(&name).map_err(|_| {
+                format!("Environment variable {name} used in {file} is not set")
+            })?;
+            out.push_str(&value);
+        }
+        Ok(out)
+    }
+
+    /// The path of a DBC file, after expanding environment variables:
+    /// relative paths are resolved against
+    /// the directory of the crate being built, when the file exists
+    /// there, and otherwise against the working directory
+    fn path(file: &str) -> std::result::Result<PathBuf, String> {
+        let path = PathBuf::from(Self::expand(file)?);
+        if path.is_relative() {
+            if let Some(dir) = env::var_os("CARGO_MANIFEST_DIR") {
+                let resolved = PathBuf::from(dir).join(&path);
+                if resolved.exists() {
+                    return Ok(resolved);
+                }
+            }
+        }
+        Ok(path)
+    }
+
+    /// A reference to the file's contents, so that the crate using it
+    /// is rebuilt when the file changes
+    pub fn track(&self) -> TokenStream {
+        let Some(path) = self.path.as_ref().and_then(|p| p.to_str()) else {
+            return TokenStream::new();
+        };
+        quote! {
+            const _: &[u8] = include_bytes!(#path);
+        }
+    }
+}
+
+/// How to resolve messages defined in more than one DBC file
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Fail with an error listing the conflicts
+    Error,
+    /// Use the definition from the first file
+    First,
+    /// Use the definition from the last file
+    Last,
+}
+
+impl ConflictPolicy {
+    /// Parse the value of `#[dbc_conflict]`, defaulting to `Error`
+    pub fn parse(value: Option<&str>) -> std::result::Result<Self, String> {
+        match value {
+            None | Some("error") => Ok(Self::Error),
+            Some("first") => Ok(Self::First),
+            Some("last") => Ok(Self::Last),
+            Some(v) => Err(format!(
+                "Invalid #[dbc_conflict] {v}; expected \"error\", \
+                 \"first\" or \"last\""
+            )),
+        }
+    }
+}
+
+/// The input to `dbc_database!`: `"file.dbc" as NAME`, optionally
+/// followed by `, crate = path` naming the `dbc_data` crate
+pub struct DatabaseDecl {
+    file: LitStr,
+    name: Ident,
+    krate: syn::Path,
+}
+
+impl Parse for DatabaseDecl {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let file = input.parse()?;
+        input.parse::<Token![as]>()?;
+        let name = input.parse()?;
+        let krate = if input.is_empty() {
+            parse_quote!(::dbc_data)
+        } else {
+            input.parse::<Token![,]>()?;
+            input.parse::<Token![crate]>()?;
+            input.parse::<Token![=]>()?;
+            input.parse()?
+        };
+        Ok(Self { file, name, krate })
+    }
+}
+
+impl DatabaseDecl {
+    /// Check that the DBC file can be parsed, and declare a `const`
+    /// naming it along with a macro of the same name, which expands
+    /// the derives using the database with `#[dbc_file]` set to it;
+    /// as an item, the macro may be used before its declaration
+    pub fn declare(&self) -> Result<TokenStream> {
+        let file = self.file.value();
+        let db = Database::load(&file)
+            .map_err(|e| syn::Error::new(self.file.span(), e))?;
+        let track = db.track();
+
+        let name = &self.name;
+        let lit = &self.file;
+        let krate = &self.krate;
+        let expander =
+            Ident::new(&format!("__dbc_database_{name}"), name.span());
+        let doc = format!("Shared DBC database loaded from `{file}`");
+        Ok(quote! {
+            #[doc = #doc]
+            pub const #name: &str = #file;
+            #track
+
+            #[doc(hidden)]
+            #[allow(unused_macros)]
+            macro_rules! #expander {
+                ($($item:tt)*) => {
+                    #krate::__dbc_use! { #lit $($item)* }
+                };
+            }
+            #[doc(hidden)]
+            #[allow(unused_imports)]
+            pub(crate) use #expander as #name;
+        })
+    }
+}
+
+/// The input to the macro declared by `dbc_database!`: the DBC file
+/// followed by an item deriving `DbcData` with `#[dbc_use]`
+pub struct SharedDerive {
+    file: LitStr,
+    input: DeriveInput,
+}
+
+impl Parse for SharedDerive {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let file = input.parse()?;
+        let input = input.parse()?;
+        Ok(Self { file, input })
+    }
+}
+
+impl SharedDerive {
+    /// Generate the code of the derive, reading the database's file
+    pub fn build(mut self) -> Result<TokenStream> {
+        let file = &self.file;
+        self.input.attrs.retain(|a| !a.path().is_ident("dbc_use"));
+        self.input.attrs.push(parse_quote!(#[dbc_file = #file]));
+        DeriveData::from(&self.input)?.build()
+    }
+}
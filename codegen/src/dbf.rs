@@ -0,0 +1,152 @@
+//! Reading of BUSMASTER (`.dbf`) databases
+
+use crate::convert::{Message, Network, Signal};
+
+fn number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid {what} {value}"))
+}
+
+/// A decimal number, which is checked but kept as written
+fn decimal(value: &str, what: &str) -> Result<String, String> {
+    number::<f64>(value, what)?;
+    Ok(value.trim().to_string())
+}
+
+/// Read a `[START_MSG]` line: name, ID, length in bytes, number of
+/// signals, byte order, frame format and transmitter
+fn message(fields: &[&str]) -> Result<Message, String> {
+    let [name, id, length, _, _, format, rest @ ..] = fields else {
+        return Err("invalid message".into());
+    };
+    let id: u32 = number(id, "message ID")?;
+    let transmitter = rest
+        .first()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .map(str::to_string);
+    Ok(Message {
+        id: if format.trim() == "X" {
+            id | 0x8000_0000
+        } else {
+            id
+        },
+        name: name.trim().to_string(),
+        length: Some(number(length, "length")?),
+        transmitter,
+        ..Message::default()
+    })
+}
+
+/// Read a `[START_SIGNALS]` line: name, length in bits, byte (from 1)
+/// and bit of the least significant bit, type, maximum, minimum, byte
+/// order (1 for Intel), factor, offset, unit, multiplexing and
+/// receivers
+fn signal(fields: &[&str]) -> Result<Signal, String> {
+    let [name, length, byte, bit, ty, max, min, order, rest @ ..] = fields
+    else {
+        return Err("invalid signal".into());
+    };
+    let [factor, offset, unit, rest @ ..] = rest else {
+        return Err(format!("invalid signal {name}"));
+    };
+    let name = name.trim();
+    let length: usize = number(length, "length")?;
+    let byte = number::<usize>(byte, "byte")?
+        .checked_sub(1)
+        .ok_or_else(|| format!("invalid byte of {name}"))?;
+    let bit: usize = number(bit, "bit")?;
+    let big_endian = order.trim() == "0";
+    let start = if big_endian {
+        // the most significant bit is towards the start of the message
+        let msb = bit + length.saturating_sub(1);
+        byte.checked_sub(msb / 8)
+            .ok_or_else(|| format!("signal {name} starts before the message"))?
+            * 8
+            + msb % 8
+    } else {
+        byte * 8 + bit
+    };
+    let mut signal = Signal {
+        big_endian,
+        factor: decimal(factor, "factor")?,
+        offset: decimal(offset, "offset")?,
+        min: decimal(min, "minimum")?,
+        max: decimal(max, "maximum")?,
+        unit: unit.trim().to_string(),
+        ..Signal::new(name, start, length)
+    };
+    match ty.trim() {
+        "U" | "B" => {}
+        "I" => signal.signed = true,
+        t => return Err(format!("signal {name} has unsupported type {t}")),
+    }
+    if rest.first().is_some_and(|m| !m.trim().is_empty()) {
+        return Err(format!("multiplexed signal {name} is not supported"));
+    }
+    signal.receivers = rest
+        .iter()
+        .skip(1)
+        .map(|r| r.trim())
+        .filter(|r| !r.is_empty() && *r != "Vector__XXX")
+        .map(str::to_string)
+        .collect();
+    Ok(signal)
+}
+
+/// Convert a BUSMASTER database to the equivalent DBC text; parameters
+/// (attributes) are not read
+pub fn to_dbc(text: &str) -> Result<String, String> {
+    let mut network = Network::default();
+    for (n, line) in text.lines().enumerate() {
+        let err = |e: String| format!("line {}: {e}", n + 1);
+        let Some((tag, value)) = line
+            .trim()
+            .strip_prefix('[')
+            .and_then(|l| l.split_once(']'))
+        else {
+            continue;
+        };
+        let fields: Vec<&str> = value.trim().split(',').collect();
+        match tag {
+            "NODE" => {
+                network.nodes = fields
+                    .iter()
+                    .map(|n| n.trim().to_string())
+                    .filter(|n| !n.is_empty())
+                    .collect();
+            }
+            "START_MSG" => {
+                network.messages.push(message(&fields).map_err(err)?);
+            }
+            "START_SIGNALS" => {
+                let message = network
+                    .messages
+                    .last_mut()
+                    .ok_or_else(|| err("signal outside a message".into()))?;
+                message.signals.push(signal(&fields).map_err(err)?);
+            }
+            "VALUE_DESCRIPTION" => {
+                let signal = network
+                    .messages
+                    .last_mut()
+                    .and_then(|m| m.signals.last_mut())
+                    .ok_or_else(|| {
+                        err("value description outside a signal".into())
+                    })?;
+                let (label, value) = value
+                    .rsplit_once(',')
+                    .ok_or_else(|| err(format!("invalid value {value}")))?;
+                let value: i64 = number(value, "value").map_err(err)?;
+                signal.labels.push((
+                    value.to_string(),
+                    label.trim().trim_matches('"').to_string(),
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(network.to_dbc())
+}
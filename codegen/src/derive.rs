@@ -0,0 +1,3997 @@
+//! Main derive macro logic
+
+use crate::{
+    compat,
+    database::{ConflictPolicy, Database},
+    find_attr, has_attr,
+    header::CHeader,
+    logs,
+    manifest::{self, Manifest},
+    mux, naming, parse_attr, parse_attrs, parse_int_attr, parse_list_attr,
+    parse_meta_attr, parse_path_list_attr, parse_range, recorder, reflect,
+    signal::{self, Rounding, SignalInfo},
+    units, MessageInfo,
+};
+use can_dbc::{ByteOrder, Message, MessageId, DBC};
+use proc_macro2::{Literal, Span, TokenStream, TokenTree};
+use quote::{quote, TokenStreamExt};
+use std::fmt::Write;
+use std::{collections::BTreeMap, fs, rc::Rc};
+use syn::{
+    parse_quote, parse_str, spanned::Spanned, Attribute, Data, DeriveInput,
+    Fields, Generics, Ident, Meta, Path, Result, Type,
+};
+
+/// The number of messages beyond which `update()` finds a frame's
+/// message by binary search, rather than by a `match` of each ID
+const DISPATCH_SEARCH_MIN: usize = 32;
+
+/// Data used for codegen
+#[allow(clippy::struct_excessive_bools)]
+pub(crate) struct DeriveData<'a> {
+    /// Name of the struct we are deriving for
+    name: &'a Ident,
+    /// Generics of the struct we are deriving for
+    generics: &'a Generics,
+    /// Whether the deriving item is a struct holding the messages
+    storage: bool,
+    /// The parsed DBC files
+    dbs: Vec<Rc<Database>>,
+    /// All of the messages to derive
+    messages: BTreeMap<String, MessageInfo>,
+    /// Type used to record message reception times, if any
+    timestamp: Option<Type>,
+    /// File to write the JSON manifest to, if any
+    manifest: Option<String>,
+    /// File to write the C header to, if any
+    c_header: Option<String>,
+    /// File to write the generated code to, if any
+    emit: Option<String>,
+    /// Fill value for unused PDU bits, when not given per-message
+    padding: u8,
+    /// Whether to generate `dbc-codegen` compatible interfaces
+    compat: bool,
+    /// Whether to fail if the generated code contains `unsafe`
+    no_unsafe: bool,
+    /// Whether to count frames and errors for each message
+    stats: bool,
+    /// Whether to generate tear-free shared wrappers for messages
+    snapshot: bool,
+    /// The number of frames held by the `FrameRecorder`, if any
+    recorder: Option<usize>,
+    /// The most bytes the deriving item may occupy, if limited
+    max_ram: Option<usize>,
+    /// The node whose received signals are generated, if limited
+    rx_node: Option<String>,
+    /// Traits to derive for each message, from `#[dbc_derive]`
+    derives: Vec<Path>,
+    /// Whether messages may share an ID, from `#[dbc_shared_ids]`
+    shared_ids: bool,
+    /// The module to generate items within, if any
+    module: Option<Ident>,
+    /// Whether to generate each message within its own module
+    message_modules: bool,
+    /// Whether to generate constants for the environment variables,
+    /// from `#[dbc_env_vars]`
+    env_vars: bool,
+    /// Whether to generate enums for `ENUM` message attributes, from
+    /// `#[dbc_attr_enums]`
+    attr_enums: bool,
+    /// Whether to implement `Debug` for each message with physical
+    /// values, units and raw values, from `#[dbc_debug]`
+    debug: bool,
+    /// Whether to generate roundtrip tests of each message, from
+    /// `#[dbc_selftest]`
+    selftest: bool,
+    /// The module of the shared functions encoding and decoding
+    /// signals, from `#[dbc_outline]`
+    outlined: Option<Ident>,
+    /// Trait implementations not to generate for each message, from
+    /// `#[dbc_skip_impls]`
+    skip_impls: Vec<String>,
+    /// How scaled values are rounded when encoding, from
+    /// `#[dbc_rounding]`
+    rounding: Rounding,
+    /// Whether signals with decimal scaling are scaled exactly, from
+    /// `#[dbc_decimal]`
+    decimal: bool,
+    /// Whether messages are `#[repr(C)]`, from `#[dbc_repr_c]`
+    repr_c: bool,
+    /// Whether to describe each message's signals in `SIGNALS`, from
+    /// `#[dbc_reflect]`
+    reflect: bool,
+    /// Whether to implement a one-line `Display` for each message, from
+    /// `#[dbc_display]`
+    display: bool,
+    /// Whether the deriving item exists, rather than only being used
+    /// to list the messages for `dbc_messages!`
+    item: bool,
+}
+
+impl<'a> DeriveData<'a> {
+    /// Generate the code of the derive, or with `#[dbc_use]`, defer to
+    /// the macro declared by `dbc_database!`, which expands it again
+    /// with the database's file
+    pub(crate) fn expand(input: &DeriveInput) -> Result<TokenStream> {
+        let Some(name) = parse_attr(&input.attrs, "dbc_use") else {
+            return DeriveData::from(input)?.build();
+        };
+        if has_attr(&input.attrs, "dbc_file")
+            || has_attr(&input.attrs, "dbc_inline")
+        {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "#[dbc_use] cannot be given with #[dbc_file] or \
+                 #[dbc_inline]",
+            ));
+        }
+        let name = parse_str::<Ident>(&name).map_err(|_| {
+            syn::Error::new_spanned(
+                find_attr(&input.attrs, "dbc_use"),
+                format!("Invalid #[dbc_use] database name {name}"),
+            )
+        })?;
+        Ok(quote! {
+            #name! { #input }
+        })
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn from(input: &'a DeriveInput) -> Result<Self> {
+        // load the DBC files
+        let files = parse_attrs(&input.attrs, "dbc_file")?;
+        let inline = parse_attr(&input.attrs, "dbc_inline");
+        if files.is_empty() && inline.is_none() && !Self::has_field_files(input)
+        {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "Missing #[dbc_file = <filename>] attribute",
+            ));
+        }
+        let mut dbs = files
+            .iter()
+            .map(|file| {
+                Database::load(&file.value())
+                    .map(Rc::new)
+                    .map_err(|e| syn::Error::new(file.span(), e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(text) = inline {
+            let db = Database::parse("#[dbc_inline]", text.as_bytes())
+                .map_err(|e| {
+                    syn::Error::new_spanned(
+                        find_attr(&input.attrs, "dbc_inline"),
+                        e,
+                    )
+                })?;
+            dbs.push(Rc::new(db));
+        }
+        let conflict = ConflictPolicy::parse(
+            parse_attr(&input.attrs, "dbc_conflict").as_deref(),
+        )
+        .map_err(|e| syn::Error::new(input.ident.span(), e))?;
+
+        let timestamp = match parse_attr(&input.attrs, "dbc_timestamp") {
+            Some(t) => Some(parse_str::<Type>(&t).map_err(|_| {
+                syn::Error::new(
+                    input.ident.span(),
+                    format!("Invalid #[dbc_timestamp] type {t}"),
+                )
+            })?),
+            None => None,
+        };
+        let manifest = parse_attr(&input.attrs, "dbc_manifest");
+        let padding = if let Some(p) =
+            parse_int_attr(&input.attrs, "dbc_padding")?
+        {
+            u8::try_from(p).map_err(|_| {
+                syn::Error::new_spanned(
+                    find_attr(&input.attrs, "dbc_padding"),
+                    format!("#[dbc_padding] value {p:#X} exceeds 0xFF"),
+                )
+            })?
+        } else {
+            let fill = dbs
+                .iter()
+                .find_map(|db| {
+                    MessageInfo::attr_default(&db.dbc, "GenMsgFillValue")
+                })
+                .unwrap_or(0);
+            u8::try_from(fill).map_err(|_| {
+                syn::Error::new(
+                    input.ident.span(),
+                    format!("Default GenMsgFillValue {fill:#X} exceeds 0xFF"),
+                )
+            })?
+        };
+
+        let rename = match parse_attr(&input.attrs, "dbc_rename_all").as_deref()
+        {
+            None => false,
+            Some("snake_case") => true,
+            Some(v) => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    format!(
+                        "Invalid #[dbc_rename_all] {v}; expected \"snake_case\""
+                    ),
+                ))
+            }
+        };
+
+        // gather all of the messages and associated attributes; files
+        // given for individual fields are added after the struct's own
+        let struct_dbs = dbs.len();
+        let mut messages: BTreeMap<String, MessageInfo> = BTreeMap::default();
+        match &input.data {
+            Data::Struct(data) => {
+                // the fields of tuple structs are accessed by index
+                for (position, field) in data.fields.iter().enumerate() {
+                    MessageInfo::field_type(&field.ty)?;
+                    let find = |dbc: &DBC| {
+                        MessageInfo::from_struct_field(
+                            dbc, field, position, rename,
+                        )
+                    };
+                    let mut info = if let Some(source) = Self::field_source(
+                        &mut dbs,
+                        &field.attrs,
+                        field.span(),
+                    )? {
+                        // the field's own file overrides the struct's
+                        let mut info =
+                            find(&dbs[source].dbc).ok_or_else(|| {
+                                syn::Error::new(
+                                    field.span(),
+                                    format!(
+                                        "Unknown message in {}",
+                                        dbs[source].file
+                                    ),
+                                )
+                            })?;
+                        info.source = source;
+                        info
+                    } else {
+                        Self::resolve(
+                            &dbs[..struct_dbs],
+                            conflict,
+                            field.span(),
+                            find,
+                        )?
+                    };
+                    info.position = position;
+                    info.normalize =
+                        Self::parse_normalize(&field.attrs, field.span())?;
+                    Self::check_signals(&info, &dbs, field.span())?;
+                    info.derives =
+                        parse_path_list_attr(&field.attrs, "dbc_derive")?;
+                    (info.decode, info.encode) =
+                        Self::parse_mode(&field.attrs, field.span())?;
+                    info.cfg = parse_meta_attr(&field.attrs, "dbc_cfg")?;
+                    messages.insert(info.ident.to_string(), info);
+                }
+                if matches!(data.fields, Fields::Unit) {
+                    Self::add_listed_messages(
+                        &mut messages,
+                        &input.attrs,
+                        &dbs,
+                        conflict,
+                        rename,
+                        input.ident.span(),
+                    )?;
+                }
+            }
+            Data::Enum(data) => {
+                for (position, variant) in data.variants.iter().enumerate() {
+                    let mut info =
+                        Self::resolve(&dbs, conflict, variant.span(), |dbc| {
+                            MessageInfo::from_enum_variant(dbc, variant, rename)
+                        })?;
+                    info.position = position;
+                    info.normalize =
+                        Self::parse_normalize(&variant.attrs, variant.span())?;
+                    Self::check_signals(&info, &dbs, variant.span())?;
+                    info.derives =
+                        parse_path_list_attr(&variant.attrs, "dbc_derive")?;
+                    (info.decode, info.encode) =
+                        Self::parse_mode(&variant.attrs, variant.span())?;
+                    info.cfg = parse_meta_attr(&variant.attrs, "dbc_cfg")?;
+                    messages.insert(info.ident.to_string(), info);
+                }
+            }
+            Data::Union(data) => {
+                return Err(syn::Error::new_spanned(
+                    data.union_token,
+                    "unions are not supported",
+                ))
+            }
+        }
+        let span = input.ident.span();
+        if let Some(node) = parse_attr(&input.attrs, "dbc_tx_node") {
+            let found = Self::add_messages(
+                &mut messages,
+                &dbs[..struct_dbs],
+                conflict,
+                rename,
+                span,
+                |dbc| MessageInfo::transmitted_by(dbc, &node),
+            )?;
+            if !found {
+                return Err(syn::Error::new(
+                    span,
+                    format!("No messages are transmitted by node {node}"),
+                ));
+            }
+        }
+        if let Some(range) = parse_attr(&input.attrs, "dbc_id_range") {
+            let (first, last) = parse_range(&range).ok_or_else(|| {
+                syn::Error::new(
+                    span,
+                    format!(
+                        "Invalid #[dbc_id_range] {range}; expected e.g. \
+                         \"0x300..=0x30F\""
+                    ),
+                )
+            })?;
+            let found = Self::add_messages(
+                &mut messages,
+                &dbs[..struct_dbs],
+                conflict,
+                rename,
+                span,
+                |dbc| MessageInfo::in_id_range(dbc, first, last),
+            )?;
+            if !found {
+                return Err(syn::Error::new(
+                    span,
+                    format!("No messages have an ID in {range}"),
+                ));
+            }
+        }
+
+        let recorder =
+            parse_int_attr(&input.attrs, "dbc_recorder")?.map(|n| n as usize);
+        if recorder == Some(0) {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "#[dbc_recorder] must hold at least one frame",
+            ));
+        }
+
+        let max_ram =
+            parse_int_attr(&input.attrs, "dbc_max_ram")?.map(|n| n as usize);
+        if max_ram.is_some() && !input.generics.params.is_empty() {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "#[dbc_max_ram] cannot be used with generic types",
+            ));
+        }
+
+        let compat = has_attr(&input.attrs, "dbc_codegen_compat");
+        if compat && messages.values().any(|m| !(m.decode && m.encode)) {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "#[dbc_mode] cannot be used with #[dbc_codegen_compat]",
+            ));
+        }
+        if compat && messages.values().any(|m| m.cfg.is_some()) {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "#[dbc_cfg] cannot be used with #[dbc_codegen_compat]",
+            ));
+        }
+
+        // C code sees the signals and the `received` flag, in the header
+        if has_attr(&input.attrs, "dbc_repr_c") {
+            for attr in ["dbc_stats", "dbc_timestamp"] {
+                if has_attr(&input.attrs, attr) {
+                    return Err(syn::Error::new(
+                        input.ident.span(),
+                        format!("#[dbc_repr_c] cannot be used with #[{attr}]"),
+                    ));
+                }
+            }
+            if let Some(m) = messages.values().find(|m| m.dirty || m.lazy) {
+                return Err(syn::Error::new(
+                    m.ident.span(),
+                    "#[dbc_repr_c] cannot be used with #[dbc_dirty] or \
+                     #[dbc_lazy]",
+                ));
+            }
+        }
+
+        // lazy messages have no signal fields
+        if let Some(m) = messages
+            .values()
+            .find(|m| m.lazy && (m.atomic || m.normalize.is_some()))
+        {
+            return Err(syn::Error::new(
+                m.ident.span(),
+                "#[dbc_lazy] cannot be used with #[dbc_atomic] or \
+                 #[dbc_normalize]",
+            ));
+        }
+        if let Some(m) =
+            messages.values().find(|m| m.dirty && (m.lazy || !m.encode))
+        {
+            return Err(syn::Error::new(
+                m.ident.span(),
+                "#[dbc_dirty] cannot be used with #[dbc_lazy] or \
+                 #[dbc_mode = \"rx\"]",
+            ));
+        }
+        if let Some(m) = messages
+            .values()
+            .find(|m| m.mux_array && (!m.is_array || m.index_signal.is_none()))
+        {
+            return Err(syn::Error::new(
+                m.ident.span(),
+                "#[dbc_mux_array] needs an array of a message with one \
+                 multiplexor signal",
+            ));
+        }
+        if let Some(m) = messages
+            .values()
+            .find(|m| m.index_signal.is_some() && !m.is_array)
+        {
+            return Err(syn::Error::new(
+                m.ident.span(),
+                "#[dbc_index_signal] needs an array of messages",
+            ));
+        }
+        if messages.values().any(|m| m.lazy) {
+            for attr in [
+                "dbc_codegen_compat",
+                "dbc_debug",
+                "dbc_display",
+                "dbc_reflect",
+                "dbc_selftest",
+            ] {
+                if has_attr(&input.attrs, attr) {
+                    return Err(syn::Error::new(
+                        input.ident.span(),
+                        format!("#[dbc_lazy] cannot be used with #[{attr}]"),
+                    ));
+                }
+            }
+        }
+
+        let skip_impls = parse_path_list_attr(&input.attrs, "dbc_skip_impls")?
+            .unwrap_or_default()
+            .iter()
+            .map(|path| match path.get_ident() {
+                Some(name)
+                    if ["Arbitrary", "Default", "Format", "TryFrom"]
+                        .iter()
+                        .any(|i| name == i) =>
+                {
+                    Ok(name.to_string())
+                }
+                _ => Err(syn::Error::new(
+                    path.span(),
+                    "Unknown #[dbc_skip_impls] trait; expected Arbitrary, \
+                     Default, Format or TryFrom",
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let rounding = match parse_attr(&input.attrs, "dbc_rounding") {
+            None => Rounding::default(),
+            Some(mode) => Rounding::parse(&mode).ok_or_else(|| {
+                syn::Error::new(
+                    input.ident.span(),
+                    format!(
+                        "Invalid #[dbc_rounding] {mode}; expected \"nearest\", \
+                         \"truncate\", \"floor\" or \"ceil\""
+                    ),
+                )
+            })?,
+        };
+
+        let module = parse_attr(&input.attrs, "dbc_module")
+            .map(|name| {
+                parse_str::<Ident>(&name).map_err(|_| {
+                    syn::Error::new(
+                        input.ident.span(),
+                        format!("Invalid #[dbc_module] name {name}"),
+                    )
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            name: &input.ident,
+            generics: &input.generics,
+            storage: matches!(
+                &input.data,
+                Data::Struct(data) if !matches!(data.fields, Fields::Unit)
+            ),
+            dbs,
+            messages,
+            timestamp,
+            manifest,
+            c_header: parse_attr(&input.attrs, "dbc_c_header"),
+            emit: parse_attr(&input.attrs, "dbc_emit"),
+            padding,
+            compat,
+            no_unsafe: has_attr(&input.attrs, "dbc_assert_no_unsafe"),
+            stats: has_attr(&input.attrs, "dbc_stats"),
+            snapshot: has_attr(&input.attrs, "dbc_snapshot"),
+            recorder,
+            max_ram,
+            rx_node: parse_attr(&input.attrs, "dbc_rx_node"),
+            derives: parse_path_list_attr(&input.attrs, "dbc_derive")?
+                .unwrap_or_default(),
+            shared_ids: has_attr(&input.attrs, "dbc_shared_ids"),
+            module,
+            message_modules: has_attr(&input.attrs, "dbc_message_modules"),
+            env_vars: has_attr(&input.attrs, "dbc_env_vars"),
+            attr_enums: has_attr(&input.attrs, "dbc_attr_enums"),
+            debug: has_attr(&input.attrs, "dbc_debug"),
+            selftest: has_attr(&input.attrs, "dbc_selftest"),
+            reflect: has_attr(&input.attrs, "dbc_reflect"),
+            repr_c: has_attr(&input.attrs, "dbc_repr_c"),
+            outlined: has_attr(&input.attrs, "dbc_outline").then(|| {
+                Ident::new(
+                    &format!(
+                        "{}_outline",
+                        naming::snake_case(&input.ident.to_string())
+                    ),
+                    input.ident.span(),
+                )
+            }),
+            skip_impls,
+            rounding,
+            decimal: has_attr(&input.attrs, "dbc_decimal"),
+            display: has_attr(&input.attrs, "dbc_display"),
+            item: true,
+        })
+    }
+
+    /// Generate only the messages, as the deriving item does not exist
+    pub(crate) fn without_item(mut self) -> Self {
+        self.item = false;
+        self
+    }
+
+    /// Add the messages selected from each DBC file, e.g. by
+    /// `#[dbc_tx_node]`, which are not already named by the deriving
+    /// item; they are declared after its own.  Returns whether any
+    /// messages were selected.
+    fn add_messages(
+        messages: &mut BTreeMap<String, MessageInfo>,
+        dbs: &[Rc<Database>],
+        conflict: ConflictPolicy,
+        rename: bool,
+        span: Span,
+        select: impl for<'d> Fn(&'d DBC) -> Vec<&'d str>,
+    ) -> Result<bool> {
+        let mut found = false;
+        for db in dbs {
+            for name in select(&db.dbc) {
+                found = true;
+                if messages.values().any(|m| m.name == name) {
+                    continue;
+                }
+                let mut info = Self::resolve(dbs, conflict, span, |dbc| {
+                    MessageInfo::from_dbc_name(dbc, name, rename)
+                })?;
+                info.position = messages.len();
+                messages.insert(info.ident.to_string(), info);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Add the messages named by `#[dbc_messages]`, for a unit struct
+    /// which generates the message types without holding them
+    fn add_listed_messages(
+        messages: &mut BTreeMap<String, MessageInfo>,
+        attrs: &[Attribute],
+        dbs: &[Rc<Database>],
+        conflict: ConflictPolicy,
+        rename: bool,
+        span: Span,
+    ) -> Result<()> {
+        let list = parse_attr(attrs, "dbc_messages").ok_or_else(|| {
+            syn::Error::new(
+                span,
+                "A unit struct needs #[dbc_messages = \"...\"] naming its \
+                 messages",
+            )
+        })?;
+        for name in list.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let found = Self::add_messages(
+                messages,
+                dbs,
+                conflict,
+                rename,
+                span,
+                |dbc| {
+                    dbc.messages()
+                        .iter()
+                        .map(|m| m.message_name().as_str())
+                        .filter(|m| *m == name)
+                        .collect()
+                },
+            )?;
+            if !found {
+                return Err(syn::Error::new(
+                    span,
+                    format!("Unknown message {name} in #[dbc_messages]"),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether any field of a struct has its own `#[dbc_file]`
+    fn has_field_files(input: &DeriveInput) -> bool {
+        match &input.data {
+            Data::Struct(data) => {
+                data.fields.iter().any(|f| has_attr(&f.attrs, "dbc_file"))
+            }
+            _ => false,
+        }
+    }
+
+    /// The index of the DBC file given by a field's `#[dbc_file]`, if
+    /// any, loading it unless already in use
+    fn field_source(
+        dbs: &mut Vec<Rc<Database>>,
+        attrs: &[Attribute],
+        span: Span,
+    ) -> Result<Option<usize>> {
+        let files = parse_attrs(attrs, "dbc_file")?;
+        let lit = match files.as_slice() {
+            [] => return Ok(None),
+            [file] => file,
+            _ => {
+                return Err(syn::Error::new(
+                    span,
+                    "Only one #[dbc_file] may be given for a field",
+                ))
+            }
+        };
+        let file = lit.value();
+        if let Some(source) = dbs.iter().position(|db| db.file == file) {
+            return Ok(Some(source));
+        }
+        let db = Database::load(&file)
+            .map_err(|e| syn::Error::new(lit.span(), e))?;
+        dbs.push(Rc::new(db));
+        Ok(Some(dbs.len() - 1))
+    }
+
+    /// Find a message in the DBC files, applying the conflict policy
+    /// when it is defined in more than one, or when its ID is used by
+    /// a different message in another file
+    fn resolve(
+        dbs: &[Rc<Database>],
+        conflict: ConflictPolicy,
+        span: Span,
+        find: impl Fn(&DBC) -> Option<MessageInfo>,
+    ) -> Result<MessageInfo> {
+        let mut candidates: Vec<MessageInfo> = dbs
+            .iter()
+            .enumerate()
+            .filter_map(|(source, db)| {
+                let mut info = find(&db.dbc)?;
+                info.source = source;
+                Some(info)
+            })
+            .collect();
+        if candidates.is_empty() {
+            return Err(syn::Error::new(span, "Unknown message"));
+        }
+        let info = match conflict {
+            ConflictPolicy::Last => candidates.pop(),
+            _ => Some(candidates.remove(0)),
+        }
+        .ok_or_else(|| syn::Error::new(span, "Unknown message"))?;
+        if conflict != ConflictPolicy::Error {
+            return Ok(info);
+        }
+        let used = &dbs[info.source].file;
+
+        let mut conflicts = vec![];
+        for other in &candidates {
+            conflicts.push(format!(
+                "message {} is defined in {} and {}; using {used}",
+                info.name, dbs[other.source].file, used
+            ));
+        }
+        for (source, db) in dbs.iter().enumerate() {
+            if source == info.source {
+                continue;
+            }
+            for m in db.dbc.messages() {
+                let (id, extended) = match *m.message_id() {
+                    MessageId::Standard(id) => (u32::from(id), false),
+                    MessageId::Extended(id) => (id, true),
+                };
+                if id == info.id
+                    && extended == info.extended
+                    && &info.name != m.message_name()
+                {
+                    conflicts.push(format!(
+                        "ID {id:#X} is {} in {} and {} in {used}; using {}",
+                        m.message_name(),
+                        db.file,
+                        info.name,
+                        info.name,
+                    ));
+                }
+            }
+        }
+
+        if !conflicts.is_empty() {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "DBC conflict: {}; use #[dbc_conflict = \"first\"] or \
+                     #[dbc_conflict = \"last\"] to choose a definition",
+                    conflicts.join("; ")
+                ),
+            ));
+        }
+        Ok(info)
+    }
+
+    /// Check that no two messages held by a struct share an ID (or,
+    /// for arrays, a range of IDs), as `update()` would only decode the
+    /// first of them; collisions are an error unless allowed with
+    /// `#[dbc_shared_ids]`
+    fn check_ids(&self) -> Result<()> {
+        if self.shared_ids {
+            return Ok(());
+        }
+
+        let mut messages: Vec<&MessageInfo> = self
+            .messages
+            .values()
+            .filter(|m| m.field.is_some() && m.decode)
+            .collect();
+        messages.sort_by_key(|m| m.position);
+
+        let ids = |m: &MessageInfo| {
+            m.id..m.id.saturating_add(m.id_count().unwrap_or(1))
+        };
+        for (n, message) in messages.iter().enumerate() {
+            let range = ids(message);
+            for earlier in &messages[..n] {
+                let other = ids(earlier);
+                if message.extended != earlier.extended
+                    || range.end <= other.start
+                    || other.end <= range.start
+                {
+                    continue;
+                }
+                let id = range.start.max(other.start);
+                return Err(syn::Error::new(
+                    message.ident.span(),
+                    format!(
+                        "{} and {} share ID {id} (0x{id:X}), so update() \
+                         only decodes {}; allow this with #[dbc_shared_ids]",
+                        earlier.ident, message.ident, earlier.ident,
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the generated code, formatted, for inspection
+    fn write_code(&self, file: &str, code: &TokenStream) -> Result<()> {
+        let span = self.name.span();
+        let path = manifest::output_path(file, span)?;
+        let text = prettyplease::unparse(&syn::parse2(code.clone())?);
+        fs::write(&path, text).map_err(|e| {
+            syn::Error::new(
+                span,
+                format!("Could not write {}: {e}", path.display()),
+            )
+        })
+    }
+
+    /// Generate `STORAGE_BYTES` for the deriving item, and the
+    /// compile-time check of `#[dbc_max_ram]`
+    fn gen_storage_bytes(&self) -> TokenStream {
+        let name = self.name;
+        let (impl_generics, ty_generics, where_clause) =
+            self.generics.split_for_impl();
+        let check = if let Some(max) = self.max_ram {
+            let msg = format!("{name} exceeds #[dbc_max_ram = {max}]");
+            quote! {
+                const _: () = assert!(#name::STORAGE_BYTES <= #max, #msg);
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// The number of bytes occupied by the messages
+                pub const STORAGE_BYTES: usize = core::mem::size_of::<Self>();
+            }
+
+            #check
+        }
+    }
+
+    /// Generate the `DBC_VERSION` constant of the deriving item, from
+    /// the `VERSION` of its (first) DBC file
+    fn gen_version(&self) -> TokenStream {
+        let Some(db) = self.dbs.first() else {
+            return quote! {};
+        };
+        let name = self.name;
+        let (impl_generics, ty_generics, where_clause) =
+            self.generics.split_for_impl();
+        let version = &db.dbc.version().0;
+        let doc = format!("The `VERSION` of `{}`", db.file);
+        quote! {
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #impl_generics #name #ty_generics #where_clause {
+                #[doc = #doc]
+                pub const DBC_VERSION: &str = #version;
+            }
+        }
+    }
+
+    /// Generate the `<Name>EnvVar` type and an `ENV_<NAME>` constant of
+    /// the deriving item for each integer or float environment variable
+    /// (`EV_`) of its DBC files
+    fn gen_env_vars(&self) -> TokenStream {
+        use can_dbc::EnvType;
+        let name = self.name;
+        let env_var = self.item_ident("EnvVar");
+        let (impl_generics, ty_generics, where_clause) =
+            self.generics.split_for_impl();
+        let mut consts = TokenStream::new();
+        let mut seen = vec![];
+        for db in &self.dbs {
+            for ev in db.dbc.environment_variables() {
+                let ev_name = ev.env_var_name();
+                if seen.contains(&ev_name) {
+                    continue;
+                }
+                seen.push(ev_name);
+                #[allow(clippy::cast_precision_loss)]
+                let (ty, min, max, initial) = match ev.env_var_type() {
+                    EnvType::EnvTypeFloat => (
+                        quote! {f64},
+                        Literal::f64_unsuffixed(*ev.min() as f64),
+                        Literal::f64_unsuffixed(*ev.max() as f64),
+                        Literal::f64_unsuffixed(*ev.initial_value()),
+                    ),
+                    #[allow(clippy::cast_possible_truncation)]
+                    EnvType::EnvTypeu64 => (
+                        quote! {i64},
+                        Literal::i64_unsuffixed(*ev.min()),
+                        Literal::i64_unsuffixed(*ev.max()),
+                        Literal::i64_unsuffixed(*ev.initial_value() as i64),
+                    ),
+                    EnvType::EnvTypeData => continue,
+                };
+                let c = Ident::new(
+                    &naming::identifier(&format!(
+                        "ENV_{}",
+                        naming::snake_case(ev_name).to_uppercase()
+                    )),
+                    Span::call_site(),
+                );
+                let unit = ev.unit();
+                let id = *ev.ev_id();
+                let doc = format!("Environment variable `{ev_name}`");
+                consts.append_all(quote! {
+                    #[doc = #doc]
+                    pub const #c: #env_var<#ty> = #env_var {
+                        name: #ev_name,
+                        id: #id,
+                        min: #min,
+                        max: #max,
+                        initial: #initial,
+                        unit: #unit,
+                    };
+                });
+            }
+        }
+        quote! {
+            /// An environment variable (`EV_`) of the DBC file
+            #[automatically_derived]
+            #[allow(dead_code)]
+            #[derive(Clone, Copy, Debug, PartialEq)]
+            pub struct #env_var<T> {
+                /// The name of the variable
+                pub name: &'static str,
+                /// The identifier of the variable
+                pub id: i64,
+                /// The minimum value
+                pub min: T,
+                /// The maximum value
+                pub max: T,
+                /// The initial value
+                pub initial: T,
+                /// The unit of the value
+                pub unit: &'static str,
+            }
+
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #impl_generics #name #ty_generics #where_clause {
+                #consts
+            }
+        }
+    }
+
+    /// The enum type and variant names of an `ENUM` attribute value
+    fn attr_enum_idents(name: &str, value: &str) -> (Ident, Ident) {
+        let ident = |name: &str| {
+            Ident::new(
+                &naming::identifier(&naming::pascal_case(name)),
+                Span::call_site(),
+            )
+        };
+        (ident(name), ident(value))
+    }
+
+    /// Generate an enum for each `ENUM` message attribute defined in
+    /// the DBC files, with a variant for each of its values
+    fn gen_attr_enums(&self) -> TokenStream {
+        let mut out = TokenStream::new();
+        let mut seen = vec![];
+        for db in &self.dbs {
+            for (name, values) in MessageInfo::enum_attr_definitions(&db.dbc) {
+                if seen.contains(&name) {
+                    continue;
+                }
+                let (ty, _) = Self::attr_enum_idents(&name, "");
+                let variants =
+                    values.iter().map(|v| Self::attr_enum_idents(&name, v).1);
+                let doc = format!("Values of the `{name}` message attribute");
+                out.append_all(quote! {
+                    #[doc = #doc]
+                    #[automatically_derived]
+                    #[allow(dead_code)]
+                    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+                    pub enum #ty {
+                        #(#variants,)*
+                    }
+                });
+                seen.push(name);
+            }
+        }
+        out
+    }
+
+    /// Generate a constant of a message for each `ENUM` attribute it
+    /// has a value for, either its own or the default
+    fn gen_attr_enum_consts(message: &MessageInfo, dbc: &DBC) -> TokenStream {
+        let mut out = TokenStream::new();
+        for (name, _) in MessageInfo::enum_attr_definitions(dbc) {
+            let Some(value) = message.enum_attr(dbc, &name) else {
+                continue;
+            };
+            let (ty, variant) = Self::attr_enum_idents(&name, &value);
+            let c = Ident::new(
+                &naming::identifier(&naming::snake_case(&name).to_uppercase()),
+                Span::call_site(),
+            );
+            let doc = format!("The `{name}` attribute of the message");
+            out.append_all(quote! {
+                #[doc = #doc]
+                pub const #c: #ty = #ty::#variant;
+            });
+        }
+        out
+    }
+
+    /// Check the field names given by `#[dbc_signals]`, that it is not
+    /// combined with `#[dbc_signals_except]` and that each pattern
+    /// matches a signal
+    fn check_signals(
+        info: &MessageInfo,
+        dbs: &[Rc<Database>],
+        span: Span,
+    ) -> Result<()> {
+        if info.signals_conflict() {
+            return Err(syn::Error::new(
+                span,
+                "Only one of #[dbc_signals] or #[dbc_signals_except] may be \
+                 given",
+            ));
+        }
+        for field in info.signal_fields() {
+            if parse_str::<Ident>(field).is_err() {
+                return Err(syn::Error::new(
+                    span,
+                    format!("Invalid field name {field} in #[dbc_signals]"),
+                ));
+            }
+        }
+        let signals = dbs[info.source]
+            .dbc
+            .messages()
+            .get(info.index)
+            .map_or(&[][..], |m| m.signals().as_slice());
+        if let Some(pattern) = info.unmatched_pattern(signals) {
+            let message = if naming::is_glob(pattern) {
+                format!("No signals of {} match {pattern}", info.name)
+            } else if let Some(name) = naming::closest(
+                pattern,
+                signals.iter().map(|s| s.name().as_str()),
+            ) {
+                format!(
+                    "Unknown signal {pattern} in {}; did you mean {name}?",
+                    info.name
+                )
+            } else {
+                format!("Unknown signal {pattern} in {}", info.name)
+            };
+            return Err(syn::Error::new(span, message));
+        }
+        Ok(())
+    }
+
+    /// Parse the unit given by `#[dbc_normalize(unit = "...")]`
+    fn parse_normalize(
+        attrs: &[Attribute],
+        span: Span,
+    ) -> Result<Option<String>> {
+        let unit = parse_list_attr(attrs, "dbc_normalize", "unit")?;
+        if let Some(unit) = &unit {
+            if !units::is_known(unit) {
+                return Err(syn::Error::new(
+                    span,
+                    format!("Unknown unit {unit} for #[dbc_normalize]"),
+                ));
+            }
+        }
+        Ok(unit)
+    }
+
+    /// Parse `#[dbc_mode]`, returning whether to generate the decoding
+    /// and encoding halves of a message
+    fn parse_mode(attrs: &[Attribute], span: Span) -> Result<(bool, bool)> {
+        match parse_attr(attrs, "dbc_mode").as_deref() {
+            None => Ok((true, true)),
+            Some("rx") => Ok((true, false)),
+            Some("tx") => Ok((false, true)),
+            Some(mode) => Err(syn::Error::new(
+                span,
+                format!(
+                    "Invalid #[dbc_mode] {mode}; expected \"rx\" or \"tx\""
+                ),
+            )),
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn build(self) -> Result<TokenStream> {
+        let mut out = TokenStream::new();
+        let files: Vec<&str> =
+            self.dbs.iter().map(|db| db.file.as_str()).collect();
+        let mut manifest = Manifest::new(&files.join(", "));
+        let mut header = CHeader::new(&files.join(", "));
+        let mut selftests = TokenStream::new();
+
+        for (name, message) in &self.messages {
+            let dbc = self.dbc(message);
+            let m = dbc.messages().get(message.index).ok_or_else(|| {
+                syn::Error::new(
+                    message.ident.span(),
+                    format!("Unknown message {name}"),
+                )
+            })?;
+
+            let mut signals: Vec<Ident> = vec![];
+            let mut docs: Vec<String> = vec![];
+            let mut infos: Vec<SignalInfo> = vec![];
+            let mut initials: Vec<TokenStream> = vec![];
+            let mut starts: Vec<f64> = vec![];
+            let mut timeouts: Vec<Option<f64>> = vec![];
+            let mut values = TokenStream::new();
+            for s in m.signals() {
+                if !message.use_signal(s.name())
+                    || self
+                        .rx_node
+                        .as_ref()
+                        .is_some_and(|node| !s.receivers().contains(node))
+                {
+                    continue;
+                }
+
+                let mut signal = SignalInfo::new(s, message);
+                signal.outlined.clone_from(&self.outlined);
+                signal.rounding = self.rounding;
+                signal.decimal = self.decimal;
+                let size = *m.message_size() as usize;
+                if signal.last_byte() >= size {
+                    return Err(syn::Error::new(
+                        message.ident.span(),
+                        format!(
+                            "Signal {} of {name} extends to byte {}, beyond \
+                             the message size of {size} bytes",
+                            s.name(),
+                            signal.last_byte(),
+                        ),
+                    ));
+                }
+                signals.push(signal.ident.clone());
+
+                // documentation text
+                let endian_string =
+                    if s.byte_order() == &ByteOrder::LittleEndian {
+                        "little-endian"
+                    } else {
+                        "big-endian"
+                    };
+                let scale_string = if signal.is_float() {
+                    &format!(", scale factor {}", s.factor())
+                } else {
+                    ""
+                };
+                let mut doc = format!(
+                    "Wire format: {} bit{} starting at bit {}{} ({})\n",
+                    s.signal_size(),
+                    if s.signal_size() == &1 { "" } else { "s" },
+                    s.start_bit(),
+                    scale_string,
+                    endian_string,
+                );
+                if signal.ident != s.name() {
+                    doc.insert_str(
+                        0,
+                        &format!("DBC signal `{}`\n\n", s.name()),
+                    );
+                }
+
+                // value-table constants
+                if let Some(descs) = MessageInfo::value_descriptions(
+                    dbc,
+                    *m.message_id(),
+                    s.name(),
+                ) {
+                    let mut arms = TokenStream::new();
+                    for desc in descs {
+                        let sanitized: String =
+                            format!("{}_{}", s.name(), desc.b())
+                                .to_uppercase()
+                                .chars()
+                                .filter(|c| c.is_alphanumeric() || c == &'_')
+                                .collect();
+                        let c = Ident::new(
+                            &naming::identifier(&sanitized),
+                            signal.ident.span(),
+                        );
+                        let i = signal.const_ident(*desc.a());
+                        let v = quote! {#i};
+                        let t = signal.ntype.clone();
+                        values.extend(quote! {
+                            pub const #c: #t = #v;
+                        });
+                        let _ = write!(doc, "\n{c} = {v}\n");
+                        let text = desc.b();
+                        arms.extend(quote! {
+                            Self::#c => Some(#text),
+                        });
+                    }
+                    let field = &signal.ident;
+                    let current = if message.lazy {
+                        quote! { self.#field() }
+                    } else {
+                        quote! { self.#field }
+                    };
+                    let name = Ident::new(
+                        &format!(
+                            "{}_name",
+                            field.to_string().trim_end_matches('_')
+                        ),
+                        field.span(),
+                    );
+                    let fn_doc = format!(
+                        "The value-table name of the current `{field}` \
+                         value, if it has one"
+                    );
+                    values.extend(quote! {
+                        #[doc = #fn_doc]
+                        #[allow(non_snake_case, unreachable_patterns)]
+                        pub fn #name(&self) -> Option<&'static str> {
+                            match #current {
+                                #arms
+                                _ => None,
+                            }
+                        }
+                    });
+                }
+
+                let start = MessageInfo::signal_start_value(
+                    dbc,
+                    *m.message_id(),
+                    s.name(),
+                )
+                .unwrap_or(0.0);
+                initials.push(signal.initial_value(start));
+                starts.push(start);
+                timeouts.push(MessageInfo::signal_timeout_value(
+                    dbc,
+                    *m.message_id(),
+                    s.name(),
+                ));
+                infos.push(signal);
+                docs.push(doc);
+            }
+
+            if self.manifest.is_some() {
+                manifest.add_message(message, m, dbc, &infos);
+            }
+            if self.c_header.is_some() {
+                header.add_message(message, m, dbc, &infos, self.storage);
+            }
+
+            let id = message.id;
+            let extended = message.extended;
+
+            let dlc = *m.message_size() as usize;
+            let dlc8 = dlc as u8;
+            let padding = match message.fill_value {
+                Some(p) => u8::try_from(p).map_err(|_| {
+                    syn::Error::new(
+                        message.ident.span(),
+                        format!(
+                            "GenMsgFillValue {p:#X} of {} exceeds 0xFF",
+                            message.name
+                        ),
+                    )
+                })?,
+                None => self.padding,
+            };
+            let ident = &message.ident;
+
+            // build signal decoders and encoders
+            let mut decoders = TokenStream::new();
+            let mut encoders = TokenStream::new();
+            let mut fields = TokenStream::new();
+            let mut initial_fields = TokenStream::new();
+            let mut accessor_fns = TokenStream::new();
+            if message.lazy {
+                let pdu = Self::lazy_pdu(dlc, &infos, &starts);
+                decoders.append_all(quote! { self.pdu.copy_from_slice(pdu); });
+                encoders.append_all(quote! { pdu.copy_from_slice(&self.pdu); });
+                fields.append_all(quote! { pdu: [u8; #dlc], });
+                initial_fields.append_all(quote! { pdu: [#(#pdu),*], });
+                accessor_fns = Self::gen_lazy_accessors(&infos, &docs);
+            } else {
+                // the fields of dirty messages are only set by setters
+                // which mark the signals changed
+                let vis = if message.private || message.dirty {
+                    accessor_fns = Self::gen_accessors(message, &infos, &docs);
+                    quote! {}
+                } else {
+                    quote! { pub }
+                };
+                for (info, doc) in infos.iter().zip(&docs) {
+                    decoders.append_all(info.gen_decoder());
+                    encoders.append_all(info.gen_encoder());
+                    let signal = &info.ident;
+                    let ntype = &info.ntype;
+                    fields.append_all(quote! {
+                        #[doc = #doc]
+                        #vis #signal: #ntype,
+                    });
+                }
+                initial_fields.append_all(quote! {
+                    #(#signals: #initials,)*
+                });
+            }
+            let bits = if cfg!(feature = "debug-bits") {
+                Self::gen_bits(&infos)
+            } else {
+                quote! {}
+            };
+            let mut cycle_time = if let Some(c) = message.cycle_time {
+                quote! {
+                    pub const CYCLE_TIME: usize = #c;
+                }
+            } else {
+                quote! {}
+            };
+            if let Some(c) = message.cycle_time_fast {
+                cycle_time.append_all(quote! {
+                    /// The cycle time in milliseconds while an event is
+                    /// repeated, from `GenMsgCycleTimeFast`
+                    pub const CYCLE_TIME_FAST: usize = #c;
+                });
+            }
+            if let Some(n) = message.repetitions {
+                cycle_time.append_all(quote! {
+                    /// The number of times an event is sent at
+                    /// `CYCLE_TIME_FAST`, from `GenMsgNrOfRepetition`
+                    pub const REPETITIONS: usize = #n;
+                });
+            }
+            if let Some(t) = message.timeout {
+                cycle_time.append_all(quote! {
+                    /// The time in milliseconds after which the message is
+                    /// missing, from `GenMsgTimeoutTime`
+                    pub const TIMEOUT_TIME: usize = #t;
+                });
+            }
+
+            if self.attr_enums {
+                cycle_time.append_all(Self::gen_attr_enum_consts(message, dbc));
+            }
+
+            let cycle_time_doc = if let Some(c) = message.cycle_time {
+                &format!(", cycle time {c}ms")
+            } else {
+                ""
+            };
+            let (rx_time, rx_time_fns) = self.gen_rx_time(message.decode);
+            let (received, received_fns) = self.gen_received();
+            let (stats, stats_fns) = self.gen_stats();
+            let builder_fns = if message.encode {
+                Self::gen_builders(message, &infos)
+            } else {
+                quote! {}
+            };
+            let (dirty, dirty_fns) = if message.dirty {
+                Self::gen_dirty(ident, &infos)
+            } else {
+                (quote! {}, quote! {})
+            };
+            let mut private_initials = TokenStream::new();
+            // and those of a message built by `try_from()`, as if decoded
+            let mut decoded_initials = TokenStream::new();
+            if self.timestamp.is_some() && message.decode {
+                private_initials.append_all(quote! { rx_time: None, });
+                decoded_initials.append_all(quote! { rx_time: None, });
+            }
+            if self.storage {
+                private_initials.append_all(quote! { received: false, });
+                decoded_initials.append_all(quote! { received: true, });
+            }
+            if self.stats {
+                let stats = self.item_ident("MessageStats");
+                private_initials.append_all(quote! { stats: #stats::new(), });
+                decoded_initials.append_all(quote! {
+                    stats: #stats {
+                        decoded: 1,
+                        ..#stats::new()
+                    },
+                });
+            }
+            if message.dirty {
+                // everything is written by the first `encode_dirty()`
+                let set = Self::signal_set_ident(ident);
+                let dirty = quote! {
+                    dirty: #set::EMPTY #(.union(#set::#signals))*,
+                };
+                private_initials.append_all(dirty.clone());
+                decoded_initials.append_all(dirty);
+            }
+            let new_with =
+                Self::gen_new_with(message, &infos, &private_initials);
+            let timeout_fn =
+                Self::gen_timeout_defaults(message, &infos, &timeouts);
+            let (count_decoded, count_length_error) = if self.stats {
+                (
+                    quote! {
+                        self.stats.decoded = self.stats.decoded.saturating_add(1);
+                    },
+                    quote! {
+                        self.stats.length_errors =
+                            self.stats.length_errors.saturating_add(1);
+                    },
+                )
+            } else {
+                (quote! {}, quote! {})
+            };
+            let set_received = if self.storage {
+                quote! { self.received = true; }
+            } else {
+                quote! {}
+            };
+            // lazy messages have no signal fields for the helpers below
+            let eager = !message.lazy;
+            let (signal_set, changes_fn) = if eager {
+                Self::gen_changes(ident, &infos, message.decode)
+            } else {
+                (quote! {}, quote! {})
+            };
+            let normalized = Self::gen_normalized(message, &infos)?;
+            let (csv, json) = if eager {
+                (Self::gen_csv(&infos), Self::gen_json(&infos))
+            } else {
+                (quote! {}, quote! {})
+            };
+            let pages = match mux::pages(dbc, m, &infos) {
+                Some((signal, pages)) => {
+                    mux::gen_pages(signal, &pages, message.lazy)
+                }
+                None => quote! {},
+            };
+            let index_fn = match &message.index_signal {
+                Some(signal) => Self::gen_index_of(message, m, signal)?,
+                None => quote! {},
+            };
+            let reflection = if self.reflect {
+                let signals = reflect::gen_signals(
+                    &infos,
+                    &self.item_ident("SignalDesc"),
+                );
+                let access = reflect::gen_access(&infos, message.dirty);
+                quote! {
+                    #signals
+                    #access
+                }
+            } else {
+                quote! {}
+            };
+            let randomize = if cfg!(feature = "rand_core") && eager {
+                Self::gen_randomize(dbc, message, *m.message_id(), &infos)
+            } else {
+                quote! {}
+            };
+            let log = if cfg!(feature = "std") && message.encode {
+                logs::gen_message_log(dlc)
+            } else {
+                quote! {}
+            };
+            let atomic = if message.atomic {
+                Self::gen_atomic(ident, &infos, message.decode)
+            } else {
+                quote! {}
+            };
+            let guarded = if cfg!(feature = "critical-section") {
+                Self::gen_guarded(ident, message.decode)
+            } else {
+                quote! {}
+            };
+            let debug = if self.debug {
+                Self::gen_debug(ident, &infos)
+            } else {
+                quote! {}
+            };
+            let display = if self.display {
+                Self::gen_display(ident, id, &infos)
+            } else {
+                quote! {}
+            };
+            let arbitrary = if cfg!(feature = "arbitrary")
+                && eager
+                && !self.skips("Arbitrary")
+            {
+                Self::gen_arbitrary(ident, dbc, *m.message_id(), &infos)
+            } else {
+                quote! {}
+            };
+            let format =
+                if cfg!(feature = "defmt") && eager && !self.skips("Format") {
+                    Self::gen_defmt(ident, &infos)
+                } else {
+                    quote! {}
+                };
+            let (decode_fns, try_from) = if message.decode {
+                let fields = if message.lazy {
+                    quote! {
+                        pdu: {
+                            let mut bytes = [0u8; #dlc];
+                            bytes.copy_from_slice(pdu);
+                            bytes
+                        },
+                    }
+                } else {
+                    let values = infos.iter().map(SignalInfo::gen_value);
+                    quote! { #(#signals: #values,)* }
+                };
+                (
+                    quote! {
+                        pub fn decode(&mut self, pdu: &[u8])
+                                      -> bool {
+                            if pdu.len() != #dlc {
+                                #count_length_error
+                                return false
+                            }
+                            #decoders
+                            #set_received
+                            #count_decoded
+                            true
+                        }
+
+                        /// Decode a PDU directly into a new message, or
+                        /// `Err` if its length is invalid
+                        #[allow(clippy::result_unit_err)]
+                        pub fn from_pdu(pdu: &[u8]) -> Result<Self, ()> {
+                            if pdu.len() != #dlc {
+                                return Err(());
+                            }
+                            Ok(Self {
+                                #fields
+                                #decoded_initials
+                            })
+                        }
+                    },
+                    if self.skips("TryFrom") {
+                        quote! {}
+                    } else {
+                        quote! {
+                            impl TryFrom<&[u8]> for #ident {
+                                type Error = ();
+                                fn try_from(pdu: &[u8]) -> Result<Self, Self::Error> {
+                                    Self::from_pdu(pdu)
+                                }
+                            }
+                        }
+                    },
+                )
+            } else {
+                (quote! {}, quote! {})
+            };
+            let encode_fns = if message.encode {
+                quote! {
+                    pub fn encode(&self, pdu: &mut [u8])
+                                  -> bool {
+                        if pdu.len() != #dlc {
+                            return false
+                        }
+                        #encoders
+                        true
+                    }
+
+                    /// Fill the PDU with `PADDING` and then encode the
+                    /// signals, so that unused bits hold the fill value
+                    pub fn encode_clean(&self, pdu: &mut [u8])
+                                        -> bool {
+                        if pdu.len() != #dlc {
+                            return false
+                        }
+                        pdu.fill(Self::PADDING);
+                        self.encode(pdu)
+                    }
+                }
+            } else {
+                quote! {}
+            };
+            // snapshots are decoded from the stored PDU
+            let shared = if self.snapshot && message.decode {
+                Self::gen_shared(ident, dlc)
+            } else {
+                quote! {}
+            };
+            let compat = if self.compat {
+                compat::gen_message(
+                    message,
+                    &infos,
+                    &self.item_ident("CanError"),
+                )
+            } else {
+                quote! {}
+            };
+
+            let doc = format!(
+                "{} ID {} (0x{:X}){}",
+                if extended { "Extended" } else { "Standard" },
+                id,
+                id,
+                cycle_time_doc,
+            );
+
+            let layout = Self::layout_doc(dlc, &infos);
+            let repr = if self.repr_c {
+                quote! { #[repr(C)] }
+            } else {
+                quote! {}
+            };
+
+            let default = if self.skips("Default") {
+                quote! {}
+            } else {
+                quote! {
+                    #[automatically_derived]
+                    impl Default for #ident {
+                        fn default() -> Self {
+                            Self::new()
+                        }
+                    }
+                }
+            };
+
+            let derives = message.derives.as_ref().unwrap_or(&self.derives);
+            // proptest values must implement `Debug`
+            let strategy = if cfg!(feature = "proptest")
+                && eager
+                && (self.debug
+                    || derives.iter().any(|d| {
+                        d.segments.last().is_some_and(|s| s.ident == "Debug")
+                    })) {
+                Self::gen_strategy(dbc, *m.message_id(), &infos)
+            } else {
+                quote! {}
+            };
+            let derives = if derives.is_empty() {
+                quote! {}
+            } else {
+                quote! { #[derive(#(#derives),*)] }
+            };
+
+            let items = quote! {
+                #[automatically_derived]
+                #[allow(non_snake_case)]
+                #[allow(non_camel_case_types)]
+                #derives
+                #[doc = #doc]
+                #[doc = #layout]
+                #repr
+                pub struct #ident {
+                    #fields
+                    #rx_time
+                    #received
+                    #stats
+                    #dirty
+                }
+
+                impl #ident {
+                    pub const ID: u32 = #id;
+                    pub const DLC: u8 = #dlc8;
+                    pub const EXTENDED: bool = #extended;
+                    pub const PADDING: u8 = #padding;
+                    /// The number of bytes occupied by the message
+                    pub const STORAGE_BYTES: usize =
+                        core::mem::size_of::<Self>();
+                    #cycle_time
+                    #values
+                    #pages
+                    #index_fn
+                    #reflection
+
+                    /// A message with each signal at its start value, from
+                    /// the `GenSigStartValue` attribute, or zero
+                    pub const fn new() -> Self {
+                        Self {
+                            #initial_fields
+                            #private_initials
+                        }
+                    }
+
+                    #new_with
+
+                    #decode_fns
+                    #encode_fns
+                    #accessor_fns
+                    #builder_fns
+
+                    #changes_fn
+                    #csv
+                    #json
+                    #log
+                    #strategy
+                    #randomize
+                    #rx_time_fns
+                    #timeout_fn
+                    #received_fns
+                    #stats_fns
+                    #dirty_fns
+                    #normalized
+                    #bits
+                }
+
+                #default
+
+                #signal_set
+                #compat
+                #shared
+                #atomic
+                #guarded
+                #debug
+                #display
+                #format
+                #arbitrary
+                #try_from
+            };
+            let items = if let Some(cfg) = &message.cfg {
+                let mut gated = Self::gen_cfg(cfg, items)?;
+                gated.append_all(
+                    self.gen_cfg_stub(message, cfg, &derives, &doc),
+                );
+                gated
+            } else {
+                items
+            };
+            if self.selftest && message.decode && message.encode {
+                let test = Self::gen_selftest(message, dlc, &infos);
+                selftests.append_all(match &message.cfg {
+                    Some(cfg) => quote! { #[cfg(#cfg)] #test },
+                    None => test,
+                });
+            }
+            if message.external {
+                // the type is generated by another derive
+            } else if self.message_modules {
+                let module = Ident::new(
+                    &naming::identifier(&naming::snake_case(name)),
+                    ident.span(),
+                );
+                let module_doc = format!("The [`{ident}`] message");
+                out.append_all(quote! {
+                    #[doc = #module_doc]
+                    pub mod #module {
+                        #[allow(unused_imports)]
+                        use super::*;
+
+                        #items
+                    }
+                    pub use #module::#ident;
+                });
+            } else {
+                out.append_all(items);
+            }
+        }
+
+        if self.stats {
+            out.append_all(self.gen_stats_type());
+        }
+        if self.reflect {
+            out.append_all(reflect::gen_signal_desc(
+                &self.item_ident("SignalDesc"),
+            ));
+        }
+        if let Some(module) = &self.outlined {
+            out.append_all(signal::gen_outlined_helpers(module));
+        }
+        if self.selftest {
+            let module = Ident::new(
+                &format!(
+                    "{}_selftest",
+                    naming::snake_case(&self.name.to_string())
+                ),
+                self.name.span(),
+            );
+            out.append_all(quote! {
+                #[cfg(test)]
+                #[allow(non_snake_case, clippy::float_cmp)]
+                mod #module {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    #selftests
+                }
+            });
+        }
+        if let Some(capacity) = self.recorder {
+            let mut messages: Vec<&MessageInfo> =
+                self.messages.values().collect();
+            messages.sort_by_key(|m| m.position);
+            let max_dlc = messages
+                .iter()
+                .filter_map(|m| self.dbc(m).messages().get(m.index))
+                .map(|m| *m.message_size() as usize)
+                .max()
+                .unwrap_or(0);
+            let timestamp =
+                self.timestamp.clone().unwrap_or_else(|| parse_quote!(u32));
+            out.append_all(recorder::gen_recorder(
+                self.name, &messages, capacity, max_dlc, &timestamp,
+            ));
+        }
+        if self.item {
+            out.append_all(self.gen_storage_bytes());
+            out.append_all(self.gen_version());
+            if self.env_vars {
+                out.append_all(self.gen_env_vars());
+            }
+        }
+        if self.attr_enums {
+            out.append_all(self.gen_attr_enums());
+        }
+        if self.storage {
+            self.check_ids()?;
+            out.append_all(self.gen_dispatcher());
+            if cfg!(feature = "std") {
+                out.append_all(logs::gen_dispatch_log(
+                    self.name,
+                    self.generics,
+                ));
+            }
+            out.append_all(self.gen_message_set());
+            out.append_all(self.gen_tx_schedule());
+        }
+        if self.compat {
+            let mut messages: Vec<&MessageInfo> =
+                self.messages.values().collect();
+            messages.sort_by_key(|m| m.position);
+            out.append_all(compat::gen_messages(
+                &messages,
+                &self.item_ident("Messages"),
+                &self.item_ident("CanError"),
+            ));
+        }
+
+        for db in &self.dbs {
+            out.append_all(db.track());
+        }
+
+        if let Some(file) = &self.manifest {
+            manifest.write(file, self.name.span())?;
+        }
+        if let Some(file) = &self.c_header {
+            header.write(file, self.name.span())?;
+        }
+        if let Some(module) = &self.module {
+            let doc = format!("Items generated for [`{}`]", self.name);
+            out = quote! {
+                #[doc = #doc]
+                pub mod #module {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    #out
+                }
+            };
+        }
+        if self.no_unsafe && Self::contains_unsafe(out.clone()) {
+            return Err(syn::Error::new(
+                self.name.span(),
+                "Generated code contains `unsafe`, which \
+                 #[dbc_assert_no_unsafe] forbids",
+            ));
+        }
+        if let Some(file) = &self.emit {
+            self.write_code(file, &out)?;
+        }
+        Ok(out)
+    }
+
+    /// Whether a token stream contains the `unsafe` keyword
+    fn contains_unsafe(tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == "unsafe",
+            TokenTree::Group(group) => Self::contains_unsafe(group.stream()),
+            _ => false,
+        })
+    }
+
+    /// The DBC which a message is defined in
+    fn dbc(&self, message: &MessageInfo) -> &DBC {
+        &self.dbs[message.source].dbc
+    }
+
+    /// Whether `#[dbc_skip_impls]` omits a trait implementation
+    fn skips(&self, name: &str) -> bool {
+        self.skip_impls.iter().any(|i| i == name)
+    }
+
+    /// Gate each item generated for a message on its `#[dbc_cfg]`
+    /// predicate
+    fn gen_cfg(cfg: &Meta, items: TokenStream) -> Result<TokenStream> {
+        let items = syn::parse2::<syn::File>(items)?.items;
+        Ok(quote! {
+            #(
+                #[cfg(#cfg)]
+                #items
+            )*
+        })
+    }
+
+    /// Generate the empty stand-in for a message whose `#[dbc_cfg]`
+    /// predicate is false, so that a struct holding it is unchanged
+    /// while its signals and functions cost nothing
+    fn gen_cfg_stub(
+        &self,
+        message: &MessageInfo,
+        cfg: &Meta,
+        derives: &TokenStream,
+        doc: &str,
+    ) -> TokenStream {
+        let ident = &message.ident;
+        let id = message.id;
+        let extended = message.extended;
+        let decode_fn = if message.decode {
+            quote! {
+                /// Never decodes, as the message is not enabled
+                pub fn decode(&mut self, _pdu: &[u8]) -> bool {
+                    false
+                }
+
+                /// Never decodes, as the message is not enabled
+                #[allow(clippy::result_unit_err)]
+                pub fn from_pdu(_pdu: &[u8]) -> Result<Self, ()> {
+                    Err(())
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let encode_fns = if message.encode {
+            quote! {
+                /// Never encodes, as the message is not enabled
+                pub fn encode(&self, _pdu: &mut [u8]) -> bool {
+                    false
+                }
+
+                /// Never encodes, as the message is not enabled
+                pub fn encode_clean(&self, _pdu: &mut [u8]) -> bool {
+                    false
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let default = if self.skips("Default") {
+            quote! {}
+        } else {
+            quote! {
+                #[cfg(not(#cfg))]
+                #[automatically_derived]
+                impl Default for #ident {
+                    fn default() -> Self {
+                        Self::new()
+                    }
+                }
+            }
+        };
+        let received_fn = if self.storage {
+            quote! {
+                /// Never received, as the message is not enabled
+                pub fn received(&self) -> bool {
+                    false
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #[cfg(not(#cfg))]
+            #[automatically_derived]
+            #[allow(dead_code)]
+            #derives
+            #[doc = #doc]
+            #[doc = ""]
+            #[doc = "Not enabled by its `#[dbc_cfg]`, so holds no signals"]
+            pub struct #ident {}
+
+            #[cfg(not(#cfg))]
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #ident {
+                pub const ID: u32 = #id;
+                pub const EXTENDED: bool = #extended;
+                /// The number of bytes occupied by the message
+                pub const STORAGE_BYTES: usize = core::mem::size_of::<Self>();
+
+                pub const fn new() -> Self {
+                    Self {}
+                }
+
+                #decode_fn
+                #encode_fns
+                #received_fn
+            }
+
+            #default
+        }
+    }
+
+    /// Generate the reception-time field and accessors for a message,
+    /// when a timestamp type has been given and the message decodes
+    fn gen_rx_time(&self, decode: bool) -> (TokenStream, TokenStream) {
+        let Some(ts) = self.timestamp.as_ref().filter(|_| decode) else {
+            return (quote! {}, quote! {});
+        };
+
+        let field = quote! {
+            rx_time: Option<#ts>,
+        };
+        let fns = quote! {
+            /// Decode the PDU and, if successful, record `now` as the
+            /// time of reception
+            pub fn decode_at(&mut self, pdu: &[u8], now: #ts) -> bool {
+                if self.decode(pdu) {
+                    self.rx_time = Some(now);
+                    true
+                } else {
+                    false
+                }
+            }
+
+            /// The time of the last successful `decode_at()`, if any
+            pub fn rx_time(&self) -> Option<#ts> {
+                self.rx_time
+            }
+
+            /// Whether more than `timeout` has elapsed since the
+            /// message was last received (or it never was); the
+            /// timestamp is allowed to wrap
+            pub fn is_stale(&self, now: #ts, timeout: #ts) -> bool {
+                match self.rx_time {
+                    Some(t) => now.wrapping_sub(t) > timeout,
+                    None => true,
+                }
+            }
+        };
+        (field, fns)
+    }
+
+    /// Generate the flag recording whether a message has been
+    /// received, for messages held in a struct
+    fn gen_received(&self) -> (TokenStream, TokenStream) {
+        if !self.storage {
+            return (quote! {}, quote! {});
+        }
+
+        let field = quote! {
+            received: bool,
+        };
+        let fns = quote! {
+            /// Whether the message has been successfully decoded at
+            /// least once
+            pub fn received(&self) -> bool {
+                self.received
+            }
+        };
+        (field, fns)
+    }
+
+    /// Generate the accessors of a lazy message, decoding each signal
+    /// from the stored PDU, and setters encoding those which can be
+    fn gen_lazy_accessors(
+        infos: &[SignalInfo],
+        docs: &[String],
+    ) -> TokenStream {
+        let mut fns = TokenStream::new();
+        for (info, doc) in infos.iter().zip(docs) {
+            let signal = &info.ident;
+            let ntype = &info.ntype;
+            let value = info.gen_value();
+            fns.append_all(quote! {
+                #[doc = #doc]
+                #[allow(non_snake_case)]
+                pub fn #signal(&self) -> #ntype {
+                    let pdu = &self.pdu;
+                    let value = #value;
+                    value
+                }
+            });
+            if !info.can_encode() {
+                continue;
+            }
+            let setter = Ident::new(&format!("set_{signal}"), signal.span());
+            let encode = info.gen_encode(&quote! { value });
+            let setter_doc = format!("Encode `{signal}` into the stored PDU");
+            fns.append_all(quote! {
+                #[doc = #setter_doc]
+                #[allow(non_snake_case)]
+                pub fn #setter(&mut self, value: #ntype) {
+                    let pdu = &mut self.pdu;
+                    #encode
+                }
+            });
+        }
+        fns
+    }
+
+    /// Generate the getters of a message with private fields, and its
+    /// setters unless `gen_dirty()` provides them
+    fn gen_accessors(
+        message: &MessageInfo,
+        infos: &[SignalInfo],
+        docs: &[String],
+    ) -> TokenStream {
+        let mut fns = TokenStream::new();
+        for (info, doc) in infos.iter().zip(docs) {
+            let signal = &info.ident;
+            let ntype = &info.ntype;
+            fns.append_all(quote! {
+                #[doc = #doc]
+                #[allow(non_snake_case)]
+                pub const fn #signal(&self) -> #ntype {
+                    self.#signal
+                }
+            });
+            if message.dirty {
+                continue;
+            }
+            let setter = Ident::new(&format!("set_{signal}"), signal.span());
+            let doc = format!("Set `{signal}`");
+            fns.append_all(quote! {
+                #[doc = #doc]
+                #[allow(non_snake_case)]
+                pub fn #setter(&mut self, value: #ntype) {
+                    self.#signal = value;
+                }
+            });
+        }
+        fns
+    }
+
+    /// Generate `with_<signal>()` of an encoded message, returning it
+    /// with a signal set, so messages are built in one expression
+    fn gen_builders(
+        message: &MessageInfo,
+        infos: &[SignalInfo],
+    ) -> TokenStream {
+        let mut fns = TokenStream::new();
+        for info in infos {
+            if message.lazy && !info.can_encode() {
+                continue;
+            }
+            let signal = &info.ident;
+            let ntype = &info.ntype;
+            let name = Ident::new(&format!("with_{signal}"), signal.span());
+            let doc = format!("The message with `{signal}` set to `value`");
+            let f = if message.lazy || message.dirty {
+                let setter =
+                    Ident::new(&format!("set_{signal}"), signal.span());
+                quote! {
+                    pub fn #name(mut self, value: #ntype) -> Self {
+                        self.#setter(value);
+                        self
+                    }
+                }
+            } else {
+                quote! {
+                    pub const fn #name(mut self, value: #ntype) -> Self {
+                        self.#signal = value;
+                        self
+                    }
+                }
+            };
+            fns.append_all(quote! {
+                #[doc = #doc]
+                #[allow(non_snake_case)]
+                #[must_use]
+                #f
+            });
+        }
+        fns
+    }
+
+    /// Generate `new_with()`, taking a value for each signal so that
+    /// none can be left unset; lazy messages need every signal to be
+    /// encoded
+    fn gen_new_with(
+        message: &MessageInfo,
+        infos: &[SignalInfo],
+        private_initials: &TokenStream,
+    ) -> TokenStream {
+        if infos.is_empty()
+            || message.lazy && !infos.iter().all(SignalInfo::can_encode)
+        {
+            return quote! {};
+        }
+        let signals: Vec<_> = infos.iter().map(|i| &i.ident).collect();
+        let ntypes = infos.iter().map(|i| &i.ntype);
+        let attrs = quote! {
+            /// A message with the value of each signal, in DBC order
+            #[allow(non_snake_case, clippy::too_many_arguments)]
+        };
+        if message.lazy {
+            let setters = signals
+                .iter()
+                .map(|s| Ident::new(&format!("set_{s}"), s.span()));
+            quote! {
+                #attrs
+                pub fn new_with(#(#signals: #ntypes),*) -> Self {
+                    let mut message = Self::new();
+                    #(message.#setters(#signals);)*
+                    message
+                }
+            }
+        } else {
+            quote! {
+                #attrs
+                pub const fn new_with(#(#signals: #ntypes),*) -> Self {
+                    Self {
+                        #(#signals,)*
+                        #private_initials
+                    }
+                }
+            }
+        }
+    }
+
+    /// Generate `apply_timeout_defaults()` of a message with signals
+    /// which have a `GenSigTimeoutValue`, setting them to that value
+    fn gen_timeout_defaults(
+        message: &MessageInfo,
+        infos: &[SignalInfo],
+        timeouts: &[Option<f64>],
+    ) -> TokenStream {
+        let mut sets = TokenStream::new();
+        for (info, timeout) in infos.iter().zip(timeouts) {
+            let Some(raw) = timeout else {
+                continue;
+            };
+            let signal = &info.ident;
+            let value = info.initial_value(*raw);
+            if message.lazy || message.dirty {
+                if !info.can_encode() {
+                    continue;
+                }
+                let setter =
+                    Ident::new(&format!("set_{signal}"), signal.span());
+                sets.append_all(quote! { self.#setter(#value); });
+            } else {
+                sets.append_all(quote! { self.#signal = #value; });
+            }
+        }
+        if sets.is_empty() {
+            return quote! {};
+        }
+        quote! {
+            /// Set each signal with a `GenSigTimeoutValue` attribute to
+            /// that value, for when the message has not been received
+            /// within its timeout
+            pub fn apply_timeout_defaults(&mut self) {
+                #sets
+            }
+        }
+    }
+
+    /// Generate `index_of()` of a message held in an array indexed by
+    /// one of its signals, giving the element of a PDU
+    fn gen_index_of(
+        message: &MessageInfo,
+        m: &Message,
+        name: &str,
+    ) -> Result<TokenStream> {
+        let info = m
+            .signals()
+            .iter()
+            .find(|s| s.name() == name)
+            .map(|s| SignalInfo::new(s, message))
+            .filter(|info| info.width > 1 && !info.is_float())
+            .ok_or_else(|| {
+                syn::Error::new(
+                    message.ident.span(),
+                    format!(
+                        "{} has no unscaled integer signal {name} to index \
+                         its array",
+                        message.ident
+                    ),
+                )
+            })?;
+        let dlc = *m.message_size() as usize;
+        let value = info.gen_value();
+        let doc = format!(
+            "The index of the array element holding a PDU, given by its \
+             `{name}` signal"
+        );
+        Ok(quote! {
+            #[doc = #doc]
+            pub fn index_of(pdu: &[u8]) -> Option<usize> {
+                if pdu.len() != #dlc {
+                    return None;
+                }
+                let value = #value;
+                usize::try_from(value).ok()
+            }
+        })
+    }
+
+    /// The initial PDU of a lazy message, holding each signal's raw
+    /// start value
+    fn lazy_pdu(dlc: usize, infos: &[SignalInfo], starts: &[f64]) -> Vec<u8> {
+        let mut pdu = vec![0u8; dlc];
+        for (info, start) in infos.iter().zip(starts) {
+            // negative values are stored in two's complement
+            let raw = *start as i128 as u64;
+            let le = info.signal.byte_order() == &ByteOrder::LittleEndian;
+            for (n, bit) in info.bit_positions().into_iter().enumerate() {
+                let shift = if le { n } else { info.width - 1 - n };
+                if (raw >> shift) & 1 != 0 {
+                    pdu[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+        }
+        pdu
+    }
+
+    /// Generate the set of changed signals of a message, setters which
+    /// add to it, and `encode_dirty()` which encodes only those signals
+    fn gen_dirty(
+        ident: &Ident,
+        infos: &[SignalInfo],
+    ) -> (TokenStream, TokenStream) {
+        let set = Self::signal_set_ident(ident);
+        let field = quote! {
+            dirty: #set,
+        };
+        let mut fns = TokenStream::new();
+        let mut encoders = TokenStream::new();
+        for info in infos {
+            let signal = &info.ident;
+            let ntype = &info.ntype;
+            let setter = Ident::new(&format!("set_{signal}"), signal.span());
+            let doc = format!(
+                "Set `{signal}`, marking it to be written by `encode_dirty()`"
+            );
+            fns.append_all(quote! {
+                #[doc = #doc]
+                #[allow(non_snake_case)]
+                pub fn #setter(&mut self, value: #ntype) {
+                    self.#signal = value;
+                    self.dirty |= #set::#signal;
+                }
+            });
+            let encoder = info.gen_encoder();
+            encoders.append_all(quote! {
+                if self.dirty.contains(#set::#signal) {
+                    #encoder
+                }
+            });
+        }
+        fns.append_all(quote! {
+            /// The signals set since the last `encode_dirty()`
+            pub fn dirty(&self) -> #set {
+                self.dirty
+            }
+
+            /// Encode only the signals set since the last call into
+            /// `pdu`, which holds the previously encoded message, and
+            /// clear the set; all signals are written by the first call
+            pub fn encode_dirty(&mut self, pdu: &mut [u8]) -> bool {
+                if pdu.len() != Self::DLC as usize {
+                    return false;
+                }
+                #encoders
+                self.dirty = #set::EMPTY;
+                true
+            }
+        });
+        (field, fns)
+    }
+
+    /// Generate the accessors converting signals to the unit given by
+    /// `#[dbc_normalize]`
+    fn gen_normalized(
+        message: &MessageInfo,
+        infos: &[SignalInfo],
+    ) -> Result<TokenStream> {
+        let Some(unit) = &message.normalize else {
+            return Ok(quote! {});
+        };
+
+        let mut out = TokenStream::new();
+        for info in infos.iter().filter(|i| i.width > 1) {
+            let from = info.signal.unit();
+            let Some((scale, offset)) = units::conversion(from, unit) else {
+                continue;
+            };
+            let field = &info.ident;
+            let name = Ident::new(&format!("{field}_normalized"), field.span());
+            let scale = Literal::f32_unsuffixed(scale as f32);
+            let offset = Literal::f32_unsuffixed(offset as f32);
+            let doc = format!("`{field}` converted from {from} to {unit}");
+            out.append_all(quote! {
+                #[doc = #doc]
+                #[allow(non_snake_case)]
+                pub fn #name(&self) -> f32 {
+                    self.#field as f32 * #scale + #offset
+                }
+            });
+        }
+        if out.is_empty() {
+            return Err(syn::Error::new(
+                message.ident.span(),
+                format!("No signals of {} convert to {unit}", message.ident),
+            ));
+        }
+        Ok(out)
+    }
+
+    /// Generate the `<Message>Atomic` type, holding each signal of up
+    /// to 32 bits in a `portable_atomic` type so it can be accessed
+    /// from other contexts without a critical section
+    #[allow(clippy::too_many_lines)]
+    fn gen_atomic(
+        ident: &Ident,
+        infos: &[SignalInfo],
+        decode: bool,
+    ) -> TokenStream {
+        let atomic = Ident::new(&format!("{ident}Atomic"), ident.span());
+        let mut fields = TokenStream::new();
+        let mut inits = TokenStream::new();
+        let mut accessors = TokenStream::new();
+        let mut stores = TokenStream::new();
+        let mut loads = TokenStream::new();
+        for info in infos.iter().filter(|i| i.nwidth <= 32) {
+            let field = &info.ident;
+            let ntype = &info.ntype;
+            let setter = Ident::new(&format!("set_{field}"), field.span());
+            // floats are held as their bits
+            let (atype, load, store) = if info.is_float() {
+                (
+                    quote! { AtomicU32 },
+                    quote! { f32::from_bits(self.#field.load(Ordering::Relaxed)) },
+                    quote! { self.#field.store(value.to_bits(), Ordering::Relaxed) },
+                )
+            } else {
+                let name = ntype.to_string();
+                let atype = if name == "bool" {
+                    Ident::new("AtomicBool", field.span())
+                } else {
+                    Ident::new(
+                        &format!("Atomic{}", name.to_uppercase()),
+                        field.span(),
+                    )
+                };
+                (
+                    quote! { #atype },
+                    quote! { self.#field.load(Ordering::Relaxed) },
+                    quote! { self.#field.store(value, Ordering::Relaxed) },
+                )
+            };
+            let zero = if info.width == 1 {
+                quote! { false }
+            } else {
+                quote! { 0 }
+            };
+            fields.append_all(quote! {
+                #field: portable_atomic::#atype,
+            });
+            inits.append_all(quote! {
+                #field: portable_atomic::#atype::new(#zero),
+            });
+            accessors.append_all(quote! {
+                #[allow(non_snake_case)]
+                pub fn #field(&self) -> #ntype {
+                    use portable_atomic::Ordering;
+                    #load
+                }
+
+                #[allow(non_snake_case)]
+                pub fn #setter(&self, value: #ntype) {
+                    use portable_atomic::Ordering;
+                    #store
+                }
+            });
+            stores.append_all(quote! {
+                self.#setter(message.#field);
+            });
+            loads.append_all(quote! {
+                message.#field = self.#field();
+            });
+        }
+
+        let doc = format!(
+            "The signals of [`{ident}`] of up to 32 bits, held in atomics \
+             which may be read and written from any context; each access \
+             is independent, so signals are not updated together"
+        );
+        let decode_fn = if decode {
+            quote! {
+                /// Decode a PDU and store its signals, returning
+                /// `false` if its length is invalid
+                pub fn decode(&self, pdu: &[u8]) -> bool {
+                    let mut message = #ident::new();
+                    if !message.decode(pdu) {
+                        return false;
+                    }
+                    self.store(&message);
+                    true
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #[automatically_derived]
+            #[allow(non_snake_case)]
+            #[allow(non_camel_case_types)]
+            #[allow(dead_code)]
+            #[doc = #doc]
+            pub struct #atomic {
+                #fields
+            }
+
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #atomic {
+                /// Signals which are all zero
+                pub const fn new() -> Self {
+                    Self {
+                        #inits
+                    }
+                }
+
+                #accessors
+
+                /// Store the signals of a message
+                pub fn store(&self, message: &#ident) {
+                    #stores
+                }
+
+                #decode_fn
+
+                /// A message holding the current value of each signal
+                pub fn load(&self) -> #ident {
+                    let mut message = #ident::new();
+                    #loads
+                    message
+                }
+            }
+
+            #[automatically_derived]
+            impl Default for #atomic {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        }
+    }
+
+    /// Generate a `Debug` implementation showing each signal's name,
+    /// value and unit, and its raw value in hexadecimal
+    fn gen_debug(ident: &Ident, infos: &[SignalInfo]) -> TokenStream {
+        let mut fields = TokenStream::new();
+        for (n, info) in infos.iter().enumerate() {
+            let s = info.signal;
+            let signal = &info.ident;
+            let utype = &info.utype;
+            let unit = if s.unit().is_empty() {
+                String::new()
+            } else {
+                format!(" {}", s.unit().replace('{', "{{").replace('}', "}}"))
+            };
+            // "0x" and a digit for each nibble of the signal
+            let digits = info.width.div_ceil(4) + 2;
+            let format = format!(
+                "{} {}: {{}}{unit} (raw {{:#0{digits}x}})",
+                if n == 0 { "" } else { "," },
+                s.name()
+            );
+            let raw = if info.width == 1 {
+                quote! { u8::from(self.#signal) }
+            } else if info.is_float() {
+                info.gen_raw(&quote! { self.#signal })
+            } else {
+                quote! { self.#signal as #utype }
+            };
+            let raw = if info.width < 64 && info.width > 1 {
+                let mask = Literal::u64_unsuffixed((1 << info.width) - 1);
+                quote! { (#raw) & #mask }
+            } else {
+                raw
+            };
+            fields.append_all(quote! {
+                ::core::write!(f, #format, self.#signal, #raw)?;
+            });
+        }
+        let open = format!("{ident} {{");
+        let close = if infos.is_empty() { "}" } else { " }" };
+        quote! {
+            #[automatically_derived]
+            impl core::fmt::Debug for #ident {
+                fn fmt(
+                    &self,
+                    f: &mut core::fmt::Formatter<'_>,
+                ) -> core::fmt::Result {
+                    f.write_str(#open)?;
+                    #fields
+                    f.write_str(#close)
+                }
+            }
+        }
+    }
+
+    /// Generate a `Display` implementation showing the message ID and
+    /// each signal's value on one line, e.g. `Message{id=0x3FF, A=1}`
+    fn gen_display(
+        ident: &Ident,
+        id: u32,
+        infos: &[SignalInfo],
+    ) -> TokenStream {
+        let signals = infos.iter().map(|i| &i.ident);
+        let mut format = format!("{ident}{{{{id={id:#X}");
+        for info in infos {
+            let _ = write!(format, ", {}={{}}", info.signal.name());
+        }
+        format.push_str("}}");
+        quote! {
+            #[automatically_derived]
+            impl core::fmt::Display for #ident {
+                fn fmt(
+                    &self,
+                    f: &mut core::fmt::Formatter<'_>,
+                ) -> core::fmt::Result {
+                    ::core::write!(f, #format #(, self.#signals)*)
+                }
+            }
+        }
+    }
+
+    /// Generate an `arbitrary::Arbitrary` implementation, choosing each
+    /// signal's value from its value table, if it has one, or else its
+    /// raw range as limited by its DBC minimum and maximum
+    fn gen_arbitrary(
+        ident: &Ident,
+        dbc: &DBC,
+        id: MessageId,
+        infos: &[SignalInfo],
+    ) -> TokenStream {
+        let mut fields = TokenStream::new();
+        for info in infos {
+            let s = info.signal;
+            let signal = &info.ident;
+            let ntype = &info.ntype;
+            let scale = info.scale;
+            let offset = *s.offset() as f32;
+            let descs = MessageInfo::value_descriptions(dbc, id, s.name())
+                .unwrap_or_default();
+            let value = if info.width == 1 {
+                quote! { u.arbitrary::<bool>()? }
+            } else if !descs.is_empty() {
+                // the values of the value-table constants
+                let values = descs.iter().map(|d| info.const_ident(*d.a()));
+                quote! { *u.choose::<#ntype>(&[#(#values),*])? }
+            } else {
+                let (low, high) = info.raw_range();
+                let (low, high) = (
+                    Literal::i128_unsuffixed(low),
+                    Literal::i128_unsuffixed(high),
+                );
+                if info.is_float() {
+                    quote! {
+                        (u.int_in_range::<i128>(#low..=#high)? as f32) * #scale
+                            + #offset
+                    }
+                } else {
+                    quote! { u.int_in_range::<#ntype>(#low..=#high)? }
+                }
+            };
+            fields.append_all(quote! {
+                message.#signal = #value;
+            });
+        }
+        quote! {
+            #[automatically_derived]
+            impl<'a> ::arbitrary::Arbitrary<'a> for #ident {
+                fn arbitrary(
+                    u: &mut ::arbitrary::Unstructured<'a>,
+                ) -> ::arbitrary::Result<Self> {
+                    #[allow(unused_mut)]
+                    let mut message = Self::new();
+                    #fields
+                    Ok(message)
+                }
+            }
+        }
+    }
+
+    /// Generate `randomize()`, setting each signal's value as for
+    /// `Arbitrary` from a `rand_core` generator
+    fn gen_randomize(
+        dbc: &DBC,
+        message: &MessageInfo,
+        id: MessageId,
+        infos: &[SignalInfo],
+    ) -> TokenStream {
+        let mut fields = TokenStream::new();
+        for info in infos {
+            let s = info.signal;
+            let signal = &info.ident;
+            let ntype = &info.ntype;
+            let descs = MessageInfo::value_descriptions(dbc, id, s.name())
+                .unwrap_or_default();
+            let value = if info.width == 1 {
+                quote! { rng.next_u32() & 1 != 0 }
+            } else if let [desc] = descs {
+                let value = info.const_ident(*desc.a());
+                quote! { #value }
+            } else if !descs.is_empty() {
+                let count = descs.len() as u64;
+                let values = descs.iter().map(|d| info.const_ident(*d.a()));
+                quote! {
+                    [#(#values),*][(rng.next_u64() % #count) as usize]
+                }
+            } else {
+                let (low, high) = info.raw_range();
+                let span = Literal::u128_unsuffixed((high - low + 1) as u128);
+                let low = Literal::i128_unsuffixed(low);
+                let raw = quote! {
+                    ((u128::from(rng.next_u64()) % #span) as i128 + #low)
+                };
+                if info.is_float() {
+                    let scale = info.scale;
+                    let offset = *s.offset() as f32;
+                    quote! { (#raw as f32) * #scale + #offset }
+                } else {
+                    quote! { #raw as #ntype }
+                }
+            };
+            fields.append_all(if message.dirty {
+                let setter =
+                    Ident::new(&format!("set_{signal}"), signal.span());
+                quote! { self.#setter(#value); }
+            } else {
+                quote! { self.#signal = #value; }
+            });
+        }
+        quote! {
+            /// Set each signal to a random value from its value table, if
+            /// it has one, or else within its DBC range
+            #[allow(unused_variables)]
+            pub fn randomize(&mut self, rng: &mut impl ::rand_core::RngCore) {
+                #fields
+            }
+        }
+    }
+
+    /// Generate a test encoding and decoding a message with each signal
+    /// at its lowest and then highest raw value, expecting the values to
+    /// be unchanged; unaligned big-endian signals are not yet encoded,
+    /// so are left at zero
+    fn gen_selftest(
+        message: &MessageInfo,
+        dlc: usize,
+        infos: &[SignalInfo],
+    ) -> TokenStream {
+        let ident = &message.ident;
+        let test = Ident::new(
+            &naming::identifier(&naming::snake_case(&ident.to_string())),
+            ident.span(),
+        );
+        let infos: Vec<&SignalInfo> =
+            infos.iter().filter(|i| i.can_encode()).collect();
+        let signals: Vec<&Ident> = infos.iter().map(|i| &i.ident).collect();
+        let names: Vec<String> = infos
+            .iter()
+            .map(|i| format!("{ident}.{}", i.ident))
+            .collect();
+        let mut cases = TokenStream::new();
+        for high in [false, true] {
+            let values = infos.iter().map(|info| {
+                let (low_raw, high_raw) = info.raw_range();
+                let raw = Literal::i128_unsuffixed(if high {
+                    high_raw
+                } else {
+                    low_raw
+                });
+                let ntype = &info.ntype;
+                if info.width == 1 {
+                    quote! { #high }
+                } else if info.is_float() {
+                    let scale = info.scale;
+                    let offset = *info.signal.offset() as f32;
+                    quote! { (#raw as f32) * #scale + #offset }
+                } else {
+                    quote! { #raw as #ntype }
+                }
+            });
+            cases.append_all(quote! {
+                let mut message = #ident::new();
+                #(message.#signals = #values;)*
+                let mut pdu = [0u8; #dlc];
+                assert!(message.encode(&mut pdu));
+                let mut decoded = #ident::new();
+                assert!(decoded.decode(&pdu));
+                #(assert_eq!(decoded.#signals, message.#signals, #names);)*
+            });
+        }
+        quote! {
+            #[test]
+            fn #test() {
+                #cases
+            }
+        }
+    }
+
+    /// Generate `strategy()`, the proptest strategy choosing each
+    /// signal's value as for `Arbitrary`
+    fn gen_strategy(
+        dbc: &DBC,
+        id: MessageId,
+        infos: &[SignalInfo],
+    ) -> TokenStream {
+        // nested pairs, as tuple strategies have at most 12 members
+        let mut strategies = quote! { ::proptest::strategy::Just(()) };
+        let mut values = quote! { () };
+        for info in infos.iter().rev() {
+            let s = info.signal;
+            let signal = &info.ident;
+            let ntype = &info.ntype;
+            let descs = MessageInfo::value_descriptions(dbc, id, s.name())
+                .unwrap_or_default();
+            let strategy = if info.width == 1 {
+                quote! { ::proptest::bool::ANY }
+            } else if !descs.is_empty() {
+                let values = descs.iter().map(|d| info.const_ident(*d.a()));
+                quote! {
+                    ::proptest::sample::select(vec![#(#values),*])
+                }
+            } else {
+                let (low, high) = info.raw_range();
+                let (low, high) = (
+                    Literal::i128_unsuffixed(low),
+                    Literal::i128_unsuffixed(high),
+                );
+                if info.is_float() {
+                    let scale = info.scale;
+                    let offset = *s.offset() as f32;
+                    quote! {
+                        ::proptest::strategy::Strategy::prop_map(
+                            #low..=#high as i128,
+                            |raw| (raw as f32) * #scale + #offset,
+                        )
+                    }
+                } else {
+                    quote! { (#low as #ntype)..=(#high as #ntype) }
+                }
+            };
+            strategies = quote! { (#strategy, #strategies) };
+            values = quote! { (#signal, #values) };
+        }
+        let signals = infos.iter().map(|i| &i.ident);
+        quote! {
+            /// A proptest strategy for messages with each signal's value
+            /// from its value table, or within its DBC range
+            #[allow(non_snake_case, unused_mut)]
+            pub fn strategy() -> impl ::proptest::strategy::Strategy<Value = Self> {
+                ::proptest::strategy::Strategy::prop_map(
+                    #strategies,
+                    |#values| {
+                        let mut message = Self::new();
+                        #(message.#signals = #signals;)*
+                        message
+                    },
+                )
+            }
+        }
+    }
+
+    /// Generate a `defmt::Format` implementation, showing each signal's
+    /// name, value and unit
+    fn gen_defmt(ident: &Ident, infos: &[SignalInfo]) -> TokenStream {
+        let signals = infos.iter().map(|i| &i.ident);
+        let fields: Vec<String> = infos
+            .iter()
+            .map(|i| {
+                let unit =
+                    i.signal.unit().replace('{', "{{").replace('}', "}}");
+                if unit.is_empty() {
+                    format!("{}: {{}}", i.signal.name())
+                } else {
+                    format!("{}: {{}} {unit}", i.signal.name())
+                }
+            })
+            .collect();
+        let format = format!("{ident} {{{{ {} }}}}", fields.join(", "));
+        quote! {
+            #[automatically_derived]
+            impl ::defmt::Format for #ident {
+                fn format(&self, f: ::defmt::Formatter) {
+                    ::defmt::write!(f, #format #(, self.#signals)*);
+                }
+            }
+        }
+    }
+
+    /// Generate the `<Message>Guarded` storage, which shares a message
+    /// between contexts within a `critical_section::Mutex`
+    fn gen_guarded(ident: &Ident, decode: bool) -> TokenStream {
+        let guarded = Ident::new(&format!("{ident}Guarded"), ident.span());
+        let doc = format!(
+            "A [`{ident}`] shared between contexts, such as an ISR and \
+             the main loop, which is accessed within a critical section"
+        );
+        let decode_fn = if decode {
+            quote! {
+                /// Decode a PDU into the message within a critical
+                /// section, returning `false` if its length is invalid
+                pub fn decode(&self, pdu: &[u8]) -> bool {
+                    self.with(|message| message.decode(pdu))
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #[automatically_derived]
+            #[allow(non_camel_case_types)]
+            #[allow(dead_code)]
+            #[doc = #doc]
+            pub struct #guarded(
+                critical_section::Mutex<core::cell::RefCell<#ident>>,
+            );
+
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #guarded {
+                pub const fn new() -> Self {
+                    Self(critical_section::Mutex::new(core::cell::RefCell::new(
+                        #ident::new(),
+                    )))
+                }
+
+                /// Call `f` with the message within a critical section
+                pub fn with<R>(&self, f: impl FnOnce(&mut #ident) -> R) -> R {
+                    critical_section::with(|cs| {
+                        f(&mut self.0.borrow_ref_mut(cs))
+                    })
+                }
+
+                #decode_fn
+            }
+
+            #[automatically_derived]
+            impl Default for #guarded {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        }
+    }
+
+    /// Generate the `<Message>Shared` wrapper, which holds a PDU
+    /// written by one context (e.g. an ISR) and read consistently by
+    /// another using a sequence lock
+    fn gen_shared(ident: &Ident, dlc: usize) -> TokenStream {
+        let shared = Ident::new(&format!("{ident}Shared"), ident.span());
+        let bytes = (0..dlc).map(|_| quote! { AtomicU8::new(0) });
+        let doc = format!(
+            "A [`{ident}`] PDU shared between a writer, such as an ISR, \
+             and readers, which always see a consistent `snapshot()`"
+        );
+        quote! {
+            #[automatically_derived]
+            #[allow(non_camel_case_types)]
+            #[allow(dead_code)]
+            #[doc = #doc]
+            pub struct #shared {
+                seq: core::sync::atomic::AtomicU32,
+                pdu: [core::sync::atomic::AtomicU8; #dlc],
+            }
+
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #shared {
+                pub const fn new() -> Self {
+                    use core::sync::atomic::{AtomicU32, AtomicU8};
+                    Self {
+                        seq: AtomicU32::new(0),
+                        pdu: [#(#bytes),*],
+                    }
+                }
+
+                /// Store a received PDU, returning `false` if its length
+                /// is invalid; there must only be one writer at a time
+                pub fn store(&self, pdu: &[u8]) -> bool {
+                    use core::sync::atomic::{fence, Ordering};
+                    if pdu.len() != #dlc {
+                        return false;
+                    }
+                    let seq = self.seq.load(Ordering::Relaxed);
+                    self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+                    fence(Ordering::Release);
+                    for (a, b) in self.pdu.iter().zip(pdu) {
+                        a.store(*b, Ordering::Relaxed);
+                    }
+                    self.seq.store(seq.wrapping_add(2), Ordering::Release);
+                    true
+                }
+
+                /// A decoded copy of the last PDU stored, or `None` if
+                /// a store was in progress
+                pub fn try_snapshot(&self) -> Option<#ident> {
+                    use core::sync::atomic::{fence, Ordering};
+                    let seq = self.seq.load(Ordering::Acquire);
+                    if seq % 2 != 0 {
+                        return None;
+                    }
+                    let mut pdu = [0u8; #dlc];
+                    for (b, a) in pdu.iter_mut().zip(self.pdu.iter()) {
+                        *b = a.load(Ordering::Relaxed);
+                    }
+                    fence(Ordering::Acquire);
+                    if self.seq.load(Ordering::Relaxed) != seq {
+                        return None;
+                    }
+                    let mut message = #ident::new();
+                    message.decode(&pdu);
+                    Some(message)
+                }
+
+                /// A decoded copy of the last PDU stored, retrying
+                /// while a store is in progress
+                pub fn snapshot(&self) -> #ident {
+                    loop {
+                        if let Some(message) = self.try_snapshot() {
+                            return message;
+                        }
+                        core::hint::spin_loop();
+                    }
+                }
+            }
+
+            #[automatically_derived]
+            impl Default for #shared {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        }
+    }
+
+    /// Generate the statistics field and accessors for a message, when
+    /// `#[dbc_stats]` is given
+    fn gen_stats(&self) -> (TokenStream, TokenStream) {
+        if !self.stats {
+            return (quote! {}, quote! {});
+        }
+
+        let stats = self.item_ident("MessageStats");
+        let field = quote! {
+            stats: #stats,
+        };
+        let fns = quote! {
+            /// The frame and error counts for the message
+            pub fn stats(&self) -> &#stats {
+                &self.stats
+            }
+
+            /// Clear the frame and error counts
+            pub fn reset_stats(&mut self) {
+                self.stats = #stats::default();
+            }
+
+            /// Count a frame which failed a CRC (or other integrity)
+            /// check performed by the application
+            pub fn record_crc_error(&mut self) {
+                self.stats.crc_errors = self.stats.crc_errors.saturating_add(1);
+            }
+
+            /// Count a reception timeout detected by the application
+            pub fn record_timeout(&mut self) {
+                self.stats.timeouts = self.stats.timeouts.saturating_add(1);
+            }
+
+            /// Count a transmitted frame
+            pub fn record_sent(&mut self) {
+                self.stats.sent = self.stats.sent.saturating_add(1);
+            }
+        };
+        (field, fns)
+    }
+
+    /// Generate the `<Name>MessageStats` type holding the counts for
+    /// each message
+    fn gen_stats_type(&self) -> TokenStream {
+        let stats = self.item_ident("MessageStats");
+        let doc = format!(
+            "Frame and error counts for a message held by [`{}`], which \
+             saturate rather than wrap",
+            self.name
+        );
+        quote! {
+            #[doc = #doc]
+            #[automatically_derived]
+            #[allow(dead_code)]
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+            pub struct #stats {
+                /// Frames successfully decoded
+                pub decoded: u32,
+                /// Frames with an invalid length
+                pub length_errors: u32,
+                /// Frames which failed a CRC check
+                pub crc_errors: u32,
+                /// Reception timeouts
+                pub timeouts: u32,
+                /// Frames transmitted
+                pub sent: u32,
+            }
+
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #stats {
+                pub const fn new() -> Self {
+                    Self {
+                        decoded: 0,
+                        length_errors: 0,
+                        crc_errors: 0,
+                        timeouts: 0,
+                        sent: 0,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Generate the `<Name>MessageKind` enum and `update()` dispatcher
+    /// which decodes frames into the matching message of the struct
+    #[allow(clippy::too_many_lines)]
+    fn gen_dispatcher(&self) -> TokenStream {
+        let message_kind = self.item_ident("MessageKind");
+        let mut messages: Vec<&MessageInfo> = self.messages.values().collect();
+        messages.sort_by_key(|m| m.position);
+
+        let mut variants = TokenStream::new();
+        let mut arms = TokenStream::new();
+        let mut id_arms = TokenStream::new();
+        let mut extended_arms = TokenStream::new();
+        let mut seen = vec![];
+        // the IDs covered by each message, for binary search
+        let mut ranges = vec![];
+        for message in messages {
+            let ident = &message.ident;
+            let Some(field) = &message.field else {
+                continue;
+            };
+            let id = message.id;
+            let extended = message.extended;
+            if message.is_array && message.index_signal.is_some() {
+                // the elements share an ID, indexed by a signal
+                id_arms.append_all(quote! {
+                    #message_kind::#ident(_) => #id,
+                });
+                extended_arms.append_all(quote! {
+                    #message_kind::#ident(_) => #extended,
+                });
+                variants.append_all(quote! { #ident(usize), });
+                if !message.decode || seen.contains(&(id, extended)) {
+                    continue;
+                }
+                seen.push((id, extended));
+                let decode = quote! {
+                    {
+                        let index = #ident::index_of(data)?;
+                        self.#field
+                            .get_mut(index)?
+                            .decode(data)
+                            .then_some(#message_kind::#ident(index))
+                    }
+                };
+                arms.append_all(quote! {
+                    (#id, #extended) => #decode,
+                });
+                ranges.push((extended, id, Some(1), decode));
+            } else if message.is_array {
+                id_arms.append_all(quote! {
+                    #message_kind::#ident(index) => #id + index as u32,
+                });
+                extended_arms.append_all(quote! {
+                    #message_kind::#ident(_) => #extended,
+                });
+                variants.append_all(quote! { #ident(usize), });
+                if !message.decode {
+                    continue;
+                }
+                arms.append_all(quote! {
+                    (id, #extended) if (id.wrapping_sub(#id) as usize)
+                        < self.#field.len() => {
+                        let index = (id - #id) as usize;
+                        self.#field[index]
+                            .decode(data)
+                            .then_some(#message_kind::#ident(index))
+                    }
+                });
+                ranges.push((
+                    extended,
+                    id,
+                    message.array_len.map(|len| len as u32),
+                    quote! {
+                        {
+                            let index = (id - first) as usize;
+                            self.#field
+                                .get_mut(index)?
+                                .decode(data)
+                                .then_some(#message_kind::#ident(index))
+                        }
+                    },
+                ));
+            } else {
+                id_arms.append_all(quote! {
+                    #message_kind::#ident => #id,
+                });
+                extended_arms.append_all(quote! {
+                    #message_kind::#ident => #extended,
+                });
+                variants.append_all(quote! { #ident, });
+                // the first message declared for an ID takes precedence
+                if !message.decode || seen.contains(&(id, extended)) {
+                    continue;
+                }
+                seen.push((id, extended));
+                arms.append_all(quote! {
+                    (#id, #extended) => self
+                        .#field
+                        .decode(data)
+                        .then_some(#message_kind::#ident),
+                });
+                ranges.push((
+                    extended,
+                    id,
+                    Some(1),
+                    quote! {
+                        self.#field.decode(data).then_some(#message_kind::#ident)
+                    },
+                ));
+            }
+        }
+        let dispatch = Self::gen_dispatch_search(ranges).unwrap_or_else(|| {
+            quote! {
+                match (id, extended) {
+                    #arms
+                    _ => None,
+                }
+            }
+        });
+
+        let name = self.name;
+        let (impl_generics, ty_generics, where_clause) =
+            self.generics.split_for_impl();
+        let doc = format!("The messages held by [`{name}`]");
+        quote! {
+            #[automatically_derived]
+            #[allow(non_camel_case_types)]
+            #[allow(dead_code)]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            #[doc = #doc]
+            pub enum #message_kind {
+                #variants
+            }
+
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #message_kind {
+                /// The ID of the message
+                pub const fn id(self) -> u32 {
+                    match self {
+                        #id_arms
+                    }
+                }
+
+                /// Whether the message has an extended ID
+                pub const fn extended(self) -> bool {
+                    match self {
+                        #extended_arms
+                    }
+                }
+            }
+
+            #[automatically_derived]
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Decode a received frame into the message with the
+                /// matching ID, returning which message was updated;
+                /// `None` indicates an unknown ID or invalid length
+                pub fn update(&mut self, id: u32, extended: bool, data: &[u8])
+                              -> Option<#message_kind> {
+                    #dispatch
+                }
+
+                /// Decode a burst of `(id, extended, data)` frames, e.g.
+                /// drained from a receive FIFO, as for `update()`,
+                /// returning how many were decoded
+                pub fn decode_all(&mut self, frames: &[(u32, bool, &[u8])])
+                                  -> usize {
+                    frames
+                        .iter()
+                        .filter(|(id, extended, data)| {
+                            self.update(*id, *extended, data).is_some()
+                        })
+                        .count()
+                }
+            }
+        }
+    }
+
+    /// Generate the body of `update()` finding the message of a frame
+    /// by binary search of a table of the messages sorted by ID, given
+    /// the extended flag, first ID, count and decoding of each message;
+    /// `None` if there are too few messages for this to be worthwhile,
+    /// or their IDs overlap or cannot be known
+    fn gen_dispatch_search(
+        ranges: Vec<(bool, u32, Option<u32>, TokenStream)>,
+    ) -> Option<TokenStream> {
+        if ranges.len() <= DISPATCH_SEARCH_MIN {
+            return None;
+        }
+        let mut ranges = ranges
+            .into_iter()
+            .map(|(extended, id, count, decode)| {
+                Some((extended, id, count?, decode))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        ranges.sort_by_key(|(extended, id, _, _)| (*extended, *id));
+        if ranges.windows(2).any(|pair| {
+            pair[0].0 == pair[1].0
+                && u64::from(pair[0].1) + u64::from(pair[0].2)
+                    > u64::from(pair[1].1)
+        }) {
+            return None;
+        }
+
+        let len = ranges.len();
+        let mut entries = vec![];
+        let mut arms = TokenStream::new();
+        for (n, (extended, id, count, decode)) in ranges.into_iter().enumerate()
+        {
+            entries.push(quote! { (#extended, #id, #count) });
+            arms.append_all(quote! { #n => #decode, });
+        }
+        Some(quote! {
+            const MESSAGES: [(bool, u32, u32); #len] = [#(#entries),*];
+            let n = MESSAGES.partition_point(|&(e, first, _)| {
+                (e, first) <= (extended, id)
+            });
+            let (e, first, count) = *MESSAGES.get(n.checked_sub(1)?)?;
+            if e != extended || id - first >= count {
+                return None;
+            }
+            match n - 1 {
+                #arms
+                _ => None,
+            }
+        })
+    }
+
+    /// Generate the `<Name>MessageSet` type, with a flag for each
+    /// message of the struct, and the functions reporting which have
+    /// been received
+    fn gen_message_set(&self) -> TokenStream {
+        let mut messages: Vec<&MessageInfo> = self.messages.values().collect();
+        messages.sort_by_key(|m| m.position);
+
+        let set = self.item_ident("MessageSet");
+        let mut flags: Vec<&Ident> = vec![];
+        let mut names: Vec<String> = vec![];
+        let mut received = TokenStream::new();
+        let mut mandatory: Vec<TokenStream> = vec![];
+        let mut all: Vec<TokenStream> = vec![];
+        for message in &messages {
+            let ident = &message.ident;
+            let Some(field) = &message.field else {
+                continue;
+            };
+            flags.push(ident);
+            names.push(ident.to_string());
+            // an array of messages is received once all of them are
+            let is_received = if message.is_array {
+                quote! { self.#field.iter().all(|m| m.received()) }
+            } else {
+                quote! { self.#field.received() }
+            };
+            received.append_all(quote! {
+                if #is_received {
+                    set |= #set::#ident;
+                }
+            });
+            // messages disabled by #[dbc_cfg] are never mandatory
+            let flag = if let Some(cfg) = &message.cfg {
+                quote! { if cfg!(#cfg) { #set::#ident } else { #set::EMPTY } }
+            } else {
+                quote! { #set::#ident }
+            };
+            if message.mandatory {
+                mandatory.push(flag.clone());
+            }
+            all.push(flag);
+        }
+        // without any #[dbc_mandatory] messages, all are mandatory
+        if mandatory.is_empty() {
+            mandatory = all;
+        }
+
+        let name = self.name;
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let doc = format!("A set of the messages held by [`{name}`]");
+        let set_type = Self::gen_set(&set, &doc, &flags, &names);
+        let (impl_generics, ty_generics, where_clause) =
+            self.generics.split_for_impl();
+        quote! {
+            #set_type
+
+            #[automatically_derived]
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// The messages which must be received before
+                /// `all_received()` is true
+                pub const MANDATORY: #set = #set::EMPTY
+                    #(.union(#mandatory))*;
+
+                /// The set of messages which have been received
+                pub fn received_messages(&self) -> #set {
+                    let mut set = #set::default();
+                    #received
+                    set
+                }
+
+                /// Whether all `MANDATORY` messages have been received
+                pub fn all_received(&self) -> bool {
+                    self.received_messages().contains(Self::MANDATORY)
+                }
+            }
+        }
+    }
+
+    /// Generate the `<Name>TxScheduler` which reports when each message
+    /// is due, with its `TX_SCHEDULE` table of messages with a cycle
+    /// time and `TX_EVENTS` table of event-triggered messages, and the
+    /// `<Name>TxManager` trait used by `transmit()`
+    #[allow(clippy::too_many_lines)]
+    fn gen_tx_schedule(&self) -> TokenStream {
+        let message_kind = self.item_ident("MessageKind");
+        let mut messages: Vec<&MessageInfo> = self.messages.values().collect();
+        messages.sort_by_key(|m| m.position);
+
+        let mut entries = TokenStream::new();
+        let mut count = 0usize;
+        let mut events = TokenStream::new();
+        let mut event_count = 0usize;
+        let mut encode_arms = TokenStream::new();
+        let mut max_dlc = 0usize;
+        for message in messages {
+            let ident = &message.ident;
+            let Some(field) = &message.field else {
+                continue;
+            };
+            let dlc = self
+                .dbc(message)
+                .messages()
+                .get(message.index)
+                .map_or(0, |m| *m.message_size() as usize);
+            max_dlc = max_dlc.max(dlc);
+
+            // receive-only messages are neither encoded nor scheduled
+            if !message.encode {
+                encode_arms.append_all(if message.is_array {
+                    quote! { #message_kind::#ident(_) => None, }
+                } else {
+                    quote! { #message_kind::#ident => None, }
+                });
+                continue;
+            }
+
+            // arrays of unknown length are not scheduled
+            let kinds: Vec<TokenStream> = if message.is_array {
+                encode_arms.append_all(quote! {
+                    #message_kind::#ident(index) => {
+                        let pdu = buf.get_mut(..#dlc)?;
+                        self.#field.get(index)?.encode_clean(pdu).then_some(#dlc)
+                    }
+                });
+                (0..message.array_len.unwrap_or(0))
+                    .map(|index| quote! { #message_kind::#ident(#index) })
+                    .collect()
+            } else {
+                encode_arms.append_all(quote! {
+                    #message_kind::#ident => {
+                        let pdu = buf.get_mut(..#dlc)?;
+                        self.#field.encode_clean(pdu).then_some(#dlc)
+                    }
+                });
+                vec![quote! { #message_kind::#ident }]
+            };
+
+            if let Some(period) = message.cycle_time.filter(|c| *c > 0) {
+                let period = period as u32;
+                let offset = message.start_delay.unwrap_or(0) as u32;
+                for kind in &kinds {
+                    entries.append_all(quote! {
+                        (#kind, #period, #offset),
+                    });
+                    count += 1;
+                }
+            }
+            if message.event {
+                for kind in &kinds {
+                    events.append_all(quote! { #kind, });
+                    event_count += 1;
+                }
+            }
+        }
+
+        let name = self.name;
+        let scheduler = self.item_ident("TxScheduler");
+        let manager = self.item_ident("TxManager");
+        let (impl_generics, ty_generics, where_clause) =
+            self.generics.split_for_impl();
+        let doc = format!(
+            "Periodic transmission schedule of the messages held by \
+             [`{name}`], from their `GenMsgCycleTime` and \
+             `GenMsgStartDelayTime` attributes, as `(message, period, \
+             offset)` in milliseconds"
+        );
+        let events_doc = format!(
+            "The messages held by [`{name}`] which are sent on events, \
+             from their `GenMsgSendType` attributes"
+        );
+        let scheduler_doc = format!(
+            "Reports when each message held by [`{name}`] is due for \
+             transmission, given a millisecond time which may wrap, \
+             along with any `TX_EVENTS` which have been triggered"
+        );
+        quote! {
+            #[doc = #scheduler_doc]
+            #[automatically_derived]
+            #[allow(dead_code)]
+            #[derive(Clone, Debug)]
+            pub struct #scheduler {
+                next: [u32; #count],
+                pending: [bool; #event_count],
+            }
+
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #scheduler {
+                #[doc = #doc]
+                pub const TX_SCHEDULE: [(#message_kind, u32, u32); #count] =
+                    [#entries];
+
+                #[doc = #events_doc]
+                pub const TX_EVENTS: [#message_kind; #event_count] = [#events];
+
+                /// A scheduler starting at time zero
+                pub const fn new() -> Self {
+                    let mut next = [0u32; #count];
+                    let mut n = 0;
+                    while n < #count {
+                        next[n] = Self::TX_SCHEDULE[n].2;
+                        n += 1;
+                    }
+                    Self {
+                        next,
+                        pending: [false; #event_count],
+                    }
+                }
+
+                /// Restart the schedule at `now`, so each message is
+                /// next due after its offset
+                pub fn start(&mut self, now: u32) {
+                    for (next, (_, _, offset)) in
+                        self.next.iter_mut().zip(Self::TX_SCHEDULE.iter())
+                    {
+                        *next = now.wrapping_add(*offset);
+                    }
+                }
+
+                /// Request that an event-triggered message is sent when
+                /// `due()` is next called, returning `false` if it is
+                /// not one of `TX_EVENTS`
+                pub fn trigger(&mut self, kind: #message_kind) -> bool {
+                    match Self::TX_EVENTS.iter().position(|k| *k == kind) {
+                        Some(n) => {
+                            self.pending[n] = true;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+
+                /// The time at which a cyclic message is next due, if
+                /// it is one of `TX_SCHEDULE`
+                pub fn next_deadline(&self, kind: #message_kind) -> Option<u32> {
+                    Self::TX_SCHEDULE
+                        .iter()
+                        .position(|(k, _, _)| *k == kind)
+                        .map(|n| self.next[n])
+                }
+
+                /// Whether a message is due for transmission at `now`,
+                /// either by its schedule or by being triggered
+                pub fn is_due(&self, kind: #message_kind, now: u32) -> bool {
+                    self.next_deadline(kind)
+                        .is_some_and(|t| now.wrapping_sub(t) <= u32::MAX / 2)
+                        || Self::TX_EVENTS
+                            .iter()
+                            .zip(self.pending.iter())
+                            .any(|(k, p)| *p && *k == kind)
+                }
+
+                /// The messages due for transmission at `now`; each
+                /// is rescheduled as it is yielded, so the iterator
+                /// should be consumed.  Messages which have fallen
+                /// more than a period behind are due once, rather
+                /// than once for each missed period.  Triggered
+                /// events follow the cyclic messages.
+                pub fn due(&mut self, now: u32)
+                           -> impl Iterator<Item = #message_kind> + '_ {
+                    let cyclic = self
+                        .next
+                        .iter_mut()
+                        .zip(Self::TX_SCHEDULE.iter())
+                        .filter_map(move |(next, (kind, period, _))| {
+                            if now.wrapping_sub(*next) > u32::MAX / 2 {
+                                return None;
+                            }
+                            *next = next.wrapping_add(*period);
+                            if now.wrapping_sub(*next) <= u32::MAX / 2 {
+                                *next = now.wrapping_add(*period);
+                            }
+                            Some(*kind)
+                        });
+                    let events = self
+                        .pending
+                        .iter_mut()
+                        .zip(Self::TX_EVENTS.iter())
+                        .filter_map(|(pending, kind)| {
+                            core::mem::take(pending).then_some(*kind)
+                        });
+                    cyclic.chain(events)
+                }
+            }
+
+            #[automatically_derived]
+            impl Default for #scheduler {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            /// The application side of transmission: a clock and a
+            /// driver which sends frames, for use with `transmit()`
+            pub trait #manager {
+                /// The current time in milliseconds, which may wrap
+                fn now(&mut self) -> u32;
+
+                /// Send a frame
+                fn send(&mut self, id: u32, extended: bool, data: &[u8]);
+            }
+
+            #[automatically_derived]
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// The largest DLC of the messages held
+                pub const MAX_DLC: usize = #max_dlc;
+
+                /// Encode a message into the start of `buf` (filling
+                /// unused bits with its `PADDING`), returning its DLC,
+                /// or `None` if `buf` is too short or the message is
+                /// receive-only
+                pub fn encode_into(&self, kind: #message_kind, buf: &mut [u8])
+                                   -> Option<usize> {
+                    match kind {
+                        #encode_arms
+                    }
+                }
+
+                /// Encode and send each message which is due, returning
+                /// the number sent
+                pub fn transmit(
+                    &self,
+                    scheduler: &mut #scheduler,
+                    tx: &mut impl #manager,
+                ) -> usize {
+                    let now = tx.now();
+                    let mut sent = 0;
+                    for kind in scheduler.due(now) {
+                        let mut buf = [0u8; #max_dlc];
+                        if let Some(len) = self.encode_into(kind, &mut buf) {
+                            tx.send(kind.id(), kind.extended(), &buf[..len]);
+                            sent += 1;
+                        }
+                    }
+                    sent
+                }
+            }
+        }
+    }
+
+    /// Generate the signal-set type for a message (with one flag per
+    /// signal) and the change-detecting functions which produce it,
+    /// omitting `decode_changes()` if the message does not decode
+    fn gen_changes(
+        ident: &Ident,
+        infos: &[SignalInfo],
+        decode: bool,
+    ) -> (TokenStream, TokenStream) {
+        let set = Self::signal_set_ident(ident);
+        let signals: Vec<&Ident> = infos.iter().map(|i| &i.ident).collect();
+        let names: Vec<&str> =
+            infos.iter().map(|i| i.signal.name().as_str()).collect();
+        let fields = (0..infos.len()).map(syn::Index::from);
+        let doc = format!("A set of [`{ident}`] signals");
+        let set_type = Self::gen_set(&set, &doc, &signals, &names);
+
+        let decode_changes = if !decode {
+            quote! {}
+        } else if infos.is_empty() {
+            quote! {
+                /// Decode the PDU, returning the set of signals whose
+                /// values changed, or `None` if the PDU length is invalid
+                pub fn decode_changes(&mut self, pdu: &[u8]) -> Option<#set> {
+                    self.decode(pdu).then(#set::default)
+                }
+            }
+        } else {
+            quote! {
+                /// Decode the PDU, returning the set of signals whose
+                /// values changed, or `None` if the PDU length is invalid
+                pub fn decode_changes(&mut self, pdu: &[u8]) -> Option<#set> {
+                    let prev = (#(self.#signals,)*);
+                    if !self.decode(pdu) {
+                        return None;
+                    }
+                    let mut changes = #set::default();
+                    #(
+                        if self.#signals != prev.#fields {
+                            changes |= #set::#signals;
+                        }
+                    )*
+                    Some(changes)
+                }
+            }
+        };
+        let diff_fn = if infos.is_empty() {
+            quote! {
+                /// The set of signals whose values differ from `other`
+                pub fn diff(&self, _other: &Self) -> #set {
+                    #set::default()
+                }
+            }
+        } else {
+            quote! {
+                /// The set of signals whose values differ from `other`
+                pub fn diff(&self, other: &Self) -> #set {
+                    let mut changes = #set::default();
+                    #(
+                        if self.#signals != other.#signals {
+                            changes |= #set::#signals;
+                        }
+                    )*
+                    changes
+                }
+            }
+        };
+
+        (
+            set_type,
+            quote! {
+                #decode_changes
+                #diff_fn
+            },
+        )
+    }
+
+    /// Generate `csv_header()` and `write_csv()`, giving the signal
+    /// names and values of a message as CSV rows
+    fn gen_csv(infos: &[SignalInfo]) -> TokenStream {
+        let signals = infos.iter().map(|i| &i.ident);
+        let header = infos
+            .iter()
+            .map(|i| i.signal.name().as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let format = vec!["{}"; infos.len()].join(",");
+        quote! {
+            /// The CSV header row, naming each signal, without a line
+            /// ending
+            pub const fn csv_header() -> &'static str {
+                #header
+            }
+
+            /// Write the value of each signal as a CSV row, in the
+            /// order of `csv_header()` and without a line ending
+            pub fn write_csv<W: core::fmt::Write>(
+                &self,
+                w: &mut W,
+            ) -> core::fmt::Result {
+                ::core::write!(w, #format, #(self.#signals),*)
+            }
+        }
+    }
+
+    /// Generate `write_json()`, writing a message as a JSON object of
+    /// its signal names and values
+    fn gen_json(infos: &[SignalInfo]) -> TokenStream {
+        let mut fields = TokenStream::new();
+        for (n, info) in infos.iter().enumerate() {
+            let key = format!(
+                "{}\"{}\":",
+                if n == 0 { "{" } else { "," },
+                info.signal.name()
+            );
+            let signal = &info.ident;
+            // JSON has no representation of infinities or NaN
+            fields.append_all(if info.is_float() {
+                quote! {
+                    w.write_str(#key)?;
+                    if self.#signal.is_finite() {
+                        ::core::write!(w, "{}", self.#signal)?;
+                    } else {
+                        w.write_str("null")?;
+                    }
+                }
+            } else {
+                quote! {
+                    w.write_str(#key)?;
+                    ::core::write!(w, "{}", self.#signal)?;
+                }
+            });
+        }
+        let close = if infos.is_empty() { "{}" } else { "}" };
+        quote! {
+            /// Write the message as a JSON object of each signal's
+            /// name and value, with `null` for non-finite values
+            pub fn write_json<W: core::fmt::Write>(
+                &self,
+                w: &mut W,
+            ) -> core::fmt::Result {
+                #fields
+                w.write_str(#close)
+            }
+        }
+    }
+
+    /// Generate a set type holding one flag for each of `flags`,
+    /// with `names` giving the name of each flag
+    fn gen_set(
+        set: &Ident,
+        doc: &str,
+        flags: &[&Ident],
+        names: &[&str],
+    ) -> TokenStream {
+        let words = flags.len().div_ceil(64).max(1);
+        let count = flags.len();
+        let index = 0..count;
+
+        quote! {
+            #[automatically_derived]
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+            #[doc = #doc]
+            pub struct #set([u64; #words]);
+
+            #[allow(non_upper_case_globals)]
+            impl #set {
+                #(
+                    pub const #flags: Self = Self::bit(#index);
+                )*
+
+                /// Names of the flags, in order
+                pub const NAMES: [&'static str; #count] = [#(#names),*];
+
+                /// The empty set
+                pub const EMPTY: Self = Self([0; #words]);
+
+                const fn bit(n: usize) -> Self {
+                    let mut words = [0u64; #words];
+                    words[n / 64] = 1 << (n % 64);
+                    Self(words)
+                }
+
+                /// The flags in either this set or `other`
+                #[must_use]
+                pub const fn union(mut self, other: Self) -> Self {
+                    let mut n = 0;
+                    while n < #words {
+                        self.0[n] |= other.0[n];
+                        n += 1;
+                    }
+                    self
+                }
+
+                /// Whether the set is empty
+                pub fn is_empty(&self) -> bool {
+                    self.0.iter().all(|w| *w == 0)
+                }
+
+                /// Whether all flags in `other` are in this set
+                pub fn contains(&self, other: Self) -> bool {
+                    self.0.iter().zip(other.0.iter()).all(|(a, b)| a & b == *b)
+                }
+
+                /// The number of flags in the set
+                pub fn len(&self) -> usize {
+                    self.0.iter().map(|w| w.count_ones() as usize).sum()
+                }
+
+                /// Iterate over the names of flags in the set
+                pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+                    Self::NAMES
+                        .iter()
+                        .enumerate()
+                        .filter(|(n, _)| self.0[n / 64] & (1 << (n % 64)) != 0)
+                        .map(|(_, name)| *name)
+                }
+            }
+
+            impl core::ops::BitOr for #set {
+                type Output = Self;
+                fn bitor(mut self, rhs: Self) -> Self {
+                    self |= rhs;
+                    self
+                }
+            }
+
+            impl core::ops::BitOrAssign for #set {
+                fn bitor_assign(&mut self, rhs: Self) {
+                    for (a, b) in self.0.iter_mut().zip(rhs.0.iter()) {
+                        *a |= b;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The identifier of a message's signal-set type
+    fn signal_set_ident(ident: &Ident) -> Ident {
+        Ident::new(&format!("{ident}Signals"), ident.span())
+    }
+
+    /// The identifier of a type generated for the deriving item, named
+    /// after it so that several derives may share a module
+    fn item_ident(&self, suffix: &str) -> Ident {
+        Ident::new(&format!("{}{suffix}", self.name), self.name.span())
+    }
+
+    /// Generate the bit-level debugging view of a message
+    fn gen_bits(infos: &[SignalInfo]) -> TokenStream {
+        let mut arms = TokenStream::new();
+        for info in infos {
+            let positions = info.bit_positions();
+            let name = info.signal.name();
+            arms.append_all(quote! {
+                #(#positions)|* => Some(#name),
+            });
+        }
+
+        quote! {
+            /// Iterate over each bit of `pdu` as `(index, value)`,
+            /// where the index is `byte * 8 + bit`
+            pub fn bits<'a>(&self, pdu: &'a [u8])
+                            -> impl Iterator<Item = (usize, bool)> + 'a {
+                pdu.iter().enumerate().flat_map(|(byte, v)| {
+                    (0..8).map(move |bit| (byte * 8 + bit, (v >> bit) & 1 != 0))
+                })
+            }
+
+            /// The name of the signal occupying a PDU bit index, if any
+            pub fn bit_signal(bit: usize) -> Option<&'static str> {
+                match bit {
+                    #arms
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// A grid of the PDU bits for the message documentation, with a
+    /// letter for the signal occupying each bit, `*` where signals
+    /// overlap, and `.` for unused bits
+    fn layout_doc(dlc: usize, infos: &[SignalInfo]) -> String {
+        const LETTERS: &[u8] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+        if infos.is_empty() {
+            return String::new();
+        }
+        let mut grid = vec![b'.'; dlc * 8];
+        let mut legend = String::new();
+        for (n, info) in infos.iter().enumerate() {
+            let letter = LETTERS.get(n).copied().unwrap_or(b'#');
+            for bit in info.bit_positions() {
+                if let Some(cell) = grid.get_mut(bit) {
+                    *cell = if *cell == b'.' { letter } else { b'*' };
+                }
+            }
+            let s = info.signal;
+            let _ = writeln!(
+                legend,
+                "{}: {} ({}|{}@{}{})",
+                letter as char,
+                s.name(),
+                s.start_bit(),
+                s.signal_size(),
+                if s.byte_order() == &ByteOrder::BigEndian {
+                    '0'
+                } else {
+                    '1'
+                },
+                if info.signed { '-' } else { '+' },
+            );
+        }
+        let mut doc = String::from("\n```text\nByte | 7 6 5 4 3 2 1 0\n");
+        for (byte, bits) in grid.chunks(8).enumerate() {
+            let _ = write!(doc, "{byte:4} |");
+            for bit in bits.iter().rev() {
+                let _ = write!(doc, " {}", *bit as char);
+            }
+            doc.push('\n');
+        }
+        doc.push('\n');
+        doc.push_str(&legend);
+        doc.push_str("```");
+        doc
+    }
+}
@@ -0,0 +1,165 @@
+//! C header of the generated messages, for C code sharing the firmware
+
+use crate::{manifest, naming, signal::SignalInfo, MessageInfo};
+use can_dbc::{Message, DBC};
+use proc_macro2::Span;
+use std::fmt::Write;
+use std::fs;
+use syn::Result;
+
+/// Builds a C header with a struct, ID and DLC for each generated
+/// message, and scaling macros and value-table constants for each of
+/// its signals
+pub struct CHeader {
+    /// The DBC file the code was generated from
+    dbc_file: String,
+    /// The declarations of each message
+    messages: Vec<String>,
+}
+
+/// The C type of a generated field
+fn c_type(ntype: &str) -> &'static str {
+    match ntype {
+        "bool" => "bool",
+        "u8" => "uint8_t",
+        "u16" => "uint16_t",
+        "u32" => "uint32_t",
+        "u64" => "uint64_t",
+        "i8" => "int8_t",
+        "i16" => "int16_t",
+        "i32" => "int32_t",
+        "i64" => "int64_t",
+        "f64" => "double",
+        _ => "float",
+    }
+}
+
+/// The prefix of a macro, in upper snake case
+fn macro_name(name: &str) -> String {
+    naming::snake_case(name)
+        .trim_matches('_')
+        .to_uppercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+impl CHeader {
+    pub fn new(dbc_file: &str) -> Self {
+        Self {
+            dbc_file: dbc_file.to_string(),
+            messages: vec![],
+        }
+    }
+
+    /// Add a message and its selected signals, followed by the
+    /// `received` flag of messages held in a struct
+    pub fn add_message(
+        &mut self,
+        message: &MessageInfo,
+        m: &Message,
+        dbc: &DBC,
+        infos: &[SignalInfo],
+        received: bool,
+    ) {
+        let ident = message.ident.to_string();
+        let prefix = macro_name(&ident);
+        let mut c = format!("/* {} */\n", m.message_name());
+        let _ = writeln!(c, "#define {prefix}_ID {:#X}u", message.id);
+        let _ = writeln!(c, "#define {prefix}_DLC {}u", m.message_size());
+        let _ = writeln!(
+            c,
+            "#define {prefix}_EXTENDED {}",
+            u8::from(message.extended)
+        );
+        if let Some(cycle_time) = message.cycle_time {
+            let _ = writeln!(c, "#define {prefix}_CYCLE_TIME {cycle_time}u");
+        }
+        if let Some(fast) = message.cycle_time_fast {
+            let _ = writeln!(c, "#define {prefix}_CYCLE_TIME_FAST {fast}u");
+        }
+        if let Some(repetitions) = message.repetitions {
+            let _ = writeln!(c, "#define {prefix}_REPETITIONS {repetitions}u");
+        }
+        if let Some(timeout) = message.timeout {
+            let _ = writeln!(c, "#define {prefix}_TIMEOUT_TIME {timeout}u");
+        }
+
+        c.push_str("\ntypedef struct {\n");
+        for info in infos {
+            let _ = writeln!(
+                c,
+                "    {} {};",
+                c_type(&info.ntype.to_string()),
+                info.ident
+            );
+        }
+        if received {
+            c.push_str("    bool received;\n");
+        }
+        let _ = writeln!(c, "}} {ident}_t;");
+
+        for info in infos {
+            let s = info.signal;
+            let signal =
+                format!("{prefix}_{}", macro_name(&info.ident.to_string()));
+            let factor = format!("{:?}", s.factor());
+            let offset = format!("{:?}", s.offset());
+            c.push('\n');
+            let _ = writeln!(c, "#define {signal}_START_BIT {}u", info.start);
+            let _ = writeln!(c, "#define {signal}_WIDTH {}u", info.width);
+            let _ = writeln!(c, "#define {signal}_FACTOR {factor}");
+            let _ = writeln!(c, "#define {signal}_OFFSET {offset}");
+            let _ = writeln!(
+                c,
+                "#define {signal}_DECODE(raw) ((raw) * ({factor}) + ({offset}))"
+            );
+            let _ = writeln!(
+                c,
+                "#define {signal}_ENCODE(value) \
+                 (((value) - ({offset})) / ({factor}))"
+            );
+            for desc in
+                MessageInfo::value_descriptions(dbc, *m.message_id(), s.name())
+                    .unwrap_or_default()
+            {
+                let _ = writeln!(
+                    c,
+                    "#define {signal}_{} {}",
+                    macro_name(desc.b()),
+                    desc.a()
+                );
+            }
+        }
+        self.messages.push(c);
+    }
+
+    /// The complete header, guarded by a macro named after its file
+    pub fn to_c(&self, file: &str) -> String {
+        let guard = macro_name(
+            std::path::Path::new(file)
+                .file_name()
+                .and_then(|f| f.to_str())
+                .unwrap_or(file),
+        );
+        format!(
+            "/* Generated by dbc-data from {}; do not edit */\n\n\
+             #ifndef {guard}\n#define {guard}\n\n\
+             #include <stdbool.h>\n#include <stdint.h>\n\n\
+             {}\n#endif /* {guard} */\n",
+            self.dbc_file,
+            self.messages.join("\n"),
+        )
+    }
+
+    /// Write the header to `file`, placed as for the manifest
+    pub fn write(&self, file: &str, span: Span) -> Result<()> {
+        let path = manifest::output_path(file, span)?;
+        fs::write(&path, self.to_c(file)).map_err(|e| {
+            syn::Error::new(
+                span,
+                format!("Could not write {}: {e}", path.display()),
+            )
+        })
+    }
+}
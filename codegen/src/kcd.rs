@@ -0,0 +1,139 @@
+//! Reading of KCD (Kayak CAN definition) XML networks
+
+use crate::{
+    convert::{Message, Network, Signal},
+    xml::{self, Element},
+};
+
+/// Parse a decimal or `0x`-prefixed hexadecimal number
+fn number(e: &Element, name: &str) -> Result<Option<usize>, String> {
+    let Some(value) = e.attr(name) else {
+        return Ok(None);
+    };
+    let parsed = match value.strip_prefix("0x").or(value.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed
+        .map(Some)
+        .map_err(|_| format!("Invalid {name} \"{value}\" in <{}>", e.name))
+}
+
+/// A decimal number, as given or else the default
+fn decimal(e: &Element, name: &str, default: &str) -> Result<String, String> {
+    let value = e.attr(name).unwrap_or(default);
+    value
+        .parse::<f64>()
+        .map(|_| value.to_string())
+        .map_err(|_| format!("Invalid {name} \"{value}\" in <{}>", e.name))
+}
+
+/// The names of the nodes referenced within an element, e.g.
+/// `<Producer>`
+fn node_refs(e: &Element, nodes: &[(String, String)]) -> Vec<String> {
+    e.children("NodeRef")
+        .filter_map(|r| r.attr("id"))
+        .map(|id| {
+            nodes
+                .iter()
+                .find(|(n, _)| n == id)
+                .map_or(id, |(_, name)| name)
+                .to_string()
+        })
+        .collect()
+}
+
+/// Read a `<Signal>` of a message
+fn signal(e: &Element, nodes: &[(String, String)]) -> Result<Signal, String> {
+    let name = e.attr("name").ok_or("Missing name in <Signal>")?;
+    let offset = number(e, "offset")?.ok_or("Missing offset in <Signal>")?;
+    let length = number(e, "length")?.unwrap_or(1);
+    let big_endian = e.attr("endianess") == Some("big");
+    let start = if big_endian {
+        Signal::msb_start(offset)
+    } else {
+        offset
+    };
+    let mut signal = Signal {
+        big_endian,
+        ..Signal::new(name, start, length)
+    };
+    if let Some(value) = e.find("Value") {
+        match value.attr("type").unwrap_or("unsigned") {
+            "unsigned" => {}
+            "signed" => signal.signed = true,
+            t => return Err(format!("Signal {name} has unsupported type {t}")),
+        }
+        signal.factor = decimal(value, "slope", "1")?;
+        signal.offset = decimal(value, "intercept", "0")?;
+        signal.min = decimal(value, "min", "0")?;
+        signal.max = decimal(value, "max", "0")?;
+        signal.unit = value.attr("unit").unwrap_or_default().to_string();
+    }
+    if let Some(consumer) = e.find("Consumer") {
+        signal.receivers = node_refs(consumer, nodes);
+    }
+    for label in e.children("LabelSet").flat_map(|s| s.children("Label")) {
+        let value =
+            number(label, "value")?.ok_or("Missing value in <Label>")?;
+        let name = label.attr("name").ok_or("Missing name in <Label>")?;
+        signal.labels.push((value.to_string(), name.to_string()));
+    }
+    Ok(signal)
+}
+
+/// Convert a KCD network definition to the equivalent DBC text
+pub fn to_dbc(text: &str) -> Result<String, String> {
+    let document = xml::parse(text)?;
+    let root = document
+        .find("NetworkDefinition")
+        .ok_or("Missing <NetworkDefinition>")?;
+    let mut network = Network {
+        version: root
+            .find("Document")
+            .and_then(|d| d.attr("version"))
+            .unwrap_or_default()
+            .to_string(),
+        ..Network::default()
+    };
+
+    // node names by ID
+    let mut nodes = vec![];
+    for node in root.children("Node") {
+        let id = node.attr("id").ok_or("Missing id in <Node>")?;
+        let name = node.attr("name").unwrap_or(id);
+        nodes.push((id.to_string(), name.to_string()));
+        network.nodes.push(name.to_string());
+    }
+
+    for m in root.children("Bus").flat_map(|b| b.children("Message")) {
+        if m.find("Multiplex").is_some() {
+            return Err("Multiplexed signals are not supported".into());
+        }
+        let id = number(m, "id")?.ok_or("Missing id in <Message>")?;
+        let id = u32::try_from(id)
+            .map_err(|_| format!("Invalid message ID {id:#X}"))?;
+        let extended = m.attr("format") == Some("extended");
+        let length = match m.attr("length") {
+            None | Some("auto") => None,
+            Some(_) => number(m, "length")?,
+        };
+        network.messages.push(Message {
+            id: if extended { id | 0x8000_0000 } else { id },
+            name: m
+                .attr("name")
+                .ok_or("Missing name in <Message>")?
+                .to_string(),
+            length,
+            cycle_time: number(m, "interval")?.filter(|i| *i > 0),
+            transmitter: m
+                .find("Producer")
+                .and_then(|p| node_refs(p, &nodes).into_iter().next()),
+            signals: m
+                .children("Signal")
+                .map(|s| signal(s, &nodes))
+                .collect::<Result<_, _>>()?,
+        });
+    }
+    Ok(network.to_dbc())
+}
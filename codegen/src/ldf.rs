@@ -0,0 +1,298 @@
+//! Reading of LIN description files (`.ldf`), mapping the unconditional
+//! frames and their signals to messages and signals
+
+use crate::convert::{Message, Network, Signal};
+
+/// A `name ... ;` statement or `name ... { ... }` block
+enum Item {
+    Statement(Vec<String>),
+    Block(Vec<String>, Vec<Item>),
+}
+
+/// The words, strings and punctuation of an LDF, without comments;
+/// strings keep their quotes, and a `{ ... }` list of values following
+/// a comma (e.g. a byte array's initial value) is a single token
+fn tokens(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens: Vec<String> = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.next_if_eq(&'/').is_some() => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => loop {
+                match chars.next() {
+                    Some('*') if chars.next_if_eq(&'/').is_some() => break,
+                    Some(_) => {}
+                    None => return Err("Unterminated comment".into()),
+                }
+            },
+            '"' => {
+                let mut s = String::from('"');
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => s.push(c),
+                        None => return Err("Unterminated string".into()),
+                    }
+                }
+                s.push('"');
+                tokens.push(s);
+            }
+            '{' if tokens.last().is_some_and(|t| t == ",") => {
+                let mut s = String::from('{');
+                for c in chars.by_ref() {
+                    s.push(c);
+                    if c == '}' {
+                        break;
+                    }
+                }
+                tokens.push(s);
+            }
+            '{' | '}' | ';' | ',' | ':' | '=' => tokens.push(c.to_string()),
+            c if c.is_whitespace() => {}
+            c => {
+                let mut s = String::from(c);
+                while let Some(c) = chars.next_if(|c| {
+                    !c.is_whitespace() && !"{};,:=\"/".contains(*c)
+                }) {
+                    s.push(c);
+                }
+                tokens.push(s);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Group tokens into statements and blocks, up to the end of the
+/// enclosing block if `nested`, or else the end of the file
+fn items(
+    tokens: &mut std::vec::IntoIter<String>,
+    nested: bool,
+) -> Result<Vec<Item>, String> {
+    let mut parsed = vec![];
+    let mut current = vec![];
+    while let Some(token) = tokens.next() {
+        match token.as_str() {
+            ";" => parsed.push(Item::Statement(std::mem::take(&mut current))),
+            "{" => {
+                let children = items(tokens, true)?;
+                parsed
+                    .push(Item::Block(std::mem::take(&mut current), children));
+            }
+            "}" if nested => return Ok(parsed),
+            "}" => return Err("Unexpected }".into()),
+            _ => current.push(token),
+        }
+    }
+    if nested {
+        return Err("Unterminated block".into());
+    }
+    Ok(parsed)
+}
+
+/// The words of a statement or block header, without punctuation
+fn words(tokens: &[String]) -> Vec<&str> {
+    tokens
+        .iter()
+        .map(String::as_str)
+        .filter(|t| !matches!(*t, "," | ":" | "="))
+        .collect()
+}
+
+fn number(value: &str) -> Result<u64, String> {
+    match value.strip_prefix("0x").or(value.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|_| format!("Invalid number {value}"))
+}
+
+/// A decimal number, which is checked but kept as written
+fn decimal(value: &str) -> Result<String, String> {
+    value
+        .parse::<f64>()
+        .map(|_| value.to_string())
+        .map_err(|_| format!("Invalid number {value}"))
+}
+
+/// The contents of the top-level block with a name
+fn section<'a>(items: &'a [Item], name: &str) -> &'a [Item] {
+    items
+        .iter()
+        .find_map(|item| match item {
+            Item::Block(header, children)
+                if header.first().is_some_and(|h| h == name) =>
+            {
+                Some(children.as_slice())
+            }
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Apply a `Signal_encoding_types` entry to a signal: the first
+/// `physical_value` range gives its scaling, and each
+/// `logical_value` a value description
+#[allow(clippy::cast_precision_loss)]
+fn encode(signal: &mut Signal, values: &[Item]) -> Result<(), String> {
+    let mut scaled = false;
+    for value in values {
+        let Item::Statement(tokens) = value else {
+            continue;
+        };
+        match words(tokens).as_slice() {
+            ["logical_value", raw, text, ..] => {
+                signal.labels.push((
+                    number(raw)?.to_string(),
+                    text.trim_matches('"').to_string(),
+                ));
+            }
+            ["physical_value", min, max, scale, offset, rest @ ..]
+                if !scaled =>
+            {
+                scaled = true;
+                signal.factor = decimal(scale)?;
+                signal.offset = decimal(offset)?;
+                let physical = |raw: &str| -> Result<String, String> {
+                    let raw = number(raw)? as f64;
+                    let scale: f64 = signal.factor.parse().unwrap_or(1.0);
+                    let offset: f64 = signal.offset.parse().unwrap_or(0.0);
+                    Ok((raw * scale + offset).to_string())
+                };
+                signal.min = physical(min)?;
+                signal.max = physical(max)?;
+                if let Some(unit) = rest.first() {
+                    signal.unit = unit.trim_matches('"').to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Convert a LIN description file to the equivalent DBC text; LIN
+/// frame IDs (0 to 63) are used as standard CAN IDs
+#[allow(clippy::too_many_lines)]
+pub fn to_dbc(text: &str) -> Result<String, String> {
+    let items = items(&mut tokens(text)?.into_iter(), false)?;
+    let mut network = Network::default();
+    for item in &items {
+        if let Item::Statement(tokens) = item {
+            if let ["LIN_protocol_version", version] = words(tokens).as_slice()
+            {
+                network.version = version.trim_matches('"').to_string();
+            }
+        }
+    }
+
+    for item in section(&items, "Nodes") {
+        if let Item::Statement(tokens) = item {
+            match words(tokens).as_slice() {
+                ["Master", name, ..] => network.nodes.push((*name).to_string()),
+                ["Slaves", names @ ..] => {
+                    network
+                        .nodes
+                        .extend(names.iter().map(|n| (*n).to_string()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // signal definitions: size, initial value, publisher, subscribers
+    let mut signals = vec![];
+    for item in section(&items, "Signals") {
+        if let Item::Statement(tokens) = item {
+            let fields = words(tokens);
+            let [name, size, initial, _publisher, subscribers @ ..] =
+                fields.as_slice()
+            else {
+                return Err(format!("Invalid signal {}", tokens.join(" ")));
+            };
+            let mut signal = Signal::new(name, 0, number(size)? as usize);
+            // byte arrays have a list of initial values
+            if !initial.starts_with('{') {
+                signal.initial = Some(number(initial)?).filter(|v| *v != 0);
+            }
+            signal.receivers =
+                subscribers.iter().map(|s| (*s).to_string()).collect();
+            signals.push(signal);
+        }
+    }
+
+    let encodings = section(&items, "Signal_encoding_types");
+    for item in section(&items, "Signal_representation") {
+        let Item::Statement(tokens) = item else {
+            continue;
+        };
+        let fields = words(tokens);
+        let [encoding, names @ ..] = fields.as_slice() else {
+            continue;
+        };
+        let values = encodings.iter().find_map(|e| match e {
+            Item::Block(header, values)
+                if header.first().is_some_and(|h| h == encoding) =>
+            {
+                Some(values)
+            }
+            _ => None,
+        });
+        let Some(values) = values else {
+            return Err(format!("Unknown signal encoding {encoding}"));
+        };
+        for signal in signals
+            .iter_mut()
+            .filter(|s| names.contains(&s.name.as_str()))
+        {
+            encode(signal, values)?;
+        }
+    }
+
+    for item in section(&items, "Frames") {
+        let Item::Block(header, children) = item else {
+            continue;
+        };
+        let fields = words(header);
+        let [name, id, publisher, length, ..] = fields.as_slice() else {
+            return Err(format!("Invalid frame {}", header.join(" ")));
+        };
+        let id = number(id)?;
+        if id > 0x3F {
+            return Err(format!("Frame {name} has ID {id}, beyond 63"));
+        }
+        let length = number(length)? as usize;
+        if !(1..=8).contains(&length) {
+            return Err(format!("Frame {name} has length {length}"));
+        }
+        let mut message = Message {
+            id: id as u32,
+            name: (*name).to_string(),
+            length: Some(length),
+            transmitter: Some((*publisher).to_string()),
+            ..Message::default()
+        };
+        for child in children {
+            let Item::Statement(tokens) = child else {
+                continue;
+            };
+            let fields = words(tokens);
+            let [signal, offset] = fields.as_slice() else {
+                continue;
+            };
+            let definition = signals
+                .iter()
+                .find(|s| s.name == *signal)
+                .ok_or_else(|| format!("Unknown signal {signal} of {name}"))?;
+            message.signals.push(Signal {
+                // LIN signals are sent least significant bit first
+                start: number(offset)? as usize,
+                ..definition.clone()
+            });
+        }
+        network.messages.push(message);
+    }
+    Ok(network.to_dbc())
+}
@@ -0,0 +1,195 @@
+//! The code generator of the [`dbc-data`](https://docs.rs/dbc-data)
+//! derive macro, for use from build scripts which prefer to write the
+//! generated code to a file, e.g. to read it without `cargo expand` or
+//! to avoid running the macro on each incremental build.
+//!
+//! The generated code is identical to that of the macro, which is
+//! built on this crate.
+//!
+//! ```
+//! let code = dbc_data_codegen::messages(
+//!     "../tests/example.dbc",
+//!     &["SomeMessage"],
+//! )
+//! .unwrap();
+//! assert!(code.to_string().contains("pub struct SomeMessage"));
+//! ```
+//!
+//! The result would typically be written to `OUT_DIR` and brought in
+//! with `include!(concat!(env!("OUT_DIR"), "/bus.rs"))`; relative DBC
+//! paths are resolved against the directory of the crate being built.
+
+#[cfg(feature = "arxml")]
+mod arxml;
+mod attrs;
+mod compat;
+// not every format uses each part
+#[allow(dead_code)]
+#[cfg(any(
+    feature = "arxml",
+    feature = "dbf",
+    feature = "kcd",
+    feature = "ldf",
+    feature = "sym"
+))]
+mod convert;
+mod database;
+#[cfg(feature = "dbf")]
+mod dbf;
+mod derive;
+mod header;
+#[cfg(feature = "kcd")]
+mod kcd;
+#[cfg(feature = "ldf")]
+mod ldf;
+mod logs;
+mod manifest;
+mod message;
+mod messages;
+mod mux;
+mod naming;
+mod recorder;
+mod reflect;
+mod signal;
+#[cfg(feature = "sym")]
+mod sym;
+mod units;
+mod variants;
+// not every format uses each part
+#[allow(dead_code)]
+#[cfg(any(feature = "kcd", feature = "arxml"))]
+mod xml;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote, parse_str, Attribute, Data, DeriveInput, Ident, Result,
+};
+
+use attrs::{
+    find_attr, has_attr, parse_attr, parse_attrs, parse_int_attr,
+    parse_list_attr, parse_meta_attr, parse_path_list_attr, parse_range,
+};
+use database::{DatabaseDecl, SharedDerive};
+use derive::DeriveData;
+use message::MessageInfo;
+use messages::MessagesDecl;
+use variants::VariantsDecl;
+
+/// Generate the types of the named messages of a DBC file, as
+/// `dbc_messages!` does
+///
+/// # Errors
+///
+/// Fails if the file cannot be read or parsed, or if a message name
+/// is invalid or not in the file.
+pub fn messages(file: &str, names: &[&str]) -> Result<TokenStream> {
+    let names = names
+        .iter()
+        .map(|name| parse_str::<Ident>(name))
+        .collect::<Result<Vec<_>>>()?;
+    let decl: MessagesDecl = parse_quote!(#file, [#(#names),*]);
+    decl.build()
+}
+
+/// Generate the code of `#[derive(DbcData)]` for a struct or enum
+/// given as source text, along with the item itself (less its
+/// `dbc_` attributes) as it is not otherwise part of the crate
+///
+/// # Errors
+///
+/// Fails if the item cannot be parsed, or for the same reasons the
+/// derive macro would.
+pub fn derive(item: &str) -> Result<TokenStream> {
+    let input: DeriveInput = parse_str(item)?;
+    let generated = DeriveData::expand(&input)?;
+
+    let mut item = input.clone();
+    strip_attrs(&mut item.attrs);
+    match &mut item.data {
+        Data::Struct(data) => {
+            for field in &mut data.fields {
+                strip_attrs(&mut field.attrs);
+            }
+        }
+        Data::Enum(data) => {
+            for variant in &mut data.variants {
+                strip_attrs(&mut variant.attrs);
+            }
+        }
+        Data::Union(_) => {}
+    }
+    Ok(quote! {
+        #item
+        #generated
+    })
+}
+
+/// Remove the attributes used by the derive, which are unknown
+/// without it
+fn strip_attrs(attrs: &mut Vec<Attribute>) {
+    attrs.retain(|a| {
+        a.path()
+            .get_ident()
+            .map_or(true, |i| !i.to_string().starts_with("dbc_"))
+    });
+}
+
+/// The expansions of the `dbc-data` macros, which are not otherwise
+/// part of the interface of this crate
+#[doc(hidden)]
+pub mod macros {
+    use super::{
+        DatabaseDecl, DeriveData, DeriveInput, MessagesDecl, Result,
+        SharedDerive, TokenStream, VariantsDecl,
+    };
+    use syn::parse2;
+
+    /// Expand `#[derive(DbcData)]`
+    ///
+    /// # Errors
+    ///
+    /// Fails for an invalid derive, as reported by the macro.
+    pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+        DeriveData::expand(input)
+    }
+
+    /// Expand `dbc_database!`
+    ///
+    /// # Errors
+    ///
+    /// Fails for an invalid declaration, or a DBC file which cannot be
+    /// read or parsed.
+    pub fn database(input: TokenStream) -> Result<TokenStream> {
+        parse2::<DatabaseDecl>(input)?.declare()
+    }
+
+    /// Expand a derive with `#[dbc_use]`, as invoked by the macro
+    /// declared by `dbc_database!`
+    ///
+    /// # Errors
+    ///
+    /// Fails for an invalid derive, as reported by the macro.
+    pub fn shared(input: TokenStream) -> Result<TokenStream> {
+        parse2::<SharedDerive>(input)?.build()
+    }
+
+    /// Expand `dbc_messages!`
+    ///
+    /// # Errors
+    ///
+    /// Fails for an invalid message list, or a DBC file which cannot
+    /// be read or parsed.
+    pub fn messages(input: TokenStream) -> Result<TokenStream> {
+        parse2::<MessagesDecl>(input)?.build()
+    }
+
+    /// Expand `dbc_variants!`
+    ///
+    /// # Errors
+    ///
+    /// Fails for an invalid `enum`.
+    pub fn variants(input: TokenStream) -> Result<TokenStream> {
+        Ok(parse2::<VariantsDecl>(input)?.build())
+    }
+}
@@ -0,0 +1,146 @@
+//! Reading and writing of frames in text logs, for replaying captures
+//! through the generated code on a host
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Generics, Ident};
+
+/// Generate the `<Name>LogFrame` type, parsing and formatting log lines
+fn gen_log_frame(frame: &Ident) -> TokenStream {
+    quote! {
+        /// A CAN frame read from or written to a text log
+        #[automatically_derived]
+        #[allow(dead_code)]
+        #[derive(Clone, Debug, Default, PartialEq, Eq)]
+        pub struct #frame {
+            pub id: u32,
+            pub extended: bool,
+            pub data: Vec<u8>,
+        }
+
+        #[automatically_derived]
+        #[allow(dead_code)]
+        impl #frame {
+            /// Parse a line of `candump -l` output, e.g.
+            /// `(1700000000.000000) can0 12D#750364`, or `None` for
+            /// remote frames and invalid lines; IDs of more than three
+            /// digits are extended
+            pub fn from_candump_line(line: &str) -> Option<Self> {
+                let frame = line.split_whitespace().last()?;
+                let (id, data) = frame.split_once('#')?;
+                let extended = id.len() > 3;
+                let id = u32::from_str_radix(id, 16).ok()?;
+                // CAN FD frames have `##` and a digit of flags
+                let data = match data.strip_prefix('#') {
+                    Some(fd) => fd.get(1..)?,
+                    None => data,
+                };
+                // pairs are sliced by byte, so other text is rejected
+                if data.len() % 2 != 0 || !data.is_ascii() {
+                    return None;
+                }
+                let data = (0..data.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&data[i..i + 2], 16).ok())
+                    .collect::<Option<Vec<u8>>>()?;
+                Some(Self { id, extended, data })
+            }
+
+            /// Parse a classic CAN frame line of a Vector ASC log with
+            /// hexadecimal numbers (`base hex`), e.g.
+            /// `0.010000 1  12D  Rx   d 3 75 03 64`, or `None` for other
+            /// events, remote frames and invalid lines
+            pub fn from_asc_line(line: &str) -> Option<Self> {
+                let mut fields = line.split_whitespace();
+                fields.next()?.parse::<f64>().ok()?;
+                fields.next()?.parse::<u8>().ok()?;
+                let id = fields.next()?;
+                let (id, extended) = match id.strip_suffix(['x', 'X']) {
+                    Some(id) => (id, true),
+                    None => (id, false),
+                };
+                let id = u32::from_str_radix(id, 16).ok()?;
+                fields.next()?;
+                if fields.next()? != "d" {
+                    return None;
+                }
+                let dlc = usize::from_str_radix(fields.next()?, 16).ok()?;
+                let data = fields
+                    .take(dlc)
+                    .map(|b| u8::from_str_radix(b, 16).ok())
+                    .collect::<Option<Vec<u8>>>()?;
+                (data.len() == dlc).then_some(Self { id, extended, data })
+            }
+
+            /// Format the frame as a line of `candump -l` output, given
+            /// its time in seconds and the interface name
+            pub fn to_candump_line(&self, time: f64, interface: &str) -> String {
+                use std::fmt::Write;
+                let mut line = if self.extended {
+                    format!("({time:.6}) {interface} {:08X}#", self.id)
+                } else {
+                    format!("({time:.6}) {interface} {:03X}#", self.id)
+                };
+                for byte in &self.data {
+                    let _ = write!(line, "{byte:02X}");
+                }
+                line
+            }
+        }
+    }
+}
+
+/// Generate `to_candump_line()` for a message which is encoded
+pub fn gen_message_log(dlc: usize) -> TokenStream {
+    quote! {
+        /// Encode the message as a line of `candump -l` output, given
+        /// its time in seconds and the interface name
+        pub fn to_candump_line(&self, time: f64, interface: &str) -> String {
+            use std::fmt::Write;
+            let mut data = [0; #dlc];
+            self.encode_clean(&mut data);
+            let mut line = if Self::EXTENDED {
+                format!("({time:.6}) {interface} {:08X}#", Self::ID)
+            } else {
+                format!("({time:.6}) {interface} {:03X}#", Self::ID)
+            };
+            for byte in data {
+                let _ = write!(line, "{byte:02X}");
+            }
+            line
+        }
+    }
+}
+
+/// Generate the `<Name>LogFrame` type and the functions of the deriving
+/// struct which decode `candump` and ASC log lines with `update()`
+pub fn gen_dispatch_log(name: &Ident, generics: &Generics) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let frame = Ident::new(&format!("{name}LogFrame"), name.span());
+    let message_kind = Ident::new(&format!("{name}MessageKind"), name.span());
+    let log_frame = gen_log_frame(&frame);
+    quote! {
+        #log_frame
+
+        #[automatically_derived]
+        #[allow(dead_code)]
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Decode a `candump -l` log line into the message with the
+            /// matching ID, as for `update()`
+            pub fn update_candump_line(
+                &mut self,
+                line: &str,
+            ) -> Option<#message_kind> {
+                let frame = #frame::from_candump_line(line)?;
+                self.update(frame.id, frame.extended, &frame.data)
+            }
+
+            /// Decode a Vector ASC log line into the message with the
+            /// matching ID, as for `update()`
+            pub fn update_asc_line(&mut self, line: &str) -> Option<#message_kind> {
+                let frame = #frame::from_asc_line(line)?;
+                self.update(frame.id, frame.extended, &frame.data)
+            }
+        }
+    }
+}
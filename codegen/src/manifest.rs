@@ -0,0 +1,175 @@
+//! Machine-readable (JSON) manifest of the generated code
+
+use crate::{signal::SignalInfo, MessageInfo};
+use can_dbc::{ByteOrder, Message, ValDescription, DBC};
+use proc_macro2::Span;
+use std::fmt::Write;
+use std::{env, fs, path::PathBuf};
+use syn::Result;
+
+/// Builds a JSON description of the messages and signals which were
+/// generated, so that external tooling can consume exactly what the
+/// application was built with
+pub struct Manifest {
+    /// The DBC file the code was generated from
+    dbc_file: String,
+    /// JSON objects for each message
+    messages: Vec<String>,
+}
+
+impl Manifest {
+    pub fn new(dbc_file: &str) -> Self {
+        Self {
+            dbc_file: dbc_file.to_string(),
+            messages: vec![],
+        }
+    }
+
+    /// Add a message and its selected signals
+    pub fn add_message(
+        &mut self,
+        message: &MessageInfo,
+        m: &Message,
+        dbc: &DBC,
+        infos: &[SignalInfo],
+    ) {
+        let mut json = String::new();
+        let _ = write!(
+            json,
+            "{{\"name\":{},\"id\":{},\"extended\":{},\"dlc\":{}",
+            quoted(m.message_name()),
+            message.id,
+            message.extended,
+            m.message_size(),
+        );
+        if let Some(c) = message.cycle_time {
+            let _ = write!(json, ",\"cycle_time\":{c}");
+        }
+        if let Some(c) = message.cycle_time_fast {
+            let _ = write!(json, ",\"cycle_time_fast\":{c}");
+        }
+        if let Some(n) = message.repetitions {
+            let _ = write!(json, ",\"repetitions\":{n}");
+        }
+        if let Some(t) = message.timeout {
+            let _ = write!(json, ",\"timeout_time\":{t}");
+        }
+
+        // signal filter as given by #[dbc_signals], or null for all
+        json.push_str(",\"signal_filter\":");
+        let filter = message.signal_filter();
+        if filter.is_empty() {
+            json.push_str("null");
+        } else {
+            json.push_str(&list(filter.iter().map(|s| quoted(s))));
+        }
+
+        json.push_str(",\"signals\":");
+        json.push_str(&list(infos.iter().map(|info| {
+            let descs = MessageInfo::value_descriptions(
+                dbc,
+                *m.message_id(),
+                info.signal.name(),
+            );
+            Self::signal(info, descs.unwrap_or_default())
+        })));
+        json.push('}');
+        self.messages.push(json);
+    }
+
+    /// A signal, with its value table (if any) as `values`
+    fn signal(info: &SignalInfo, descs: &[ValDescription]) -> String {
+        let s = info.signal;
+        let values = if descs.is_empty() {
+            String::new()
+        } else {
+            let values = descs.iter().map(|d| {
+                format!("{{\"value\":{},\"name\":{}}}", d.a(), quoted(d.b()))
+            });
+            format!(",\"values\":{}", list(values))
+        };
+        format!(
+            "{{\"name\":{},\"field\":{},\"type\":{},\"start_bit\":{},\
+             \"width\":{},\"byte_order\":{},\"signed\":{},\"factor\":{},\
+             \"offset\":{},\"min\":{},\"max\":{},\"unit\":{}{values}}}",
+            quoted(s.name()),
+            quoted(&info.ident.to_string()),
+            quoted(&info.ntype.to_string()),
+            info.start,
+            info.width,
+            quoted(if s.byte_order() == &ByteOrder::LittleEndian {
+                "little_endian"
+            } else {
+                "big_endian"
+            }),
+            info.signed,
+            s.factor(),
+            s.offset(),
+            s.min(),
+            s.max(),
+            quoted(s.unit()),
+        )
+    }
+
+    /// The complete JSON document
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"dbc_file\":{},\"messages\":{}}}\n",
+            quoted(&self.dbc_file),
+            list(self.messages.iter().cloned()),
+        )
+    }
+
+    /// Write the manifest to `file`; relative paths are placed in
+    /// `OUT_DIR` (or `CARGO_TARGET_TMPDIR` for integration tests)
+    pub fn write(&self, file: &str, span: Span) -> Result<()> {
+        let path = output_path(file, span)?;
+        fs::write(&path, self.to_json()).map_err(|e| {
+            syn::Error::new(
+                span,
+                format!("Could not write {}: {e}", path.display()),
+            )
+        })
+    }
+}
+
+/// Resolve the path of a generated output file
+pub fn output_path(file: &str, span: Span) -> Result<PathBuf> {
+    let path = PathBuf::from(file);
+    if path.is_absolute() {
+        return Ok(path);
+    }
+    let dir = env::var_os("OUT_DIR")
+        .or_else(|| env::var_os("CARGO_TARGET_TMPDIR"))
+        .ok_or_else(|| {
+            syn::Error::new(
+                span,
+                "OUT_DIR is not set; add a build script to the crate",
+            )
+        })?;
+    Ok(PathBuf::from(dir).join(path))
+}
+
+/// A JSON string literal
+fn quoted(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A JSON array of already-formatted values
+fn list(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
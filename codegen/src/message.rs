@@ -0,0 +1,590 @@
+//! DBC Message information
+
+use crate::{
+    has_attr,
+    naming::{glob_match, identifier, pascal_case},
+    parse_attr,
+};
+use can_dbc::{
+    AttributeDefinition, AttributeValuedForObjectType, MessageId, Signal,
+    Transmitter, ValDescription, DBC,
+};
+use syn::{
+    Attribute, Expr, ExprLit, Field, Ident, Lit, Member, Meta, Path, Type,
+    TypePath, Variant,
+};
+
+#[allow(clippy::struct_excessive_bools)]
+pub struct MessageInfo {
+    pub id: u32,
+    pub extended: bool,
+    pub index: usize,
+    /// Which of the DBC files the message is defined in
+    pub source: usize,
+    pub ident: Ident,
+    /// The name of the message in the DBC, from `#[dbc_message]` or
+    /// else the type name
+    pub name: String,
+    /// The struct field holding the message, if any: named, or the
+    /// index of a tuple struct's field
+    pub field: Option<Member>,
+    /// Whether the field is an array of messages
+    pub is_array: bool,
+    /// The length of the array, when given as a literal
+    pub array_len: Option<usize>,
+    /// Declaration order within the deriving item
+    pub position: usize,
+    pub cycle_time: Option<usize>,
+    /// The cycle time while an event is being repeated, from the
+    /// `GenMsgCycleTimeFast` attribute
+    pub cycle_time_fast: Option<usize>,
+    /// How many times an event is sent at the fast cycle time, from the
+    /// `GenMsgNrOfRepetition` attribute
+    pub repetitions: Option<usize>,
+    /// The time in milliseconds after which the message is missing,
+    /// from the `GenMsgTimeoutTime` attribute
+    pub timeout: Option<usize>,
+    pub start_delay: Option<usize>,
+    /// Whether the message may be sent on events, from its
+    /// `GenMsgSendType` attribute
+    pub event: bool,
+    pub fill_value: Option<usize>,
+    /// Whether the message must be received for `all_received()`
+    pub mandatory: bool,
+    /// Whether to generate an atomic-backed copy of the message
+    pub atomic: bool,
+    /// Whether the message holds its PDU and decodes signals when they
+    /// are read, from `#[dbc_lazy]`
+    pub lazy: bool,
+    /// Whether setters record changed signals for `encode_dirty()`,
+    /// from `#[dbc_dirty]`
+    pub dirty: bool,
+    /// Whether signal fields are private, with accessors, from
+    /// `#[dbc_private]`
+    pub private: bool,
+    /// Whether an array of the message holds each multiplexor page in
+    /// the element it indexes, from `#[dbc_mux_array]`
+    pub mux_array: bool,
+    /// The signal whose value indexes an array of the message, rather
+    /// than consecutive IDs, from `#[dbc_index_signal]` or the
+    /// multiplexor of `#[dbc_mux_array]`
+    pub index_signal: Option<String>,
+    /// Whether the message type is generated by another derive, from
+    /// `#[dbc_extern]`
+    pub external: bool,
+    /// The unit to generate normalized signal accessors for, if any
+    pub normalize: Option<String>,
+    /// Traits to derive for the message, from `#[dbc_derive]` on its
+    /// field or variant, overriding those of the deriving item
+    pub derives: Option<Vec<Path>>,
+    /// Whether to generate decoding functions, unless `#[dbc_mode]`
+    /// is `"tx"`
+    pub decode: bool,
+    /// Whether to generate encoding functions, unless `#[dbc_mode]`
+    /// is `"rx"`
+    pub encode: bool,
+    /// The predicate from `#[dbc_cfg]` under which the message is
+    /// generated, if any
+    pub cfg: Option<Meta>,
+    /// Whether signal fields are named in `snake_case`, as given by
+    /// `#[dbc_rename_all]`
+    pub rename: bool,
+    signal_list: Vec<String>,
+    /// Field names given to signals by `#[dbc_signals]`, by DBC name
+    signal_fields: Vec<(String, String)>,
+    /// Signals excluded by `#[dbc_signals_except]`
+    signal_except: Vec<String>,
+}
+
+impl MessageInfo {
+    pub fn from_enum_variant(
+        dbc: &DBC,
+        variant: &Variant,
+        rename: bool,
+    ) -> Option<Self> {
+        Self::new(dbc, &variant.ident, &variant.attrs, rename)
+    }
+
+    /// The type of the message held by a struct field, which is either
+    /// the message or an array of them; messages take no generic
+    /// arguments, so e.g. `Box<Message>` is not one
+    pub fn field_type(ty: &Type) -> syn::Result<&TypePath> {
+        let stype = match ty {
+            Type::Array(a) => &*a.elem,
+            _ => ty,
+        };
+        match stype {
+            Type::Path(v)
+                if v.path
+                    .segments
+                    .last()
+                    .is_some_and(|s| s.arguments.is_empty()) =>
+            {
+                Ok(v)
+            }
+            _ => Err(syn::Error::new_spanned(ty, "unsupported field type")),
+        }
+    }
+
+    /// Information for the message held by a struct field, given its
+    /// index for the fields of tuple structs
+    pub fn from_struct_field(
+        dbc: &DBC,
+        field: &Field,
+        index: usize,
+        rename: bool,
+    ) -> Option<Self> {
+        let stype = Self::field_type(&field.ty).ok()?;
+        // the type may be named by a path, e.g. within `#[dbc_module]`
+        let ident = &stype.path.segments.last()?.ident;
+        let mut info = Self::new(dbc, ident, &field.attrs, rename)?;
+        info.field = Some(match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(index.into()),
+        });
+        if let Type::Array(a) = &field.ty {
+            info.is_array = true;
+            if let Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }) = &a.len
+            {
+                info.array_len = len.base10_parse().ok();
+            }
+        }
+        Some(info)
+    }
+
+    /// Information for a message by its DBC name, which is converted
+    /// to `PascalCase` for the type name when renaming
+    pub fn from_dbc_name(dbc: &DBC, name: &str, rename: bool) -> Option<Self> {
+        let ident = if rename {
+            pascal_case(name)
+        } else {
+            name.to_string()
+        };
+        let ident = syn::parse_str::<Ident>(&identifier(&ident)).ok()?;
+        let mut info = Self::new(dbc, &ident, &[], rename)?;
+        info.name = name.to_string();
+        Some(info)
+    }
+
+    /// The names of the messages transmitted by a node, from their
+    /// `BO_` definition or `BO_TX_BU_`
+    pub fn transmitted_by<'d>(dbc: &'d DBC, node: &str) -> Vec<&'d str> {
+        let sends = |t: &Transmitter| matches!(t, Transmitter::NodeName(n) if n == node);
+        dbc.messages()
+            .iter()
+            .filter(|m| {
+                sends(m.transmitter())
+                    || dbc.message_transmitters().iter().any(|t| {
+                        t.message_id() == m.message_id()
+                            && t.transmitter().iter().any(sends)
+                    })
+            })
+            .map(|m| m.message_name().as_str())
+            .collect()
+    }
+
+    /// The names of the messages with IDs in an inclusive range
+    pub fn in_id_range(dbc: &DBC, first: u64, last: u64) -> Vec<&str> {
+        dbc.messages()
+            .iter()
+            .filter(|m| {
+                let id = match *m.message_id() {
+                    MessageId::Standard(id) => u64::from(id),
+                    MessageId::Extended(id) => u64::from(id),
+                };
+                (first..=last).contains(&id)
+            })
+            .map(|m| m.message_name().as_str())
+            .collect()
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn new(
+        dbc: &DBC,
+        ident: &Ident,
+        attrs: &[Attribute],
+        rename: bool,
+    ) -> Option<Self> {
+        let name = parse_attr(attrs, "dbc_message");
+        let exact = name.is_some() || !rename;
+        let name = name.unwrap_or_else(|| ident.to_string());
+
+        // with #[dbc_rename_all], type names are also matched against
+        // DBC names converted to `PascalCase`
+        let messages = dbc.messages();
+        let index = messages
+            .iter()
+            .position(|m| m.message_name() == &name)
+            .or_else(|| {
+                if exact {
+                    return None;
+                }
+                messages
+                    .iter()
+                    .position(|m| pascal_case(m.message_name()) == name)
+            })?;
+        let message = &messages[index];
+        let name = message.message_name().clone();
+        let id = message.message_id();
+        let (id32, extended) = match *id {
+            MessageId::Standard(id) => (u32::from(id), false),
+            MessageId::Extended(id) => (id, true),
+        };
+
+        let cycle_time = Self::message_attr_value(dbc, *id, "GenMsgCycleTime");
+        let cycle_time_fast =
+            Self::message_attr_value(dbc, *id, "GenMsgCycleTimeFast");
+        let repetitions =
+            Self::message_attr_value(dbc, *id, "GenMsgNrOfRepetition");
+        let timeout = Self::message_attr_value(dbc, *id, "GenMsgTimeoutTime");
+
+        let start_delay =
+            Self::message_attr_value(dbc, *id, "GenMsgStartDelayTime");
+
+        let fill_value = Self::message_attr_value(dbc, *id, "GenMsgFillValue");
+
+        let event = Self::message_enum_attr(dbc, *id, "GenMsgSendType")
+            .is_some_and(|t| {
+                ["Event", "Spontan", "IfActive"]
+                    .iter()
+                    .any(|e| t.contains(e))
+            });
+
+        let mux_array = has_attr(attrs, "dbc_mux_array");
+        let index_signal = if mux_array {
+            Self::multiplexor(dbc, *id)
+        } else {
+            parse_attr(attrs, "dbc_index_signal")
+        };
+
+        let mut signal_list: Vec<String> = vec![];
+        let mut signal_fields = vec![];
+        if let Some(attrs) = parse_attr(attrs, "dbc_signals") {
+            let list = attrs.split(',');
+            for name in list {
+                let name = match name.split_once(" as ") {
+                    Some((name, field)) => {
+                        signal_fields.push((
+                            name.trim().to_string(),
+                            field.trim().to_string(),
+                        ));
+                        name
+                    }
+                    None => name,
+                };
+                signal_list.push(name.trim().to_string());
+            }
+        }
+        let signal_except = parse_attr(attrs, "dbc_signals_except")
+            .map(|except| {
+                except
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            id: id32,
+            extended,
+            index,
+            source: 0,
+            ident: ident.clone(),
+            name,
+            field: None,
+            is_array: false,
+            array_len: None,
+            position: 0,
+            cycle_time,
+            cycle_time_fast,
+            repetitions,
+            timeout,
+            start_delay,
+            event,
+            fill_value,
+            mandatory: has_attr(attrs, "dbc_mandatory"),
+            atomic: has_attr(attrs, "dbc_atomic"),
+            lazy: has_attr(attrs, "dbc_lazy"),
+            dirty: has_attr(attrs, "dbc_dirty"),
+            private: has_attr(attrs, "dbc_private"),
+            mux_array,
+            index_signal,
+            external: has_attr(attrs, "dbc_extern"),
+            normalize: None,
+            derives: None,
+            decode: true,
+            encode: true,
+            cfg: None,
+            rename,
+            signal_list,
+            signal_fields,
+            signal_except,
+        })
+    }
+
+    /// The name of the multiplexor signal of a message, unless it has
+    /// none or several
+    fn multiplexor(dbc: &DBC, id: MessageId) -> Option<String> {
+        let switch = dbc.message_multiplexor_switch(id).ok()??;
+        Some(switch.name().clone())
+    }
+
+    /// The number of IDs taken by the message's field: one for each
+    /// element of an array, unless they are indexed by a signal, or
+    /// `None` if the length of the array is unknown
+    pub fn id_count(&self) -> Option<u32> {
+        if self.is_array && self.index_signal.is_none() {
+            self.array_len.map(|len| len as u32)
+        } else {
+            Some(1)
+        }
+    }
+
+    pub fn use_signal(&self, name: impl Into<String>) -> bool {
+        let name = name.into();
+        if self.signal_except.iter().any(|p| glob_match(p, &name)) {
+            return false;
+        }
+        if self.signal_list.is_empty() {
+            return true;
+        }
+        self.signal_list.iter().any(|p| glob_match(p, &name))
+    }
+
+    /// The first name or pattern in `#[dbc_signals]` or
+    /// `#[dbc_signals_except]` which matches none of the given
+    /// signals, if any
+    pub fn unmatched_pattern<'s>(
+        &'s self,
+        signals: &[Signal],
+    ) -> Option<&'s str> {
+        self.signal_list
+            .iter()
+            .chain(&self.signal_except)
+            .find(|p| !signals.iter().any(|s| glob_match(p, s.name())))
+            .map(String::as_str)
+    }
+
+    /// Whether both `#[dbc_signals]` and `#[dbc_signals_except]` are
+    /// given
+    pub fn signals_conflict(&self) -> bool {
+        !self.signal_list.is_empty() && !self.signal_except.is_empty()
+    }
+
+    /// The field name given to a signal by `#[dbc_signals]`, if any
+    pub fn signal_field(&self, name: &str) -> Option<&str> {
+        self.signal_fields
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, field)| field.as_str())
+    }
+
+    /// The field names given by `#[dbc_signals]`
+    pub fn signal_fields(&self) -> impl Iterator<Item = &str> {
+        self.signal_fields.iter().map(|(_, field)| field.as_str())
+    }
+
+    /// The signal names given by `#[dbc_signals]`, empty if all
+    /// signals are used
+    pub fn signal_filter(&self) -> &[String] {
+        &self.signal_list
+    }
+
+    // TODO: revisit this to handle type conversion better; we
+    // expect that the value fits in a usize for e.g. GenMsgCycleTime
+    fn attr_value(v: &can_dbc::AttributeValue) -> usize {
+        use can_dbc::AttributeValue as AV;
+        match v {
+            AV::AttributeValueU64(x) => *x as usize,
+            AV::AttributeValueI64(x) => *x as usize,
+            AV::AttributeValueF64(x) => *x as usize,
+            AV::AttributeValueCharString(_) => 0usize, // TODO: parse as int?
+        }
+    }
+
+    /// The default value of an attribute, as given by `BA_DEF_DEF_`
+    pub fn attr_default(dbc: &DBC, name: &str) -> Option<usize> {
+        dbc.attribute_defaults()
+            .iter()
+            .find(|d| d.attribute_name() == name)
+            .map(|d| Self::attr_value(d.attribute_value()))
+    }
+
+    fn message_attr<'d>(
+        dbc: &'d DBC,
+        id: MessageId,
+        name: &str,
+    ) -> Option<&'d can_dbc::AttributeValue> {
+        for attr in dbc.attribute_values() {
+            let value = attr.attribute_value();
+            if let AttributeValuedForObjectType::MessageDefinitionAttributeValue(aid, Some(av)) = value {
+                if aid == &id && attr.attribute_name() == name {
+                    return Some(av);
+                }
+            }
+        }
+        None
+    }
+
+    /// The value descriptions of a signal, from its `VAL_` entry or
+    /// else the global `VAL_TABLE_` named by its signal type (from
+    /// `SIG_TYPE_REF_` and `SGTYPE_`)
+    pub fn value_descriptions<'d>(
+        dbc: &'d DBC,
+        id: MessageId,
+        signal: &str,
+    ) -> Option<&'d [ValDescription]> {
+        if let Some(descs) = dbc.value_descriptions_for_signal(id, signal) {
+            return Some(descs);
+        }
+        let type_name = dbc
+            .signal_type_refs()
+            .iter()
+            .find(|r| r.message_id() == &id && r.signal_name() == signal)?
+            .signal_type_name();
+        let table = dbc
+            .signal_types()
+            .iter()
+            .find(|t| t.signal_type_name() == type_name)?
+            .value_table();
+        dbc.value_tables()
+            .iter()
+            .find(|t| t.value_table_name() == table)
+            .map(|t| t.value_descriptions().as_slice())
+    }
+
+    /// The raw start value of a signal, from its `GenSigStartValue`
+    /// attribute or the attribute's default
+    pub fn signal_start_value(
+        dbc: &DBC,
+        id: MessageId,
+        signal: &str,
+    ) -> Option<f64> {
+        Self::signal_attr_value(dbc, id, signal, "GenSigStartValue")
+    }
+
+    /// The raw value of a signal used when its message times out, from
+    /// its `GenSigTimeoutValue` attribute or the attribute's default
+    pub fn signal_timeout_value(
+        dbc: &DBC,
+        id: MessageId,
+        signal: &str,
+    ) -> Option<f64> {
+        Self::signal_attr_value(dbc, id, signal, "GenSigTimeoutValue")
+    }
+
+    /// The value of a numeric signal attribute, or the attribute's
+    /// default
+    fn signal_attr_value(
+        dbc: &DBC,
+        id: MessageId,
+        signal: &str,
+        attr_name: &str,
+    ) -> Option<f64> {
+        use can_dbc::AttributeValue as AV;
+        let value = dbc
+            .attribute_values()
+            .iter()
+            .find_map(|attr| match attr.attribute_value() {
+                AttributeValuedForObjectType::SignalAttributeValue(
+                    aid,
+                    name,
+                    av,
+                ) if aid == &id
+                    && name == signal
+                    && attr.attribute_name() == attr_name =>
+                {
+                    Some(av)
+                }
+                _ => None,
+            })
+            .or_else(|| {
+                dbc.attribute_defaults()
+                    .iter()
+                    .find(|d| d.attribute_name() == attr_name)
+                    .map(can_dbc::AttributeDefault::attribute_value)
+            })?;
+        #[allow(clippy::cast_precision_loss)]
+        match value {
+            AV::AttributeValueU64(x) => Some(*x as f64),
+            AV::AttributeValueI64(x) => Some(*x as f64),
+            AV::AttributeValueF64(x) => Some(*x),
+            AV::AttributeValueCharString(s) => s.trim().parse().ok(),
+        }
+    }
+
+    fn message_attr_value(
+        dbc: &DBC,
+        id: MessageId,
+        name: &str,
+    ) -> Option<usize> {
+        Self::message_attr(dbc, id, name).map(Self::attr_value)
+    }
+
+    /// The name of a message's `ENUM` attribute value, which is given
+    /// as an index into the values of its `BA_DEF_` definition
+    fn message_enum_attr(
+        dbc: &DBC,
+        id: MessageId,
+        name: &str,
+    ) -> Option<String> {
+        use can_dbc::AttributeValue as AV;
+        let index = match Self::message_attr(dbc, id, name)? {
+            AV::AttributeValueCharString(s) => return Some(s.clone()),
+            v => Self::attr_value(v),
+        };
+        Self::enum_attr_values(dbc, name)?.into_iter().nth(index)
+    }
+
+    /// The names and values of the message attributes defined with
+    /// `BA_DEF_ BO_ "name" ENUM ...`
+    pub fn enum_attr_definitions(dbc: &DBC) -> Vec<(String, Vec<String>)> {
+        dbc.attribute_definitions()
+            .iter()
+            .filter_map(|d| {
+                let AttributeDefinition::Message(def) = d else {
+                    return None;
+                };
+                let (name, values) = def.trim().split_once(' ')?;
+                let values = values.trim().strip_prefix("ENUM")?;
+                Some((
+                    name.trim_matches('"').to_string(),
+                    values
+                        .split(',')
+                        .map(|v| v.trim().trim_matches('"').to_string())
+                        .collect(),
+                ))
+            })
+            .collect()
+    }
+
+    /// The values of a message `ENUM` attribute definition
+    fn enum_attr_values(dbc: &DBC, name: &str) -> Option<Vec<String>> {
+        Self::enum_attr_definitions(dbc)
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, values)| values)
+    }
+
+    /// The value of a message's `ENUM` attribute, or else the default
+    /// from `BA_DEF_DEF_`, if it is one of the defined values
+    pub fn enum_attr(&self, dbc: &DBC, name: &str) -> Option<String> {
+        use can_dbc::AttributeValue as AV;
+        let id = dbc.messages().get(self.index)?.message_id();
+        let values = Self::enum_attr_values(dbc, name)?;
+        let value = Self::message_enum_attr(dbc, *id, name).or_else(|| {
+            match dbc
+                .attribute_defaults()
+                .iter()
+                .find(|d| d.attribute_name() == name)?
+                .attribute_value()
+            {
+                AV::AttributeValueCharString(s) => Some(s.clone()),
+                v => values.get(Self::attr_value(v)).cloned(),
+            }
+        })?;
+        values.contains(&value).then_some(value)
+    }
+}
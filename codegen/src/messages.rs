@@ -0,0 +1,51 @@
+//! Generation of message types without a deriving item
+
+use crate::derive::DeriveData;
+use proc_macro2::TokenStream;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    DeriveInput, Ident, LitStr, Result, Token,
+};
+
+/// The input to `dbc_messages!`: `"file.dbc", [Message, ...]`
+pub struct MessagesDecl {
+    file: LitStr,
+    messages: Punctuated<Ident, Token![,]>,
+}
+
+impl Parse for MessagesDecl {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let file: LitStr = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let content;
+        bracketed!(content in input);
+        let messages = content.parse_terminated(Ident::parse, Token![,])?;
+        if messages.is_empty() {
+            return Err(syn::Error::new(
+                file.span(),
+                "Expected at least one message",
+            ));
+        }
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Self { file, messages })
+    }
+}
+
+impl MessagesDecl {
+    /// Generate the message types as a derive on an `enum` of the
+    /// messages would, without the `enum` itself
+    pub fn build(&self) -> Result<TokenStream> {
+        let file = &self.file;
+        let messages = self.messages.iter();
+        let input: DeriveInput = parse_quote! {
+            #[dbc_file = #file]
+            enum DbcMessages {
+                #(#messages,)*
+            }
+        };
+        DeriveData::from(&input)?.without_item().build()
+    }
+}
@@ -0,0 +1,75 @@
+//! Multiplexed messages, whose multiplexor signal selects which of the
+//! other signals (a page) a PDU holds
+
+use crate::signal::SignalInfo;
+use can_dbc::{Message, MultiplexIndicator, DBC};
+use proc_macro2::TokenStream;
+use quote::{quote, TokenStreamExt};
+use syn::Ident;
+
+/// The multiplexor of a message, if it has one among the selected
+/// signals which is an integer, and the values of its pages in order
+pub fn pages<'i, 'a>(
+    dbc: &DBC,
+    m: &Message,
+    infos: &'i [SignalInfo<'a>],
+) -> Option<(&'i SignalInfo<'a>, Vec<u64>)> {
+    // messages with several multiplexors are not supported
+    let switch = dbc.message_multiplexor_switch(*m.message_id()).ok()??;
+    let mux = infos.iter().find(|i| i.signal.name() == switch.name())?;
+    if mux.width == 1 || mux.is_float() {
+        return None;
+    }
+    let mut pages: Vec<u64> = m
+        .signals()
+        .iter()
+        .filter_map(|s| match s.multiplexer_indicator() {
+            MultiplexIndicator::MultiplexedSignal(page)
+            | MultiplexIndicator::MultiplexorAndMultiplexedSignal(page) => {
+                Some(*page)
+            }
+            _ => None,
+        })
+        .collect();
+    pages.sort_unstable();
+    pages.dedup();
+    Some((mux, pages))
+}
+
+/// Generate the `PAGE_<n>` constant of each page, the `PAGES` table
+/// and `active_page()` of a multiplexed message
+#[allow(clippy::cast_precision_loss)]
+pub fn gen_pages(mux: &SignalInfo, pages: &[u64], lazy: bool) -> TokenStream {
+    let ntype = &mux.ntype;
+    let signal = &mux.ident;
+    let mut out = TokenStream::new();
+    let values: Vec<_> =
+        pages.iter().map(|p| mux.const_ident(*p as f64)).collect();
+    for (page, value) in pages.iter().zip(&values) {
+        let name = Ident::new(&format!("PAGE_{page}"), signal.span());
+        let doc = format!("The `{signal}` value selecting page {page}");
+        out.append_all(quote! {
+            #[doc = #doc]
+            pub const #name: #ntype = #value;
+        });
+    }
+    let count = pages.len();
+    let current = if lazy {
+        quote! { self.#signal() }
+    } else {
+        quote! { self.#signal }
+    };
+    let doc = format!("The values of `{signal}` which select a page");
+    out.append_all(quote! {
+        #[doc = #doc]
+        pub const PAGES: [#ntype; #count] = [#(#values),*];
+
+        /// The page of signals held by the message, or `None` if the
+        /// multiplexor does not select a defined page
+        pub fn active_page(&self) -> Option<#ntype> {
+            let page = #current;
+            Self::PAGES.contains(&page).then_some(page)
+        }
+    });
+    out
+}
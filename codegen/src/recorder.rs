@@ -0,0 +1,176 @@
+//! Capture of recent frames for post-mortem analysis
+
+use crate::MessageInfo;
+use proc_macro2::TokenStream;
+use quote::{quote, TokenStreamExt};
+use syn::{Ident, Type};
+
+/// Generate the `<Name>FrameRecorder` ring buffer holding the last
+/// `capacity` frames of the given messages, each up to `max_dlc` bytes,
+/// and its `<Name>RecordedFrame`, given the deriving type's name
+#[allow(clippy::too_many_lines)]
+pub fn gen_recorder(
+    name: &Ident,
+    messages: &[&MessageInfo],
+    capacity: usize,
+    max_dlc: usize,
+    timestamp: &Type,
+) -> TokenStream {
+    let mut arms = TokenStream::new();
+    for message in messages {
+        let id = message.id;
+        let extended = message.extended;
+        let name = message.ident.to_string();
+        if message.is_array && message.index_signal.is_none() {
+            // arrays of unknown length only match their first ID
+            let len = message.array_len.unwrap_or(1) as u32;
+            arms.append_all(quote! {
+                (id, #extended) if id.wrapping_sub(#id) < #len => Some(#name),
+            });
+        } else {
+            arms.append_all(quote! {
+                (#id, #extended) => Some(#name),
+            });
+        }
+    }
+
+    let recorder = Ident::new(&format!("{name}FrameRecorder"), name.span());
+    let frame = Ident::new(&format!("{name}RecordedFrame"), name.span());
+    let frame_doc = format!("A frame captured by a [`{recorder}`]");
+    let recorder_doc = format!(
+        "A ring buffer holding the most recent frames of the messages of \
+         [`{name}`], as a flight recorder for bus issues"
+    );
+    quote! {
+        #[doc = #frame_doc]
+        #[automatically_derived]
+        #[allow(dead_code)]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        pub struct #frame {
+            pub id: u32,
+            pub extended: bool,
+            pub dlc: u8,
+            pub data: [u8; #max_dlc],
+            pub time: #timestamp,
+        }
+
+        #[automatically_derived]
+        #[allow(dead_code)]
+        impl #frame {
+            /// The valid bytes of the frame
+            pub fn data(&self) -> &[u8] {
+                &self.data[..self.dlc as usize]
+            }
+        }
+
+        #[doc = #recorder_doc]
+        #[automatically_derived]
+        #[allow(dead_code)]
+        #[derive(Clone, Debug)]
+        pub struct #recorder {
+            frames: [#frame; #capacity],
+            next: usize,
+            len: usize,
+        }
+
+        #[automatically_derived]
+        #[allow(dead_code)]
+        impl #recorder {
+            /// The number of frames held
+            pub const CAPACITY: usize = #capacity;
+
+            pub fn new() -> Self {
+                Self {
+                    frames: [#frame::default(); #capacity],
+                    next: 0,
+                    len: 0,
+                }
+            }
+
+            /// The name of the derived message with an ID, if any
+            pub fn message_name(id: u32, extended: bool) -> Option<&'static str> {
+                match (id, extended) {
+                    #arms
+                    _ => None,
+                }
+            }
+
+            /// Record a frame, replacing the oldest if full; frames of
+            /// other messages, or which are too long, are ignored
+            pub fn record(
+                &mut self,
+                id: u32,
+                extended: bool,
+                data: &[u8],
+                time: #timestamp,
+            ) -> bool {
+                if data.len() > #max_dlc
+                    || Self::message_name(id, extended).is_none()
+                {
+                    return false;
+                }
+                let frame = &mut self.frames[self.next];
+                frame.id = id;
+                frame.extended = extended;
+                frame.dlc = data.len() as u8;
+                frame.data[..data.len()].copy_from_slice(data);
+                frame.time = time;
+                self.next = (self.next + 1) % #capacity;
+                self.len = (self.len + 1).min(#capacity);
+                true
+            }
+
+            /// The number of frames recorded
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            /// Whether no frames have been recorded
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            /// Discard all recorded frames
+            pub fn clear(&mut self) {
+                self.next = 0;
+                self.len = 0;
+            }
+
+            /// Iterate over the recorded frames, oldest first
+            pub fn iter(&self) -> impl Iterator<Item = &#frame> + '_ {
+                let start = (self.next + #capacity - self.len) % #capacity;
+                (0..self.len).map(move |n| &self.frames[(start + n) % #capacity])
+            }
+
+            /// Write the recorded frames, oldest first, one per line
+            pub fn dump(&self, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+                for frame in self.iter() {
+                    if frame.extended {
+                        write!(w, "{:?} {:08X}x", frame.time, frame.id)?;
+                    } else {
+                        write!(w, "{:?} {:03X} ", frame.time, frame.id)?;
+                    }
+                    write!(
+                        w,
+                        " {} [{}]",
+                        Self::message_name(frame.id, frame.extended)
+                            .unwrap_or("?"),
+                        frame.dlc
+                    )?;
+                    for b in frame.data() {
+                        write!(w, " {b:02X}")?;
+                    }
+                    writeln!(w)?;
+                }
+                Ok(())
+            }
+        }
+
+        #[automatically_derived]
+        impl Default for #recorder {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Descriptions of each message's signals, for code which handles
+//! messages without knowing their types
+
+use crate::signal::SignalInfo;
+use can_dbc::ByteOrder;
+use proc_macro2::{Literal, TokenStream};
+use quote::{quote, TokenStreamExt};
+use syn::Ident;
+
+/// Generate the `<Name>SignalDesc` type; a proc-macro crate cannot
+/// export types, so it is generated alongside the messages, named
+/// after the deriving type
+pub fn gen_signal_desc(desc: &Ident) -> TokenStream {
+    quote! {
+        /// The description of a signal of a message, from the DBC
+        #[automatically_derived]
+        #[allow(dead_code)]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct #desc {
+            /// The DBC signal name
+            pub name: &'static str,
+            /// The DBC start bit: the least significant bit of
+            /// little-endian signals, or the most significant of
+            /// big-endian ones
+            pub start: usize,
+            /// The width in bits
+            pub width: usize,
+            pub big_endian: bool,
+            pub signed: bool,
+            /// The scaling of the raw value: `raw * factor + offset`
+            pub factor: f64,
+            pub offset: f64,
+            /// The DBC unit, which may be empty
+            pub unit: &'static str,
+        }
+    }
+}
+
+/// Generate the `SIGNALS` constant of a message
+pub fn gen_signals(infos: &[SignalInfo], desc: &Ident) -> TokenStream {
+    let descs = infos.iter().map(|info| {
+        let s = info.signal;
+        let name = s.name();
+        let start = info.start;
+        let width = info.width;
+        let big_endian = s.byte_order() == &ByteOrder::BigEndian;
+        let signed = info.signed;
+        let factor = Literal::f64_unsuffixed(*s.factor());
+        let offset = Literal::f64_unsuffixed(*s.offset());
+        let unit = s.unit();
+        quote! {
+            #desc {
+                name: #name,
+                start: #start,
+                width: #width,
+                big_endian: #big_endian,
+                signed: #signed,
+                factor: #factor,
+                offset: #offset,
+                unit: #unit,
+            },
+        }
+    });
+    quote! {
+        /// The description of each signal, in the order of the fields
+        pub const SIGNALS: &[#desc] = &[#(#descs)*];
+    }
+}
+
+/// Generate `get()` and `set()`, accessing signals by their DBC names
+/// as `f64` values; `set()` uses the setters of `dirty` messages, so
+/// that the signals are marked changed
+pub fn gen_access(infos: &[SignalInfo], dirty: bool) -> TokenStream {
+    let mut gets = TokenStream::new();
+    let mut sets = TokenStream::new();
+    for info in infos {
+        let name = info.signal.name();
+        let signal = &info.ident;
+        let ntype = &info.ntype;
+        let (get, set) = match ntype.to_string().as_str() {
+            "bool" => (
+                quote! { f64::from(u8::from(self.#signal)) },
+                quote! { v != 0.0 },
+            ),
+            "f64" => (quote! { self.#signal }, quote! { v }),
+            _ => (quote! { self.#signal as f64 }, quote! { v as #ntype }),
+        };
+        gets.append_all(quote! { #name => #get, });
+        sets.append_all(if dirty {
+            let setter = Ident::new(&format!("set_{signal}"), signal.span());
+            quote! { #name => self.#setter(#set), }
+        } else {
+            quote! { #name => self.#signal = #set, }
+        });
+    }
+    quote! {
+        /// The value of the signal with a DBC name, or `None` if the
+        /// message has no such signal
+        #[allow(
+            unreachable_code,
+            clippy::cast_precision_loss,
+            clippy::cast_lossless
+        )]
+        pub fn get(&self, name: &str) -> Option<f64> {
+            Some(match name {
+                #gets
+                _ => return None,
+            })
+        }
+
+        /// Set the signal with a DBC name, converting the value as for
+        /// an `as` cast; returns `false` if the message has no such
+        /// signal
+        #[allow(
+            unreachable_code,
+            clippy::cast_possible_truncation,
+            clippy::float_cmp
+        )]
+        pub fn set(&mut self, name: &str, v: f64) -> bool {
+            match name {
+                #sets
+                _ => return false,
+            }
+            true
+        }
+    }
+}
@@ -1,11 +1,38 @@
 //! Signal information and codegen
 
-use crate::MessageInfo;
+use crate::{naming, MessageInfo};
 use can_dbc::{ByteOrder, Signal, ValueType};
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use quote::{quote, TokenStreamExt};
 use syn::{parse_quote, Expr, Ident};
 
+/// How scaled values are converted to raw values when encoding, from
+/// `#[dbc_rounding]`
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// To the nearest raw value, with halves away from zero
+    #[default]
+    Nearest,
+    /// Towards zero, as by `as`
+    Truncate,
+    /// Towards negative infinity
+    Floor,
+    /// Towards positive infinity
+    Ceil,
+}
+
+impl Rounding {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "nearest" => Some(Self::Nearest),
+            "truncate" => Some(Self::Truncate),
+            "floor" => Some(Self::Floor),
+            "ceil" => Some(Self::Ceil),
+            _ => None,
+        }
+    }
+}
+
 /// Information about signal within message
 pub struct SignalInfo<'a> {
     /// The DBC signal reference
@@ -26,13 +53,26 @@ pub struct SignalInfo<'a> {
     pub scale: f32,
     /// Indicates signed v.s. unsigned signal
     pub signed: bool,
+    /// The module of the shared helpers from `gen_outlined_helpers()`
+    /// used by multi-bit signals rather than inline code, if any
+    pub outlined: Option<Ident>,
+    /// How scaled values are rounded when encoding
+    pub rounding: Rounding,
+    /// Whether decimal factors and offsets are applied exactly, with
+    /// `f64` arithmetic on integers of the decimal's units
+    pub decimal: bool,
 }
 
 impl<'a> SignalInfo<'a> {
     /// Create signal information
     pub fn new(signal: &'a Signal, message: &MessageInfo) -> Self {
-        // TODO: sanitize and/or change name format
-        let name = signal.name();
+        let name = if let Some(field) = message.signal_field(signal.name()) {
+            field.to_string()
+        } else if message.rename {
+            naming::snake_case_field(signal.name())
+        } else {
+            naming::identifier(signal.name())
+        };
         let signed = matches!(signal.value_type(), ValueType::Signed);
         let width = *signal.signal_size() as usize;
         let scale = *signal.factor() as f32;
@@ -57,7 +97,7 @@ impl<'a> SignalInfo<'a> {
 
         Self {
             signal,
-            ident: Ident::new(name, message.ident.span()),
+            ident: Ident::new(&name, message.ident.span()),
             ntype: Ident::new(ntype, message.ident.span()),
             utype: Ident::new(utype, message.ident.span()),
             start: *signal.start_bit() as usize,
@@ -65,10 +105,27 @@ impl<'a> SignalInfo<'a> {
             signed,
             width,
             nwidth,
+            outlined: None,
+            rounding: Rounding::default(),
+            decimal: false,
+        }
+    }
+
+    /// The index of the last PDU byte holding part of the signal;
+    /// big-endian signals run from their most significant bit towards
+    /// the following bytes
+    pub fn last_byte(&self) -> usize {
+        if self.signal.byte_order() == &ByteOrder::LittleEndian {
+            (self.start + self.width - 1) / 8
+        } else {
+            let first = self.start % 8 + 1;
+            self.start / 8 + (self.width.saturating_sub(first)).div_ceil(8)
         }
     }
 
-    /// Produce an identifier for the DBC f64 value
+    /// Produce a literal of the signal's type for a DBC value, e.g. of
+    /// a value description; negative values of signed signals keep
+    /// their sign
     pub fn const_ident(&self, v: f64) -> Expr {
         if self.is_float() {
             let v = v as f32;
@@ -77,15 +134,43 @@ impl<'a> SignalInfo<'a> {
             let b = v != 0.0;
             parse_quote!(#b)
         } else {
-            let v = v as usize;
-            let t = self.ntype.clone();
-            // TODO: make this less verbose and use type directly
-            parse_quote!(#v as #t)
+            let v = v as i128;
+            let literal = match (self.signed, self.nwidth) {
+                (true, 8) => Literal::i8_suffixed(v as i8),
+                (true, 16) => Literal::i16_suffixed(v as i16),
+                (true, 32) => Literal::i32_suffixed(v as i32),
+                (true, _) => Literal::i64_suffixed(v as i64),
+                (false, 8) => Literal::u8_suffixed(v as u8),
+                (false, 16) => Literal::u16_suffixed(v as u16),
+                (false, 32) => Literal::u32_suffixed(v as u32),
+                (false, _) => Literal::u64_suffixed(v as u64),
+            };
+            parse_quote!(#literal)
+        }
+    }
+
+    /// The initial value of the signal, given its raw start value
+    pub fn initial_value(&self, raw: f64) -> TokenStream {
+        if self.width == 1 {
+            let b = raw != 0.0;
+            quote! { #b }
+        } else if self.is_float() {
+            let v = Literal::f32_unsuffixed(
+                (raw * self.signal.factor() + self.signal.offset()) as f32,
+            );
+            quote! { #v }
+        } else {
+            let v = Literal::i128_suffixed(raw as i128);
+            let t = &self.ntype;
+            quote! { #v as #t }
         }
     }
 
     /// Generate the code for extracting signal bits
     fn extract_bits(&self) -> TokenStream {
+        if let Some(helpers) = &self.outlined {
+            return self.extract_outlined(helpers);
+        }
         let same_width = self.width == self.nwidth;
         let le = self.signal.byte_order() == &ByteOrder::LittleEndian;
         let bit_aligned = if le {
@@ -103,6 +188,42 @@ impl<'a> SignalInfo<'a> {
         }
     }
 
+    /// Code generation calling the shared extraction helper
+    fn extract_outlined(&self, helpers: &Ident) -> TokenStream {
+        let start = self.start;
+        let width = self.width;
+        let utype = &self.utype;
+        let extract = if self.signal.byte_order() == &ByteOrder::LittleEndian {
+            quote! { #helpers::dbc_extract_le }
+        } else {
+            quote! { #helpers::dbc_extract_be }
+        };
+        if self.signed && self.width < 64 {
+            // sign-extend from the top bit of the signal
+            let shift = 64 - self.width;
+            quote! {
+                ((((#extract(pdu, #start, #width) << #shift) as i64) >> #shift)
+                    as #utype)
+            }
+        } else {
+            quote! { (#extract(pdu, #start, #width) as #utype) }
+        }
+    }
+
+    /// Code generation calling the shared insertion helper, for the
+    /// signals which are encoded
+    fn insert_outlined(&self, helpers: &Ident) -> TokenStream {
+        let start = self.start;
+        let width = self.width;
+        if self.signal.byte_order() == &ByteOrder::LittleEndian {
+            quote! { #helpers::dbc_insert_le(pdu, #start, #width, v as u64); }
+        } else if self.can_encode() {
+            quote! { #helpers::dbc_insert_be(pdu, #start, #width, v as u64); }
+        } else {
+            quote! {}
+        }
+    }
+
     /// Code generation for aligned signal bits
     fn extract_aligned(&self, le: bool) -> TokenStream {
         let low = self.start / 8;
@@ -301,25 +422,38 @@ impl<'a> SignalInfo<'a> {
     /// Generate a signal's decoder
     pub fn gen_decoder(&self) -> TokenStream {
         let name = &self.ident;
+        let value = self.gen_value();
+        quote! {
+            self.#name = #value;
+        }
+    }
+
+    /// Generate an expression of the signal's value, decoded from
+    /// `pdu`
+    pub fn gen_value(&self) -> TokenStream {
         if self.width == 1 {
             // boolean
             let byte = self.start / 8;
             let bit = self.start % 8;
             quote! {
-                self.#name = (pdu[#byte] & (1 << #bit)) != 0;
+                (pdu[#byte] & (1 << #bit)) != 0
             }
         } else {
             let value = self.extract_bits();
             let ntype = &self.ntype;
-            if self.is_float() {
+            if let Some((factor, offset, units)) = self.decimal_scaling() {
+                quote! {
+                    (((#value as f64) * #factor + #offset) / #units) as f32
+                }
+            } else if self.is_float() {
                 let scale = self.scale;
                 let offset = *self.signal.offset() as f32;
                 quote! {
-                    self.#name = ((#value as f32) * #scale) + #offset;
+                    ((#value as f32) * #scale) + #offset
                 }
             } else {
                 quote! {
-                    self.#name = #value as #ntype;
+                    #value as #ntype
                 }
             }
         }
@@ -328,6 +462,54 @@ impl<'a> SignalInfo<'a> {
     /// Generate code for encoding a signal value
     pub fn gen_encoder(&self) -> TokenStream {
         let name = &self.ident;
+        self.gen_encode(&quote! { self.#name })
+    }
+
+    /// Generate an expression of the raw value of a scaled signal,
+    /// given an expression of its value; these are computed without
+    /// `std`, so do not use the `f32` rounding methods
+    pub fn gen_raw(&self, value: &TokenStream) -> TokenStream {
+        let utype = &self.utype;
+        let (raw, ftype) = if let Some((factor, offset, units)) =
+            self.decimal_scaling()
+        {
+            (
+                quote! { ((f64::from(#value) * #units - #offset) / #factor) },
+                quote! { f64 },
+            )
+        } else {
+            let scale = self.scale;
+            let offset = *self.signal.offset() as f32;
+            (quote! { ((#value - #offset) / #scale) }, quote! { f32 })
+        };
+        match self.rounding {
+            Rounding::Truncate => quote! { #raw as #utype },
+            Rounding::Nearest => quote! {
+                {
+                    let raw = #raw;
+                    (if raw < 0.0 { raw - 0.5 } else { raw + 0.5 }) as #utype
+                }
+            },
+            Rounding::Floor => quote! {
+                {
+                    let raw = #raw;
+                    let v = raw as #utype;
+                    if (v as #ftype) > raw { v.saturating_sub(1) } else { v }
+                }
+            },
+            Rounding::Ceil => quote! {
+                {
+                    let raw = #raw;
+                    let v = raw as #utype;
+                    if (v as #ftype) < raw { v.saturating_add(1) } else { v }
+                }
+            },
+        }
+    }
+
+    /// Generate code encoding the value of an expression as the
+    /// signal into `pdu`
+    pub fn gen_encode(&self, value: &TokenStream) -> TokenStream {
         let low = self.start / 8;
         let mut byte = low;
         let bit = self.start % 8;
@@ -335,31 +517,31 @@ impl<'a> SignalInfo<'a> {
             // boolean
             quote! {
                 let mask: u8 = (1 << #bit);
-                if self.#name {
+                if #value {
                     pdu[#byte] |= mask;
                 } else {
                     pdu[#byte] &= !mask;
                 }
             }
         } else {
-            let utype = &self.utype;
             let left = self.start % 8;
             // let right = (self.start + self.width) % 8;
             let le = self.signal.byte_order() == &ByteOrder::LittleEndian;
 
             let mut ts = TokenStream::new();
             if self.is_float() {
-                let scale = self.scale;
-                let offset = self.signal.offset as f32;
+                let raw = self.gen_raw(value);
                 ts.append_all(quote! {
-                    let v = ((self.#name - #offset) / #scale) as #utype;
+                    let v = #raw;
                 });
             } else {
                 ts.append_all(quote! {
-                    let v = self.#name;
+                    let v = #value;
                 });
             }
-            if le {
+            if let Some(helpers) = &self.outlined {
+                ts.append_all(self.insert_outlined(helpers));
+            } else if le {
                 if self.width == self.nwidth && left == 0 {
                     // aligned little-endian
                     let mut bits = self.nwidth;
@@ -435,9 +617,175 @@ impl<'a> SignalInfo<'a> {
         }
     }
 
+    /// The PDU bit indices (`byte * 8 + bit`) occupied by the signal,
+    /// starting from its least-significant bit for little-endian
+    /// signals and its most-significant bit for big-endian signals
+    pub fn bit_positions(&self) -> Vec<usize> {
+        if self.signal.byte_order() == &ByteOrder::LittleEndian {
+            (self.start..self.start + self.width).collect()
+        } else {
+            let mut positions = Vec::with_capacity(self.width);
+            let mut bit = self.start;
+            for _ in 0..self.width {
+                positions.push(bit);
+                // Motorola order walks down within a byte and then
+                // continues at the top of the next byte
+                bit = if bit % 8 == 0 { bit + 15 } else { bit - 1 };
+            }
+            positions
+        }
+    }
+
     /// We consider any signal with a scale to be a floating-point
     /// value
     pub fn is_float(&self) -> bool {
         self.scale != 1.0
     }
+
+    /// With `decimal`, the factor and offset of a scaled signal as whole
+    /// numbers of units, and the number of units in one, if both are
+    /// decimals of up to nine places: e.g. a factor of 0.1 and an
+    /// offset of -40 are 1 and -400 units of 10
+    fn decimal_scaling(&self) -> Option<(Literal, Literal, Literal)> {
+        if !self.decimal || !self.is_float() {
+            return None;
+        }
+        // within rounding error of a whole number, relative to its size,
+        // and not rounding a small factor away to nothing
+        let whole = |v: f64| {
+            let r = v.round();
+            ((v - r).abs() <= 1e-12 * v.abs().max(1.0)
+                && (r != 0.0 || v == 0.0)
+                && r.abs() < 2f64.powi(53))
+            .then_some(r)
+        };
+        (0..=9).find_map(|places| {
+            let units = 10f64.powi(places);
+            let factor = whole(self.signal.factor() * units)?;
+            let offset = whole(self.signal.offset() * units)?;
+            Some((
+                Literal::f64_unsuffixed(factor),
+                Literal::f64_unsuffixed(offset),
+                Literal::f64_unsuffixed(units),
+            ))
+        })
+    }
+
+    /// Whether the signal is written by `encode()`, which does not yet
+    /// support unaligned big-endian signals
+    pub fn can_encode(&self) -> bool {
+        self.width == 1
+            || self.signal.byte_order() == &ByteOrder::LittleEndian
+            || (self.width == self.nwidth && self.start % 8 == 7)
+    }
+
+    /// The lowest and highest raw values of the signal: those which fit
+    /// its width, limited to its DBC minimum and maximum when given
+    pub fn raw_range(&self) -> (i128, i128) {
+        let (mut low, mut high) = if self.signed {
+            (
+                -(1i128 << (self.width - 1)),
+                (1i128 << (self.width - 1)) - 1,
+            )
+        } else {
+            (0, (1i128 << self.width) - 1)
+        };
+        let s = self.signal;
+        if s.min() < s.max() && *s.factor() != 0.0 {
+            let raw = |v: f64| (v - s.offset()) / s.factor();
+            let (min, max) = (raw(*s.min()), raw(*s.max()));
+            let (min, max) = if min <= max { (min, max) } else { (max, min) };
+            low = low.max(min.ceil() as i128);
+            high = high.min(max.floor() as i128);
+        }
+        (low, high.max(low))
+    }
+}
+
+/// Generate the extraction and insertion functions shared by outlined
+/// signals, which walk each bit of a signal rather than being
+/// specialized for its position, trading speed for code size; they are
+/// in a module named after the deriving type, so that several derives
+/// in one module may use them
+pub fn gen_outlined_helpers(module: &Ident) -> TokenStream {
+    quote! {
+        mod #module {
+            /// Extract a little-endian signal from a PDU
+            #[inline(never)]
+            #[allow(dead_code)]
+            pub(super) fn dbc_extract_le(
+                pdu: &[u8],
+                start: usize,
+                width: usize,
+            ) -> u64 {
+                let mut v = 0u64;
+                for n in 0..width {
+                    let bit = start + n;
+                    v |= u64::from((pdu[bit / 8] >> (bit % 8)) & 1) << n;
+                }
+                v
+            }
+
+            /// Extract a big-endian signal from a PDU, starting from its
+            /// most significant bit
+            #[inline(never)]
+            #[allow(dead_code)]
+            pub(super) fn dbc_extract_be(
+                pdu: &[u8],
+                start: usize,
+                width: usize,
+            ) -> u64 {
+                let mut v = 0u64;
+                let mut bit = start;
+                for _ in 0..width {
+                    v = (v << 1) | u64::from((pdu[bit / 8] >> (bit % 8)) & 1);
+                    bit = if bit % 8 == 0 { bit + 15 } else { bit - 1 };
+                }
+                v
+            }
+
+            /// Insert the low `width` bits of a value as a little-endian
+            /// signal into a PDU
+            #[inline(never)]
+            #[allow(dead_code)]
+            pub(super) fn dbc_insert_le(
+                pdu: &mut [u8],
+                start: usize,
+                width: usize,
+                v: u64,
+            ) {
+                for n in 0..width {
+                    let bit = start + n;
+                    let mask = 1u8 << (bit % 8);
+                    if (v >> n) & 1 != 0 {
+                        pdu[bit / 8] |= mask;
+                    } else {
+                        pdu[bit / 8] &= !mask;
+                    }
+                }
+            }
+
+            /// Insert the low `width` bits of a value as a big-endian
+            /// signal into a PDU, starting from its most significant bit
+            #[inline(never)]
+            #[allow(dead_code)]
+            pub(super) fn dbc_insert_be(
+                pdu: &mut [u8],
+                start: usize,
+                width: usize,
+                v: u64,
+            ) {
+                let mut bit = start;
+                for n in (0..width).rev() {
+                    let mask = 1u8 << (bit % 8);
+                    if (v >> n) & 1 != 0 {
+                        pdu[bit / 8] |= mask;
+                    } else {
+                        pdu[bit / 8] &= !mask;
+                    }
+                    bit = if bit % 8 == 0 { bit + 15 } else { bit - 1 };
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,245 @@
+//! Reading of PCAN Symbol Editor (`.sym`) files
+
+use crate::convert::{Message, Network, Signal};
+
+/// A line without its `//` comment, which may not start within quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '/' if !quoted && line[i + 1..].starts_with('/') => {
+                return &line[..i];
+            }
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Split on whitespace, except within quotes, which are removed
+fn words(text: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Parse a number, which is hexadecimal if it has an `h` suffix
+fn number(value: &str) -> Result<usize, String> {
+    match value.strip_suffix(['h', 'H']) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|_| format!("invalid number {value}"))
+}
+
+/// Parse an `enum Name(0="A", 1="B")` definition
+fn parse_enum(text: &str) -> Result<(String, Vec<(String, String)>), String> {
+    let (name, values) = text
+        .strip_prefix("enum")
+        .and_then(|t| t.strip_suffix(')'))
+        .and_then(|t| t.split_once('('))
+        .ok_or_else(|| format!("invalid enum {text}"))?;
+    let values = values
+        .split(',')
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| {
+            let (value, label) = v
+                .split_once('=')
+                .ok_or_else(|| format!("invalid enum value {v}"))?;
+            Ok((
+                value.trim().to_string(),
+                label.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok((name.trim().to_string(), values))
+}
+
+/// Build a signal from its name, type, start bit, length and flags
+fn signal(
+    name: &str,
+    ty: &str,
+    start: usize,
+    length: usize,
+    flags: &[String],
+    enums: &[(String, Vec<(String, String)>)],
+) -> Result<Signal, String> {
+    let mut signal = Signal::new(name, start, length);
+    match ty {
+        "unsigned" | "raw" | "char" => {}
+        "bit" => signal.length = 1,
+        "signed" => signal.signed = true,
+        _ => return Err(format!("signal {name} has unsupported type {ty}")),
+    }
+    for flag in flags {
+        if flag == "-m" {
+            signal.big_endian = true;
+            signal.start = Signal::msb_start(start);
+            continue;
+        }
+        let Some((key, value)) =
+            flag.strip_prefix('/').and_then(|f| f.split_once(':'))
+        else {
+            continue;
+        };
+        let decimal = || {
+            value
+                .parse::<f64>()
+                .map(|_| value.to_string())
+                .map_err(|_| format!("invalid {key} {value} of {name}"))
+        };
+        match key {
+            "u" => signal.unit = value.to_string(),
+            "f" => signal.factor = decimal()?,
+            "o" => signal.offset = decimal()?,
+            "min" => signal.min = decimal()?,
+            "max" => signal.max = decimal()?,
+            "e" => {
+                signal.labels.clone_from(
+                    &enums
+                        .iter()
+                        .find(|(n, _)| n == value)
+                        .ok_or_else(|| {
+                            format!("unknown enum {value} of {name}")
+                        })?
+                        .1,
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(signal)
+}
+
+/// Convert a symbol file to the equivalent DBC text
+#[allow(clippy::too_many_lines)]
+pub fn to_dbc(text: &str) -> Result<String, String> {
+    let mut network = Network::default();
+    let mut enums = vec![];
+    // signals of the `{SIGNALS}` section, by name: type, length, flags
+    let mut definitions: Vec<Vec<String>> = vec![];
+    let mut section = String::new();
+    let mut pending = String::new();
+    for (n, line) in text.lines().enumerate() {
+        let err = |e: String| format!("line {}: {e}", n + 1);
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) =
+            line.strip_prefix('{').and_then(|l| l.strip_suffix('}'))
+        {
+            section = name.to_string();
+            continue;
+        }
+
+        if section == "ENUMS" {
+            // definitions may span several lines
+            pending.push_str(line);
+            if pending.ends_with(')') {
+                enums.push(parse_enum(&pending).map_err(err)?);
+                pending.clear();
+            }
+            continue;
+        }
+        if let Some(name) =
+            line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+        {
+            if network.messages.iter().any(|m| m.name == name) {
+                return Err(err(format!(
+                    "multiplexed message {name} is not supported"
+                )));
+            }
+            network.messages.push(Message {
+                name: name.to_string(),
+                ..Message::default()
+            });
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let words = words(value);
+        if section == "SIGNALS" {
+            if key == "Sig" {
+                definitions.push(words);
+            }
+            continue;
+        }
+        let Some(message) = network.messages.last_mut() else {
+            continue;
+        };
+        match key {
+            "ID" => {
+                let id = number(value.trim()).map_err(err)?;
+                message.id |= u32::try_from(id)
+                    .map_err(|_| err(format!("invalid ID {value}")))?;
+            }
+            "Type" if value.trim().eq_ignore_ascii_case("extended") => {
+                message.id |= 0x8000_0000;
+            }
+            "DLC" => message.length = Some(number(value.trim()).map_err(err)?),
+            "CycleTime" => {
+                message.cycle_time =
+                    Some(number(value.trim()).map_err(err)?).filter(|c| *c > 0);
+            }
+            "Mux" => {
+                return Err(err(format!(
+                    "multiplexed message {} is not supported",
+                    message.name
+                )));
+            }
+            "Var" => {
+                let [name, ty, position, flags @ ..] = words.as_slice() else {
+                    return Err(err(format!("invalid variable {value}")));
+                };
+                let (start, length) =
+                    position.split_once(',').ok_or_else(|| {
+                        err(format!("invalid position {position}"))
+                    })?;
+                let start = number(start).map_err(err)?;
+                let length = number(length).map_err(err)?;
+                message.signals.push(
+                    signal(name, ty, start, length, flags, &enums)
+                        .map_err(err)?,
+                );
+            }
+            "Sig" => {
+                let [name, start, ..] = words.as_slice() else {
+                    return Err(err(format!("invalid signal {value}")));
+                };
+                let Some([_, ty, length, flags @ ..]) = definitions
+                    .iter()
+                    .find(|d| d.first() == Some(name))
+                    .map(Vec::as_slice)
+                else {
+                    return Err(err(format!("unknown signal {name}")));
+                };
+                let start = number(start).map_err(err)?;
+                let length = number(length).map_err(err)?;
+                message.signals.push(
+                    signal(name, ty, start, length, flags, &enums)
+                        .map_err(err)?,
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(network.to_dbc())
+}
@@ -0,0 +1,190 @@
+//! Physical unit conversions
+
+/// A unit of measure, as `value * scale + offset` in the base unit of
+/// its dimension
+struct Unit {
+    names: &'static [&'static str],
+    dimension: &'static str,
+    scale: f64,
+    offset: f64,
+}
+
+const UNITS: &[Unit] = &[
+    // speed, in m/s
+    Unit {
+        names: &["m/s", "mps"],
+        dimension: "speed",
+        scale: 1.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["km/h", "kph", "kmh"],
+        dimension: "speed",
+        scale: 1.0 / 3.6,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["mph", "mi/h"],
+        dimension: "speed",
+        scale: 0.447_04,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["kn", "kt", "knots"],
+        dimension: "speed",
+        scale: 1852.0 / 3600.0,
+        offset: 0.0,
+    },
+    // distance, in m
+    Unit {
+        names: &["m"],
+        dimension: "distance",
+        scale: 1.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["mm"],
+        dimension: "distance",
+        scale: 0.001,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["cm"],
+        dimension: "distance",
+        scale: 0.01,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["km"],
+        dimension: "distance",
+        scale: 1000.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["mi"],
+        dimension: "distance",
+        scale: 1609.344,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["ft"],
+        dimension: "distance",
+        scale: 0.3048,
+        offset: 0.0,
+    },
+    // temperature, in K
+    Unit {
+        names: &["K"],
+        dimension: "temperature",
+        scale: 1.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["°C", "degC", "C"],
+        dimension: "temperature",
+        scale: 1.0,
+        offset: 273.15,
+    },
+    Unit {
+        names: &["°F", "degF", "F"],
+        dimension: "temperature",
+        scale: 5.0 / 9.0,
+        offset: 273.15 - 32.0 * 5.0 / 9.0,
+    },
+    // angle, in rad
+    Unit {
+        names: &["rad"],
+        dimension: "angle",
+        scale: 1.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["deg", "°"],
+        dimension: "angle",
+        scale: std::f64::consts::PI / 180.0,
+        offset: 0.0,
+    },
+    // pressure, in Pa
+    Unit {
+        names: &["Pa"],
+        dimension: "pressure",
+        scale: 1.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["kPa"],
+        dimension: "pressure",
+        scale: 1000.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["bar"],
+        dimension: "pressure",
+        scale: 100_000.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["mbar", "hPa"],
+        dimension: "pressure",
+        scale: 100.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["psi"],
+        dimension: "pressure",
+        scale: 6_894.757,
+        offset: 0.0,
+    },
+    // time, in s
+    Unit {
+        names: &["s"],
+        dimension: "time",
+        scale: 1.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["ms"],
+        dimension: "time",
+        scale: 0.001,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["us", "µs"],
+        dimension: "time",
+        scale: 0.000_001,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["min"],
+        dimension: "time",
+        scale: 60.0,
+        offset: 0.0,
+    },
+    Unit {
+        names: &["h"],
+        dimension: "time",
+        scale: 3600.0,
+        offset: 0.0,
+    },
+];
+
+fn find(name: &str) -> Option<&'static Unit> {
+    let name = name.trim();
+    UNITS.iter().find(|u| u.names.contains(&name))
+}
+
+/// Whether a unit name is known
+pub fn is_known(name: &str) -> bool {
+    find(name).is_some()
+}
+
+/// The `(scale, offset)` converting a value in unit `from` to unit
+/// `to`, if both are known and of the same dimension
+pub fn conversion(from: &str, to: &str) -> Option<(f64, f64)> {
+    let from = find(from)?;
+    let to = find(to)?;
+    if from.dimension != to.dimension {
+        return None;
+    }
+    Some((from.scale / to.scale, (from.offset - to.offset) / to.scale))
+}
@@ -0,0 +1,109 @@
+//! Runtime selection between database variants
+
+use proc_macro2::TokenStream;
+use quote::{quote, TokenStreamExt};
+use syn::{
+    parse::{Parse, ParseStream},
+    spanned::Spanned,
+    Fields, ItemEnum, Result,
+};
+
+/// The input to `dbc_variants!`: an `enum` whose variants each hold
+/// a struct deriving `DbcData` from one of the database variants
+pub struct VariantsDecl {
+    item: ItemEnum,
+}
+
+impl Parse for VariantsDecl {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let item: ItemEnum = input.parse()?;
+        if item.variants.is_empty() {
+            return Err(syn::Error::new(
+                item.ident.span(),
+                "Expected at least one database variant",
+            ));
+        }
+        for variant in &item.variants {
+            let single = match &variant.fields {
+                Fields::Unnamed(f) => f.unnamed.len() == 1,
+                _ => false,
+            };
+            if !single {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    "Expected a variant holding one DbcData struct",
+                ));
+            }
+        }
+        Ok(Self { item })
+    }
+}
+
+impl VariantsDecl {
+    /// Generate the `enum`, the fieldless `Variant` used to select it
+    /// and the variant-aware dispatcher
+    pub fn build(&self) -> TokenStream {
+        let item = &self.item;
+        let name = &item.ident;
+        let vis = &item.vis;
+
+        let mut variants = TokenStream::new();
+        let mut new_arms = TokenStream::new();
+        let mut variant_arms = TokenStream::new();
+        let mut update_arms = TokenStream::new();
+        for variant in &item.variants {
+            let ident = &variant.ident;
+            variants.append_all(quote! { #ident, });
+            new_arms.append_all(quote! {
+                Variant::#ident => #name::#ident(Default::default()),
+            });
+            variant_arms.append_all(quote! {
+                #name::#ident(_) => Variant::#ident,
+            });
+            update_arms.append_all(quote! {
+                #name::#ident(m) => m.update(id, extended, data).is_some(),
+            });
+        }
+
+        let doc = format!("The database variants of [`{name}`]");
+        quote! {
+            #item
+
+            #[automatically_derived]
+            #[allow(non_camel_case_types)]
+            #[allow(dead_code)]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            #[doc = #doc]
+            #vis enum Variant {
+                #variants
+            }
+
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #name {
+                /// The default messages of the given database variant
+                pub fn new(variant: Variant) -> Self {
+                    match variant {
+                        #new_arms
+                    }
+                }
+
+                /// The database variant in use
+                pub fn variant(&self) -> Variant {
+                    match self {
+                        #variant_arms
+                    }
+                }
+
+                /// Decode a received frame into the matching message of
+                /// the variant in use, returning whether it was decoded
+                pub fn update(&mut self, id: u32, extended: bool, data: &[u8])
+                              -> bool {
+                    match self {
+                        #update_arms
+                    }
+                }
+            }
+        }
+    }
+}
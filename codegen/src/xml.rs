@@ -0,0 +1,154 @@
+//! A minimal XML reader for the network formats based on it, which
+//! only needs elements, attributes and text
+
+/// An XML element, with its attributes, text and child elements
+#[derive(Default)]
+pub struct Element {
+    pub name: String,
+    attrs: Vec<(String, String)>,
+    /// The text directly within the element, trimmed
+    pub text: String,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    /// The value of an attribute
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The child elements with a name
+    pub fn children<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// The first element along a `/`-separated path of child names
+    pub fn find<'a>(&'a self, path: &str) -> Option<&'a Element> {
+        path.split('/').try_fold(self, |e, name| {
+            e.children.iter().find(|c| c.name == name)
+        })
+    }
+
+    /// The text of the first element along a path, if not empty
+    pub fn text(&self, path: &str) -> Option<&str> {
+        self.find(path)
+            .map(|e| e.text.as_str())
+            .filter(|t| !t.is_empty())
+    }
+
+    /// Each element with a name within this one, at any depth
+    pub fn descendants<'a>(&'a self, name: &str, out: &mut Vec<&'a Element>) {
+        for child in &self.children {
+            if child.name == name {
+                out.push(child);
+            }
+            child.descendants(name, out);
+        }
+    }
+}
+
+/// Replace the predefined XML entities
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Parse XML into an element holding the document's root element,
+/// skipping comments and declarations
+pub fn parse(xml: &str) -> Result<Element, String> {
+    let mut stack = vec![Element::default()];
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        let text = rest[..start].trim();
+        if !text.is_empty() {
+            if let Some(e) = stack.last_mut() {
+                e.text.push_str(&unescape(text));
+            }
+        }
+        rest = &rest[start..];
+        if let Some((open, close)) =
+            [("<!--", "-->"), ("<?", "?>"), ("<!", ">")]
+                .into_iter()
+                .find(|(open, _)| rest.starts_with(open))
+        {
+            let end = rest
+                .find(close)
+                .ok_or_else(|| format!("Unterminated {open}"))?;
+            rest = &rest[end + close.len()..];
+            continue;
+        }
+
+        if let Some(r) = rest.strip_prefix("</") {
+            let end = r.find('>').ok_or("Unterminated tag")?;
+            let name = r[..end].trim();
+            let element = stack
+                .pop()
+                .filter(|e| e.name == name)
+                .ok_or_else(|| format!("Unexpected </{name}>"))?;
+            stack
+                .last_mut()
+                .ok_or_else(|| format!("Unexpected </{name}>"))?
+                .children
+                .push(element);
+            rest = &r[end + 1..];
+            continue;
+        }
+
+        let mut s = &rest[1..];
+        let len = s
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .ok_or("Unterminated tag")?;
+        let mut element = Element {
+            name: s[..len].to_string(),
+            ..Element::default()
+        };
+        s = &s[len..];
+        loop {
+            s = s.trim_start();
+            if let Some(r) = s.strip_prefix("/>") {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+                rest = r;
+                break;
+            }
+            if let Some(r) = s.strip_prefix('>') {
+                stack.push(element);
+                rest = r;
+                break;
+            }
+            let name = &element.name;
+            let (attr, r) = s
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid attribute in <{name}>"))?;
+            let r = r.trim_start();
+            let quote = r
+                .chars()
+                .next()
+                .filter(|c| *c == '"' || *c == '\'')
+                .ok_or_else(|| format!("Unquoted attribute in <{name}>"))?;
+            let (value, r) = r[1..]
+                .split_once(quote)
+                .ok_or_else(|| format!("Unterminated attribute in <{name}>"))?;
+            element
+                .attrs
+                .push((attr.trim().to_string(), unescape(value)));
+            s = r;
+        }
+    }
+    match stack.pop() {
+        Some(document) if stack.is_empty() => Ok(document),
+        Some(e) => Err(format!("Unterminated <{}>", e.name)),
+        None => Err("Unexpected end of document".into()),
+    }
+}
@@ -0,0 +1,274 @@
+#[test]
+fn messages() {
+    let code =
+        dbc_data_codegen::messages("../tests/test.dbc", &["MiscMessage"])
+            .unwrap()
+            .to_string();
+    assert!(code.contains("pub struct MiscMessage"));
+    assert!(!code.contains("WheelSpeedA"));
+}
+
+#[test]
+fn derive() {
+    let code = dbc_data_codegen::derive(
+        r#"
+        #[derive(Default)]
+        #[dbc_file = "../tests/test.dbc"]
+        #[dbc_timestamp = "u32"]
+        struct Bus {
+            #[dbc_signals = "Bool_A"]
+            misc: MiscMessage,
+        }
+        "#,
+    )
+    .unwrap()
+    .to_string();
+    assert!(code.contains("struct Bus"));
+    assert!(code.contains("pub struct MiscMessage"));
+    assert!(code.contains("fn update"));
+    assert!(code.contains("include_bytes"));
+    assert!(!code.contains("dbc_file"));
+    assert!(!code.contains("dbc_signals"));
+}
+
+#[test]
+fn layout_doc() {
+    let code =
+        dbc_data_codegen::messages("../tests/test.dbc", &["WheelSpeedB"])
+            .unwrap()
+            .to_string();
+    assert!(code.contains("Byte | 7 6 5 4 3 2 1 0\\n   0 | A A A A"));
+    assert!(code.contains("   1 | A A A A A A A A\\n   2 | B B B B B B B B"));
+    assert!(code.contains("A: Speed (0|16@1+)\\nB: Temp (16|8@1+)"));
+}
+
+#[test]
+fn unknown_message() {
+    assert!(
+        dbc_data_codegen::messages("../tests/test.dbc", &["Missing"]).is_err()
+    );
+}
+
+#[test]
+fn file_errors() {
+    let err = dbc_data_codegen::messages("missing.dbc", &["MiscMessage"])
+        .unwrap_err()
+        .to_string();
+    assert!(err.starts_with("Could not read missing.dbc"));
+
+    let err = dbc_data_codegen::derive("struct Bus { misc: MiscMessage }")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Missing #[dbc_file"));
+}
+
+#[test]
+fn env_errors() {
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_file = "$DBC_DATA_UNSET/test.dbc"]
+        struct Bus { misc: MiscMessage }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(
+        err,
+        "Environment variable DBC_DATA_UNSET used in \
+         $DBC_DATA_UNSET/test.dbc is not set"
+    );
+
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_file = "${CARGO_MANIFEST_DIR/test.dbc"]
+        struct Bus { misc: MiscMessage }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.starts_with("Unterminated ${...}"));
+}
+
+#[test]
+fn unknown_signal() {
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_file = "../tests/test.dbc"]
+        struct Bus {
+            #[dbc_signals = "Bool_X"]
+            misc: MiscMessage,
+        }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(
+        err,
+        "Unknown signal Bool_X in MiscMessage; did you mean Bool_A?"
+    );
+}
+
+#[test]
+fn signal_beyond_dlc() {
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_inline = "
+VERSION \"\"
+
+BU_: Ecu1
+
+BO_ 1000 Short: 1 Ecu1
+ SG_ Level : 4|8@1+ (1,0) [0|255] \"\" Vector__XXX
+"]
+        struct Bus {
+            short: Short,
+        }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(
+        err,
+        "Signal Level of Short extends to byte 1, beyond the message size \
+         of 1 bytes"
+    );
+}
+
+#[test]
+fn shared_ids() {
+    let bus = r#"
+        #[dbc_file = "../tests/test.dbc"]
+        struct Bus {
+            le: AlignedLE,
+            be: AlignedBE,
+        }
+        "#;
+    let err = dbc_data_codegen::derive(bus).unwrap_err().to_string();
+    assert_eq!(
+        err,
+        "AlignedLE and AlignedBE share ID 1023 (0x3FF), so update() only \
+         decodes AlignedLE; allow this with #[dbc_shared_ids]"
+    );
+    assert!(
+        dbc_data_codegen::derive(&format!("#[dbc_shared_ids] {bus}")).is_ok()
+    );
+}
+
+#[test]
+fn malformed_dbc() {
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_inline = "
+VERSION \"\"
+
+BU_: Ecu1
+
+BO_ 1000 Broken 1 Ecu1
+"]
+        struct Bus {
+            broken: Broken,
+        }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(
+        err,
+        "Unable to parse #[dbc_inline], line 6: `BO_ 1000 Broken 1 Ecu1`"
+    );
+}
+
+#[test]
+fn repr_c_errors() {
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_file = "../tests/test.dbc"]
+        #[dbc_repr_c]
+        #[dbc_timestamp = "u32"]
+        struct Bus { wheel: WheelSpeedB }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(err, "#[dbc_repr_c] cannot be used with #[dbc_timestamp]");
+
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_file = "../tests/test.dbc"]
+        #[dbc_repr_c]
+        struct Bus {
+            #[dbc_dirty]
+            wheel: WheelSpeedB,
+        }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(
+        err,
+        "#[dbc_repr_c] cannot be used with #[dbc_dirty] or #[dbc_lazy]"
+    );
+}
+
+#[test]
+fn padding_errors() {
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_file = "../tests/test.dbc"]
+        #[dbc_padding = 0x1AA]
+        struct Bus { wheel: WheelSpeedB }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(err, "#[dbc_padding] value 0x1AA exceeds 0xFF");
+
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_inline = "
+VERSION \"\"
+
+BU_: Ecu1
+
+BO_ 1000 Filled: 1 Ecu1
+ SG_ Value : 0|8@1+ (1,0) [0|255] \"\" Vector__XXX
+
+BA_DEF_ BO_ \"GenMsgFillValue\" INT 0 65535;
+BA_ \"GenMsgFillValue\" BO_ 1000 256;
+"]
+        struct Bus { filled: Filled }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(err, "GenMsgFillValue 0x100 of Filled exceeds 0xFF");
+}
+
+#[test]
+fn unsupported_items() {
+    for field in [
+        "&'a MiscMessage",
+        "(MiscMessage, AlignedLE)",
+        "[&'a MiscMessage; 2]",
+    ] {
+        let err = dbc_data_codegen::derive(&format!(
+            r#"
+            #[dbc_file = "../tests/test.dbc"]
+            struct Bus<'a> {{ misc: {field} }}
+            "#
+        ))
+        .unwrap_err()
+        .to_string();
+        assert_eq!(err, "unsupported field type");
+    }
+
+    let err = dbc_data_codegen::derive(
+        r#"
+        #[dbc_file = "../tests/test.dbc"]
+        union Bus { misc: MiscMessage }
+        "#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert_eq!(err, "unions are not supported");
+}
@@ -11,7 +11,7 @@
 //!
 //! ```text
 //! BO_ 1023 SomeMessage: 4 Ecu1
-//!  SG_ Unsigned16 : 16|16@0+ (1,0) [0|0] "" Vector__XXX
+//!  SG_ Unsigned16 : 23|16@0+ (1,0) [0|0] "" Vector__XXX
 //!  SG_ Unsigned8 : 8|8@1+ (1,0) [0|0] "" Vector__XXX
 //!  SG_ Signed8 : 0|8@1- (1,0) [0|0] "" Vector__XXX
 //! ```
@@ -39,7 +39,10 @@
 //! }
 //! ```
 //! An `enum` can also be used to derive the types for signals
-//! and messages.
+//! and messages.  A tuple struct holds messages as a struct does, e.g.
+//! `struct Bus(SomeMessage, [OtherMessage; 2]);`, and a unit struct
+//! generates the messages named by `#[dbc_messages = "A, B"]`, as an
+//! `enum` would.
 //!
 //! See the test cases in this crate for examples of usage.
 //!
@@ -100,16 +103,154 @@
 //! interfaces.
 //!
 //! # Functionality
-//! * Decode signals from PDU into native types
+//! * Decode signals from PDU into native types, or into a new message with
+//!   `from_pdu()`
 //!     * const definitions for `ID: u32`, `DLC: u8`, `EXTENDED: bool`,
-//!       and `CYCLE_TIME: usize` when present
+//!       and `CYCLE_TIME: usize` when present, with `CYCLE_TIME_FAST` and
+//!       `REPETITIONS` from `GenMsgCycleTimeFast` and `GenMsgNrOfRepetition`
+//!     * `TIMEOUT_TIME: usize` from `GenMsgTimeoutTime`, and
+//!       `apply_timeout_defaults()` setting signals to their
+//!       `GenSigTimeoutValue` when the message goes missing
 //! * Encode signal into PDU (except unaligned BE)
+//! * Detect which signals changed with `decode_changes()`, which returns
+//!   a generated `<Message>Signals` set with a flag for each signal
+//! * Record reception times with `decode_at()` and check for stale
+//!   messages with `is_stale()`, when `#[dbc_timestamp]` is given
+//! * Write a JSON manifest of the generated messages and signals
+//!   with `#[dbc_manifest]`, for use by external tooling
+//! * Write a C header of the same messages, IDs, scaling and value tables
+//!   with `#[dbc_c_header]`, for C code sharing the firmware, and lay out
+//!   messages as C structs with `#[dbc_repr_c]`
+//! * Compare two instances of a message with `diff()`, returning the
+//!   `<Message>Signals` set of signals which differ
+//! * Log messages as CSV with `csv_header()` and `write_csv()`, which
+//!   writes a row of signal values to any `core::fmt::Write`
+//! * Serialize messages as JSON objects of signal names and values with
+//!   `write_json()`, likewise without `std` or allocation
+//! * Share one DBC file between multiple derives with `dbc_database!`
+//!   and `#[dbc_use]`
+//! * Fill unused PDU bits with the `PADDING` value when encoding with
+//!   `encode_clean()`, taken from the `GenMsgFillValue` attribute or
+//!   `#[dbc_padding]`
+//! * Dispatch received frames by ID with `update()`, generated for
+//!   structs, which returns the `<Name>MessageKind` that was decoded
+//!   (types generated for a struct are named after it, so several derives
+//!   may share a module); each message records whether it has been
+//!   `received()`, and `decode_all()` dispatches a burst of frames
+//! * Generate `dbc-codegen` style names with `#[dbc_codegen_compat]`: a
+//!   `<Name>Messages` enum with `from_can_message()`, `raw()`/`set_raw()`
+//!   and `snake_case` signal accessors, to ease migration
+//! * Track which messages of a struct have been received with
+//!   `received_messages()`, and check that all `#[dbc_mandatory]` messages
+//!   (or all messages, if none are marked) have been with `all_received()`
+//! * Drive periodic transmission with a `<Name>TxScheduler` generated for
+//!   structs, whose `due()` yields the `<Name>MessageKind` of each
+//!   message to send according to its `TX_SCHEDULE` table of message cycle
+//!   times and offsets
+//! * Select between variants of a database at runtime with
+//!   `dbc_variants!`, which dispatches frames to the messages of the
+//!   chosen `Variant`
+//! * Generated code is free of `unsafe`, for `#![forbid(unsafe_code)]`
+//!   crates, which `#[dbc_assert_no_unsafe]` checks at compile time
+//! * Omit unused trait implementations of messages, such as `TryFrom`,
+//!   with `#[dbc_skip_impls]`
+//! * Transmit messages from a struct with `transmit()`, given a
+//!   `<Name>TxManager` supplying the clock and a send function;
+//!   event-triggered messages (by `GenMsgSendType`) are sent after a
+//!   `trigger()`
+//! * Count decoded frames and errors for each message with `#[dbc_stats]`,
+//!   for bus diagnostics
+//! * Read signals in a chosen physical unit with `<Signal>_normalized()`
+//!   accessors, generated by `#[dbc_normalize(unit = "km/h")]`
+//! * Share messages between an interrupt handler and the main loop with
+//!   the `<Message>Shared` types generated by `#[dbc_snapshot]`, whose
+//!   `snapshot()` is never torn
+//! * Capture the most recent frames of the derived messages in the
+//!   `<Name>FrameRecorder` generated by `#[dbc_recorder = N]`, for
+//!   post-mortem analysis
+//! * Access signals from other contexts without a critical section using
+//!   the `portable_atomic` backed `<Message>Atomic` types generated by
+//!   `#[dbc_atomic]`
+//! * Use messages from several DBC files by repeating `#[dbc_file]`, with
+//!   `#[dbc_conflict]` choosing which definition of a message is used
+//! * `const fn new()` on each message (and its `Atomic` companion) for use in
+//!   `static` items, initializing signals from their `GenSigStartValue`
+//! * `STORAGE_BYTES` constants giving the memory used by each message and the
+//!   deriving type, with `#[dbc_max_ram = N]` failing compilation when exceeded
+//! * Generate message types without a carrier struct or enum using
+//!   `dbc_messages!("file.dbc", [SomeMessage])`
+//! * Generate the same code from `build.rs` with the companion
+//!   `dbc-data-codegen` crate, e.g. to inspect it without `cargo expand`
+//! * `<signal>_name()` on signals with value descriptions, giving the
+//!   name of the current value for logging or display
+//! * Decode signals only when they are read, from the stored PDU of
+//!   messages marked `#[dbc_lazy]`
+//! * Encode only the signals changed by setters with `encode_dirty()`, for
+//!   messages marked `#[dbc_dirty]`
+//! * Keep signal fields private behind getters and setters for messages
+//!   marked `#[dbc_private]`
+//! * Reduce the code size of many messages by sharing the functions which
+//!   decode and encode signals with `#[dbc_outline]`
+//! * Message documentation shows the PDU bits occupied by each signal in
+//!   a grid, for reviewing the layout of big-endian signals
+//! * Describe the signals of each message with a `SIGNALS` table using
+//!   `#[dbc_reflect]`, for code which handles any message, and access
+//!   them by name with `get()` and `set()`
+//! * Build encoded messages in one expression with `with_<signal>()`, e.g.
+//!   `WheelSpeed::new().with_Speed(88.5).with_Gear(3)`, including in `const`
+//!   items
+//! * `new_with()` on each message, taking every signal in DBC order, so that
+//!   none is left at its start value by mistake
+//! * `PAGE_<n>` constants and a `PAGES` table of the pages of multiplexed
+//!   messages, with `active_page()` giving the page selected by the
+//!   multiplexor signal
+//! * Gather the pages of a multiplexed message into an array with
+//!   `#[dbc_mux_array]`, each page decoded into the element it indexes
+//! * Decode frames into the array element given by a signal's value with
+//!   `#[dbc_index_signal = "Index"]`
+//!
+//! # Cargo Features
+//! * `debug-bits`: generates `bits()` and `bit_signal()` for each
+//!   message, to show which PDU bits belong to which signal
+//! * `critical-section`: generates a `<Message>Guarded` type for each
+//!   message, for sharing between contexts with `with(|msg| ...)` inside a
+//!   critical section; the crate using it must depend on `critical-section`
+//! * `std`: generates host-side helpers for text logs, to replay captures
+//!   in tests: `update_candump_line()` and `update_asc_line()` feeding a
+//!   `candump -l` or Vector ASC line to a struct's `update()`, the
+//!   `<Name>LogFrame` type they parse lines into, and `to_candump_line()` for
+//!   each message
+//! * `arbitrary`: generates an `arbitrary::Arbitrary` implementation for
+//!   each message, choosing signal values from their value tables or within
+//!   their DBC minimum and maximum, for fuzzing; the crate using it must
+//!   depend on `arbitrary`
+//! * `proptest`: generates `strategy()` for each message which implements
+//!   `Debug`, e.g. with `#[dbc_derive(Debug)]`, a proptest strategy choosing
+//!   signal values as for `arbitrary`; the crate using it must depend on
+//!   `proptest`
+//! * `rand_core`: generates `randomize()` for each message, setting signals
+//!   from a `rand_core::RngCore` as for `arbitrary`, e.g. for simulated bus
+//!   traffic; the crate using it must depend on `rand_core`
+//! * `defmt`: generates a `defmt::Format` implementation for each message,
+//!   showing each signal's name, value and unit, e.g. `Speed: 88.5 km/h`;
+//!   the crate using it must depend on `defmt`
+//! * `arxml`: loads files with an `.arxml` extension as AUTOSAR system
+//!   descriptions, mapping each CAN frame's I-PDUs and I-signals to a message
+//!   and its signals
+//! * `dbf`: loads files with a `.dbf` extension as BUSMASTER databases, without
+//!   their parameters (attributes) or multiplexed or floating-point signals
+//! * `kcd`: loads files with a `.kcd` extension as KCD (Kayak) XML network
+//!   definitions, without multiplexed or floating-point signals
+//! * `ldf`: loads files with an `.ldf` extension as LIN description files,
+//!   mapping each unconditional frame to a message with the frame ID (0 to
+//!   63) as its ID and the signals' encodings as scaling and value tables
+//! * `sym`: loads files with a `.sym` extension as PCAN Symbol Editor files,
+//!   likewise without multiplexed or floating-point signals
 //!
 //! # TODO
 //! * Encode unaligned BE signals
-//! * Generate dispatcher for decoding based on ID (including ranges)
 //! * Enforce that arrays of messages contain the same signals
-//! * Support multiplexed signals
+//! * Decode only the signals of the active page of multiplexed messages
 //! * Emit `enum`s for value-tables, with optional type association
 //!
 //! # License
@@ -118,52 +259,440 @@
 
 extern crate proc_macro;
 
-mod derive;
-mod message;
-mod signal;
-
-use proc_macro2::TokenStream;
-use syn::{parse_macro_input, Attribute, DeriveInput, Expr, Lit, Meta, Result};
-
-use derive::DeriveData;
-use message::MessageInfo;
+use dbc_data_codegen::macros;
+use syn::{parse_macro_input, DeriveInput};
 
 /// See the crate documentation for details.
 ///
 /// The `#[dbc_file]` attribute specifies the name of the .dbc file
-/// to use, and is required.
+/// to use, and is required unless `#[dbc_use]` names a database
+/// declared with [`dbc_database!`].  It may be repeated, or given a
+/// list as `#[dbc_file("chassis.dbc", "body.dbc")]`, to use messages
+/// from several files; when a message name or ID is defined in more
+/// than one, `#[dbc_conflict = "first"]` or `#[dbc_conflict = "last"]`
+/// chooses which definition is used, otherwise an error lists them. Environment variables in the path,
+/// as `$NAME` or `${NAME}`, are expanded at compile time, and relative
+/// paths are resolved against the directory holding the crate's
+/// `Cargo.toml`, falling back to the working directory of the compiler.
+/// The generated code includes each file with `include_bytes!` (which
+/// adds nothing to the binary), so the crate is rebuilt when it changes.
+///
+/// Small databases, such as test fixtures, may instead be given inline
+/// as DBC text with `#[dbc_inline = r#"..."#]`; this may be combined
+/// with `#[dbc_file]`.
+///
+/// A field may give its own `#[dbc_file]`, overriding the struct's, so
+/// that one struct can hold messages from different networks (e.g. for
+/// a gateway).  When messages on different networks share an ID,
+/// `update()` decodes into the first declared; decode the others
+/// directly.
 ///
 /// Individual messages may specify a `#[dbc_signals]` attribute
 /// naming the individual signals of interest; otherwise, all
-/// signals within the message are generated.
-#[proc_macro_derive(DbcData, attributes(dbc_file, dbc_signals))]
+/// signals within the message are generated.  A signal may be given
+/// its own field name with `as`, e.g.
+/// `#[dbc_signals = "Bool_A as brake_active, Float_A as speed"]`.
+/// Alternatively, `#[dbc_signals_except = "Reserved1, Reserved2"]`
+/// generates all signals except those named.  Names may be patterns,
+/// such as `"Wheel_*_Speed"`, where `*` matches any characters and `?`
+/// any one character; it is an error if a pattern matches no signals.
+/// Messages are found by the name of their type, unless
+/// `#[dbc_message = "DBC_Name"]` gives the DBC name, so that the type
+/// can follow Rust naming.
+///
+/// The optional `#[dbc_rename_all = "snake_case"]` attribute names the
+/// signal fields in `snake_case` (e.g. `EngineSpeed` as `engine_speed`),
+/// and also matches message types to DBC names converted to
+/// `PascalCase` (e.g. `EngineData` for `ENGINE_DATA`).
+///
+/// The optional `#[dbc_tx_node = "BCM"]` attribute also generates the
+/// types of every message transmitted by the given node, as an ECU
+/// implementing it would need.  A derive cannot add fields, so those
+/// not held by the struct are only available as types.  Likewise,
+/// `#[dbc_id_range = "0x300..=0x30F"]` generates the types of every
+/// message with an ID in the given range.  Similarly,
+/// `#[dbc_rx_node = "BCM"]` generates only the signals whose receivers
+/// include the given node.
+///
+/// The optional `#[dbc_derive(Clone, Debug)]` attribute adds derives
+/// to each generated message; traits may be given by path, such as
+/// `serde::Serialize`, and are emitted as written, so need not be in
+/// scope.  It may also be given on a field or
+/// variant, replacing those of the deriving item for that message,
+/// e.g. to keep frequently decoded messages minimal.
+///
+/// The optional `#[dbc_skip_impls(TryFrom, Default)]` attribute omits
+/// the given trait implementations of each message, to keep code for
+/// small targets minimal or to implement them differently: `TryFrom`
+/// (`TryFrom<&[u8]>`), `Default` (which is otherwise `new()`), and
+/// with their Cargo features, `Arbitrary` and `Format` (`defmt`).
+/// Generated code does not depend on these, so a message without
+/// `Default` is still created with `new()`, or decoded into a new
+/// message with `from_pdu()`.
+///
+/// Messages may specify `#[dbc_mode = "rx"]` to generate only their
+/// decoding half (omitting `encode()` and `encode_clean()`, and leaving
+/// them out of the TX schedule), or `#[dbc_mode = "tx"]` to generate
+/// only their encoding half (omitting `decode()` and the functions and
+/// types built on it, and leaving them out of `update()`).
+///
+/// Messages may also specify a predicate such as
+/// `#[dbc_cfg(feature = "variant_b")]`, generating their signals and
+/// functions only when it holds.  Otherwise, the message is an empty
+/// stand-in which is never received, decoded or encoded, so a struct
+/// holding it is unchanged but the message costs no RAM or flash.
+///
+/// A message type can only be defined once in a module, so when a DBC
+/// is split across several deriving types, a message they share may
+/// be marked `#[dbc_extern]` in all but one of them: its type is then
+/// not generated, but used from the scope of the derive (e.g. brought
+/// in with `use`).
+///
+/// Two messages held by a struct which share an ID, such as after
+/// merging DBC files, are an error, as `update()` only decodes the
+/// first declared; the optional `#[dbc_shared_ids]` attribute allows
+/// this.
+///
+/// The optional `#[dbc_module = "messages"]` attribute generates all
+/// items within the named module, rather than alongside the deriving
+/// type, so that fields name their messages by path, e.g.
+/// `messages::WheelSpeed`.  The optional `#[dbc_message_modules]`
+/// attribute generates each message and its companion types within a
+/// module of its own, named in `snake_case`, from which the message
+/// type is re-exported.
+///
+/// The optional `#[dbc_timestamp = "u32"]` attribute names the type
+/// used to record reception times via `decode_at()`, e.g. a `u32`
+/// millisecond tick or a `u64` microsecond counter.
+///
+/// The optional `#[dbc_manifest = "dbc.json"]` attribute writes a
+/// JSON description of the generated messages and signals to the
+/// given file, relative to `OUT_DIR`: their IDs, bit positions,
+/// scaling, ranges, units and value tables, for only the messages and
+/// signals compiled in.  Similarly, the optional
+/// `#[dbc_emit = "generated.rs"]` attribute writes the formatted
+/// generated code, for review and debugging without `cargo expand`.
+///
+/// The optional `#[dbc_c_header = "can_db.h"]` attribute writes a C
+/// header, placed likewise, for C code built alongside the Rust: a
+/// struct with the same fields as each message, its `_ID`, `_DLC`,
+/// `_EXTENDED` and `_CYCLE_TIME` macros (and `_CYCLE_TIME_FAST`,
+/// `_REPETITIONS` and `_TIMEOUT_TIME` when present), and for each
+/// signal its bit position, `_FACTOR`, `_OFFSET`, `_DECODE(raw)` and
+/// `_ENCODE(value)` macros and value-table constants, e.g.
+/// `ENGINE_DATA_TYPE_ELECTRIC`.
+///
+/// The optional `#[dbc_padding = 0xAA]` attribute gives the fill value
+/// for unused PDU bits written by `encode_clean()`, for messages
+/// without a `GenMsgFillValue` attribute in the DBC.
+///
+/// The optional `#[dbc_codegen_compat]` attribute also generates
+/// interfaces named as by `dbc-codegen`, to ease migration: the
+/// `<Name>Messages` enum with `from_can_message()` (which may be
+/// imported `as Messages`), `raw()`/`set_raw()` and `snake_case`
+/// signal accessors.  Where these are named as the accessors of a
+/// private or dirty message, only the latter are generated.
+///
+/// Messages of a struct may be marked `#[dbc_mandatory]`, so that
+/// `all_received()` only requires those messages to have been
+/// received; otherwise, all messages are required.
+///
+/// The generated code never uses `unsafe`, so it may be used within
+/// `#![forbid(unsafe_code)]` crates; the optional
+/// `#[dbc_assert_no_unsafe]` attribute also makes the derive fail if
+/// any generated code would contain `unsafe`.
+///
+/// The optional `#[dbc_stats]` attribute adds counts of decoded frames
+/// and errors to each message, available from `stats()` as a
+/// `<Name>MessageStats` named after the deriving type.
+///
+/// Messages may specify `#[dbc_normalize(unit = "km/h")]` to generate
+/// a `<Signal>_normalized()` accessor for each signal whose DBC unit
+/// converts to the given unit, returning its value in that unit as an
+/// `f32`.  The accessors are still named after each signal, so code
+/// reading the same quantity from different messages names each one.
+///
+/// The optional `#[dbc_snapshot]` attribute generates a
+/// `<Message>Shared` type for each message, which can be written from
+/// an interrupt handler with `store()` and read from the main loop with
+/// `snapshot()` without tearing.
+///
+/// The optional `#[dbc_recorder = 32]` attribute generates a
+/// `<Name>FrameRecorder` which holds the given number of the most recent
+/// frames of the derived messages, along with their times (using the
+/// `#[dbc_timestamp]` type, or `u32`), and can `dump()` them as text.
+///
+/// Messages may specify `#[dbc_atomic]` to generate a
+/// `<Message>Atomic` type, which holds each signal of up to 32 bits in
+/// a [`portable_atomic`](https://docs.rs/portable-atomic) type with
+/// relaxed accessors; the crate using it must depend on
+/// `portable-atomic`.
+///
+/// Messages may specify `#[dbc_dirty]` to generate a setter for each
+/// signal, e.g. `set_Speed()`, which records the signal as changed,
+/// and `encode_dirty()`, which encodes only the changed signals into a
+/// PDU kept from the previous call, so a large frame updated piecemeal
+/// is not packed again in full.  As for `#[dbc_private]`, the signal
+/// fields are private, with a getter for each, so that every change
+/// (including by `set()` of `#[dbc_reflect]` and the setters of
+/// `#[dbc_codegen_compat]`) goes through the setters.
+///
+/// Messages may specify `#[dbc_private]` to make their signal fields
+/// private, with a getter and setter for each signal, e.g. `Speed()`
+/// and `set_Speed()`, so that code built on the setters cannot be
+/// bypassed; messages are then created with `new()`, `new_with()` or
+/// `with_<signal>()`.
+///
+/// An array of a multiplexed message may specify `#[dbc_mux_array]` to
+/// hold each page in the element indexed by its multiplexor value, so
+/// that `update()` gathers a table spread over several pages, e.g. cell
+/// voltages, into one array; frames of pages beyond the array are not
+/// decoded.  Each element decodes every signal of its frames, so only
+/// those of its own page are meaningful.  The elements share the ID of
+/// the message, and `index_of()` gives the element of a PDU.
+///
+/// Similarly, an array of a message which carries the index of a
+/// logical channel in one of its signals may specify
+/// `#[dbc_index_signal = "Index"]`, so that `update()` decodes each
+/// frame into the element given by the value of that signal.
+///
+/// Messages may specify `#[dbc_lazy]` to store their last PDU rather
+/// than a field for each signal, with an accessor decoding each signal
+/// only when it is read, e.g. `Speed()`, and a setter encoding it, e.g.
+/// `set_Speed()`, for messages where few of many signals are used.
+/// Lazy messages do not have the helpers which use signal fields, such
+/// as `decode_changes()`, `write_csv()` or `write_json()`.
+///
+/// The deriving type has a `DBC_VERSION` constant holding the
+/// `VERSION` string of its DBC file (the first, if there are several),
+/// so firmware can report which database it was built against.
+///
+/// The optional `#[dbc_env_vars]` attribute generates a
+/// `<Name>EnvVar` type and an `ENV_<NAME>` constant of the deriving
+/// type for each integer or float environment variable (`EV_`) in the
+/// DBC, giving its name, identifier, range, initial value and unit, so
+/// test tooling and firmware share one definition.
+///
+/// The optional `#[dbc_attr_enums]` attribute generates an enum for
+/// each message attribute defined with an `ENUM` type, e.g.
+/// `GenMsgSendType`, and a constant of each message holding its value
+/// of the attribute (or the default), e.g.
+/// `SomeMessage::GEN_MSG_SEND_TYPE`.
+///
+/// Scaled signals are encoded by rounding to the nearest raw value,
+/// with halves away from zero; the optional `#[dbc_rounding]`
+/// attribute selects `"nearest"`, `"truncate"` (towards zero, as
+/// before), `"floor"` or `"ceil"` instead.
+///
+/// The optional `#[dbc_decimal]` attribute scales signals whose factor
+/// and offset are decimals, e.g. 0.1 and -40, exactly: the raw value
+/// is scaled as a whole number of tenths (or hundredths, etc.) in
+/// `f64` and divided once, so a raw value of 13 decodes to `1.3`
+/// rather than `1.3000001`.  Fields remain `f32`.
+///
+/// The optional `#[dbc_outline]` attribute decodes and encodes signals
+/// with functions shared by all of the messages, which take the start
+/// bit and width of a signal, rather than with code specialized for
+/// each signal; this is slower, but much smaller for many messages.
+///
+/// The optional `#[dbc_repr_c]` attribute makes each message
+/// `#[repr(C)]`, with its signals in DBC order followed by the
+/// `received` flag of messages held in a struct, for sharing with C
+/// code across an FFI boundary; the structs of `#[dbc_c_header]` match
+/// this layout.  As their other bookkeeping is not FFI-safe, it cannot
+/// be used with `#[dbc_stats]`, `#[dbc_timestamp]`, `#[dbc_dirty]` or
+/// `#[dbc_lazy]`.
+///
+/// The optional `#[dbc_reflect]` attribute generates a `SIGNALS`
+/// constant for each message, describing each of its signals with a
+/// `<Name>SignalDesc` named after the deriving type: its name, start
+/// bit, width, byte order, signedness, factor, offset and unit, for
+/// logging or display code which handles any message.  Each message
+/// also gets `get(name)` and `set(name, value)`, accessing its signals
+/// by DBC name as `f64` values for diagnostic or scripting layers.
+///
+/// The optional `#[dbc_selftest]` attribute generates a `#[cfg(test)]`
+/// module of tests, one for each message, encoding and decoding it with
+/// its signals at their lowest and highest values, so that the crate
+/// using it checks the packing of its own messages with `cargo test`.
+///
+/// The optional `#[dbc_debug]` attribute implements `Debug` for each
+/// message, showing each signal's value with its unit and raw value,
+/// e.g. `Speed: 88.5 km/h (raw 0x0375)`, rather than deriving it with
+/// `#[dbc_derive(Debug)]`.  Similarly, the optional `#[dbc_display]`
+/// attribute implements `Display` for each message as a single line
+/// of its ID and signal values, e.g. `SomeMessage{id=0x3FF, Speed=88.5}`,
+/// for log lines and assertions.
+///
+/// Each message, and the deriving type, has a `STORAGE_BYTES` constant
+/// giving the memory it occupies. The optional `#[dbc_max_ram = 512]`
+/// attribute fails compilation if the deriving type exceeds that many
+/// bytes.
+#[proc_macro_derive(
+    DbcData,
+    attributes(
+        dbc_file,
+        dbc_use,
+        dbc_signals,
+        dbc_timestamp,
+        dbc_manifest,
+        dbc_c_header,
+        dbc_padding,
+        dbc_codegen_compat,
+        dbc_mandatory,
+        dbc_assert_no_unsafe,
+        dbc_stats,
+        dbc_normalize,
+        dbc_snapshot,
+        dbc_recorder,
+        dbc_atomic,
+        dbc_lazy,
+        dbc_dirty,
+        dbc_private,
+        dbc_mux_array,
+        dbc_index_signal,
+        dbc_messages,
+        dbc_conflict,
+        dbc_max_ram,
+        dbc_inline,
+        dbc_emit,
+        dbc_message,
+        dbc_rename_all,
+        dbc_signals_except,
+        dbc_tx_node,
+        dbc_rx_node,
+        dbc_id_range,
+        dbc_derive,
+        dbc_skip_impls,
+        dbc_mode,
+        dbc_cfg,
+        dbc_extern,
+        dbc_module,
+        dbc_message_modules,
+        dbc_shared_ids,
+        dbc_env_vars,
+        dbc_attr_enums,
+        dbc_debug,
+        dbc_display,
+        dbc_selftest,
+        dbc_reflect,
+        dbc_repr_c,
+        dbc_outline,
+        dbc_rounding,
+        dbc_decimal
+    )
+)]
 pub fn dbc_data_derive(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    derive_data(&parse_macro_input!(input as DeriveInput))
+    macros::derive(&parse_macro_input!(input as DeriveInput))
         .unwrap_or_else(|err| err.to_compile_error())
         .into()
 }
 
-fn derive_data(input: &DeriveInput) -> Result<TokenStream> {
-    Ok(DeriveData::from(input)?.build())
+/// Declare a DBC database which is shared by multiple derives in the
+/// same crate, so that they are guaranteed to use the same file, e.g.
+///
+/// ```
+/// use dbc_data::{dbc_database, DbcData};
+///
+/// dbc_database!("tests/example.dbc" as BUS);
+///
+/// #[derive(DbcData, Default)]
+/// #[dbc_use = "BUS"]
+/// struct TestData {
+///     some_message: SomeMessage,
+/// }
+/// ```
+///
+/// The declaration must be in scope of the derives which use it, as
+/// it also declares a macro of the same name which expands them; each
+/// derive reads the file again, so none depends on the order in which
+/// the compiler expands them, but only parses it again if it has
+/// changed.  The macro refers to `::dbc_data`; if the crate is imported
+/// under another name, give it as `dbc_database!("bus.dbc" as BUS,
+/// crate = dbc)`.
+#[proc_macro]
+pub fn dbc_database(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    macros::database(input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
 }
 
-fn parse_attr(attrs: &[Attribute], name: &str) -> Option<String> {
-    let attr = attrs.iter().find(|a| {
-        a.path().segments.len() == 1 && a.path().segments[0].ident == name
-    })?;
+/// Expand a derive with `#[dbc_use]`, as invoked by the macro declared
+/// by `dbc_database!`
+#[doc(hidden)]
+#[proc_macro]
+pub fn __dbc_use(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    macros::shared(input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
 
-    let expr = match &attr.meta {
-        Meta::NameValue(n) => Some(&n.value),
-        _ => None,
-    };
+/// Generate message types from a DBC file without a deriving struct
+/// or enum, e.g.
+///
+/// ```
+/// use dbc_data::dbc_messages;
+///
+/// dbc_messages!("tests/example.dbc", [SomeMessage]);
+///
+/// let some = SomeMessage::new();
+/// assert_eq!(SomeMessage::ID, 1023);
+/// assert_eq!(some.Signed8, 0);
+/// ```
+///
+/// The types are as generated by `#[derive(DbcData)]` on an `enum` of
+/// the messages.
+#[proc_macro]
+pub fn dbc_messages(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    macros::messages(input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
 
-    match &expr {
-        Some(Expr::Lit(e)) => match &e.lit {
-            Lit::Str(s) => Some(s.value()),
-            _ => None,
-        },
-        _ => None,
-    }
+/// Select between variants of a database at runtime, e.g. to support
+/// different wiring harnesses from one binary.  Each variant of the
+/// `enum` holds a struct deriving `DbcData` (and `Default`) from one
+/// of the DBC files, declared in its own module:
+///
+/// ```
+/// use dbc_data::dbc_variants;
+///
+/// mod a {
+///     #[derive(dbc_data::DbcData, Default)]
+///     #[dbc_file = "tests/example.dbc"]
+///     pub struct Bus {
+///         pub some_message: SomeMessage,
+///     }
+/// }
+///
+/// mod b {
+///     #[derive(dbc_data::DbcData, Default)]
+///     #[dbc_file = "tests/variant.dbc"]
+///     pub struct Bus {
+///         pub misc: MiscMessage,
+///     }
+/// }
+///
+/// dbc_variants! {
+///     pub enum Vehicle {
+///         A(a::Bus),
+///         B(b::Bus),
+///     }
+/// }
+///
+/// let mut vehicle = Vehicle::new(Variant::B);
+/// assert!(!vehicle.update(1023, false, &[0; 4]));
+/// assert!(vehicle.update(8190, false, &[0; 2]));
+/// ```
+///
+/// This generates the fieldless `Variant` enum used to choose one,
+/// `new()`, `variant()` and an `update()` which dispatches frames to
+/// the variant in use.
+#[proc_macro]
+pub fn dbc_variants(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    macros::variants(input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
 }
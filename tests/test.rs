@@ -1,11 +1,117 @@
+#![forbid(unsafe_code)]
+
 #[cfg(test)]
 mod test {
     use assert_eq_float::assert_eq_float;
     use assert_hex::assert_eq_hex;
-    use dbc_data::DbcData;
+    use dbc_data::{dbc_database, dbc_variants, DbcData};
+
+    // uses the database before its declaration
+    mod early {
+        use super::TEST_DB;
+        use dbc_data::DbcData;
+
+        #[derive(DbcData, Default)]
+        #[dbc_use = "TEST_DB"]
+        pub struct Early {
+            pub misc: MiscMessage,
+        }
+    }
+
+    dbc_database!("tests/test.dbc" as TEST_DB);
+
+    // with the crate imported under another name
+    mod renamed {
+        use dbc::{dbc_database, DbcData};
+        use dbc_data as dbc;
+
+        dbc_database!("tests/variant.dbc" as VARIANT_DB, crate = dbc);
+
+        #[derive(DbcData, Default)]
+        #[dbc_use = "VARIANT_DB"]
+        pub struct Variant {
+            pub misc: MiscMessage,
+        }
+    }
+
+    mod harness_a {
+        use dbc_data::DbcData;
+
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        pub struct Bus {
+            pub misc: MiscMessage,
+        }
+    }
+
+    mod harness_b {
+        use dbc_data::DbcData;
+
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/variant.dbc"]
+        pub struct Bus {
+            pub misc: MiscMessage,
+        }
+    }
+
+    // generates tests of its own
+    mod selftest {
+        use dbc_data::DbcData;
+
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_shared_ids]
+        #[dbc_selftest]
+        struct SelfTested {
+            aligned_le: AlignedLE,
+            aligned_be: AlignedBE,
+            unaligned_ule: UnalignedUnsignedLE,
+            unaligned_sle: UnalignedSignedLE,
+            misc: MiscMessage,
+            wheel: WheelSpeedB,
+            signed: SixtyFourBitSigned,
+        }
+    }
+
+    mod outlined {
+        use dbc_data::DbcData;
+
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_shared_ids]
+        #[dbc_outline]
+        pub struct Outlined {
+            aligned_be: AlignedBE,
+            unaligned_ule: UnalignedUnsignedLE,
+            unaligned_ube: UnalignedUnsignedBE,
+            unaligned_sle: UnalignedSignedLE,
+            unaligned_sbe: UnalignedSignedBE,
+            #[dbc_signals = "Bool_A, Bool_H, Float_A"]
+            misc: MiscMessage,
+            signed: SixtyFourBitSigned,
+        }
+
+        // another derive in the module has helpers of its own
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_messages = "WheelSpeedB"]
+        #[dbc_outline]
+        pub struct Wheels;
+    }
+
+    dbc_variants! {
+        enum Harness {
+            A(harness_a::Bus),
+            B(harness_b::Bus),
+        }
+    }
 
     #[derive(DbcData, Default)]
     #[dbc_file = "tests/test.dbc"]
+    #[dbc_shared_ids]
+    #[dbc_assert_no_unsafe]
     struct Test {
         aligned_le: AlignedLE,
         aligned_be: AlignedBE,
@@ -19,7 +125,6 @@ mod test {
         sixty_four_be: SixtyFourBitBE,
         sixty_four_signed: SixtyFourBitSigned,
         grouped: [GroupData1; 3],
-        #[allow(dead_code)]
         extended: Extended1,
     }
 
@@ -44,6 +149,32 @@ mod test {
     fn cycle_time() {
         assert_eq!(MiscMessage::CYCLE_TIME, 100);
         assert_eq!(SixtyFourBitSigned::CYCLE_TIME, 2000);
+        assert_eq!(MiscMessage::CYCLE_TIME_FAST, 20);
+        assert_eq!(MiscMessage::REPETITIONS, 3);
+    }
+
+    #[test]
+    fn padding() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_padding = 0xAA]
+        struct Padded {
+            misc: MiscMessage,
+            #[allow(dead_code)]
+            sixty_four: SixtyFourBitLE,
+        }
+
+        assert_eq!(self::MiscMessage::PADDING, 0x00);
+        assert_eq!(self::SixtyFourBitLE::PADDING, 0xFF);
+        assert_eq!(MiscMessage::PADDING, 0xAA);
+        assert_eq!(SixtyFourBitLE::PADDING, 0xFF); // per-message value
+
+        let mut t = Padded::default();
+        let mut pdu = [0u8; 2];
+        t.misc.Float_A = 16.25;
+        assert!(t.misc.encode_clean(&mut pdu));
+        assert_eq_hex!(pdu, [0x28, 0x20]);
+        assert!(!t.misc.encode_clean(&mut [0u8; 3]));
     }
 
     #[test]
@@ -52,6 +183,11 @@ mod test {
         assert_eq_float!(MiscMessage::FLOAT_A_E, 2.718f32);
         assert!(MiscMessage::BOOL_A_ON);
         assert!(!MiscMessage::BOOL_A_OFF);
+        assert_eq!(AlignedLE::SIGNED8_MIN, -128);
+        assert_eq!(AlignedLE::SIGNED8_INVALID, -1);
+        assert_eq!(SixtyFourBitLE::SIXTYFOUR_WIDE, 1 << 32);
+        assert_eq!(SixtyFourBitSigned::SIXTYFOUR_MIN, i64::MIN);
+        assert_eq!(SixtyFourBitSigned::SIXTYFOUR_INVALID, -1);
     }
 
     #[test]
@@ -230,6 +366,169 @@ mod test {
         assert_eq_hex!(t.grouped[0].ValueA, 0x2001_55AA);
     }
 
+    #[test]
+    fn decode_all() {
+        let mut t = Test::default();
+        let frames: [(u32, bool, &[u8]); 3] = [
+            (MiscMessage::ID, false, &[0x82, 0x20]),
+            (0x7FE, false, &[0x00]),
+            (Extended1::ID, true, &[0u8; 8]),
+        ];
+        assert_eq!(t.decode_all(&frames), 2);
+        assert!(t.misc.received());
+        assert!(t.misc.Bool_H);
+        assert_eq!(t.decode_all(&[]), 0);
+    }
+
+    #[test]
+    fn update_search() {
+        macro_rules! large {
+            ($($field:ident: $message:tt),* $(,)?) => {
+                #[allow(dead_code)]
+                #[derive(DbcData, Default)]
+                #[dbc_file = "tests/large.dbc"]
+                struct Large {
+                    $($field: $message),*
+                }
+            };
+        }
+        large!(
+            l0: Large0, l1: Large1, l2: Large2, l3: Large3, l4: Large4,
+            l5: Large5, l6: Large6, l7: Large7, l8: Large8, l9: Large9,
+            l10: Large10, l11: Large11, l12: Large12, l13: Large13,
+            l14: Large14, l15: Large15, l16: Large16, l17: Large17,
+            l18: Large18, l19: Large19, l20: Large20, l21: Large21,
+            l22: Large22, l23: Large23, l24: Large24, l25: Large25,
+            l26: Large26, l27: Large27, l28: Large28, l29: Large29,
+            l30: Large30, l31: Large31, l32: Large32, l33: Large33,
+            l34: Large34, l35: Large35, l36: Large36, l37: Large37,
+            l38: [Large38; 3], l39: Large39,
+        );
+
+        let mut t = Large::default();
+        assert_eq!(t.update(5, false, &[1]), Some(LargeMessageKind::Large0));
+        assert_eq!(t.update(102, false, &[2]), Some(LargeMessageKind::Large1));
+        assert_eq!(
+            t.update(1945, false, &[3]),
+            Some(LargeMessageKind::Large20)
+        );
+        assert_eq!(
+            t.update(1594, false, &[4]),
+            Some(LargeMessageKind::Large37)
+        );
+        assert_eq!((t.l0.Value, t.l1.Value, t.l20.Value), (1, 2, 3));
+        assert_eq!(t.l37.Value, 4);
+
+        // arrays cover consecutive IDs
+        assert_eq!(
+            t.update(2003, false, &[5]),
+            Some(LargeMessageKind::Large38(2))
+        );
+        assert_eq!(t.l38[2].Value, 5);
+        assert_eq!(t.update(2004, false, &[5]), None);
+
+        assert_eq!(
+            t.update(0x18FF_0000, true, &[6]),
+            Some(LargeMessageKind::Large39)
+        );
+        assert_eq!(t.l39.Value, 6);
+
+        // unknown IDs, mismatched ID types and invalid lengths
+        assert_eq!(t.update(0, false, &[0]), None);
+        assert_eq!(t.update(6, false, &[0]), None);
+        assert_eq!(t.update(5, true, &[0]), None);
+        assert_eq!(t.update(0x18FF_0000, false, &[0]), None);
+        assert_eq!(t.update(0x1FFF_FFFF, true, &[0]), None);
+        assert_eq!(t.update(5, false, &[0, 0]), None);
+    }
+
+    #[test]
+    fn update() {
+        let mut t = Test::default();
+
+        assert!(!t.misc.received());
+        assert_eq!(
+            t.update(MiscMessage::ID, false, &[0x82, 0x20]),
+            Some(TestMessageKind::MiscMessage)
+        );
+        assert!(t.misc.received());
+        assert!(t.misc.Bool_H);
+
+        // invalid length, unknown ID, or mismatched ID type
+        assert_eq!(t.update(MiscMessage::ID, false, &[0x82]), None);
+        assert_eq!(t.update(0x7FE, false, &[0x00]), None);
+        assert_eq!(t.update(MiscMessage::ID, true, &[0x82, 0x20]), None);
+        assert_eq!(
+            t.update(Extended1::ID, true, &[0u8; 8]),
+            Some(TestMessageKind::Extended1)
+        );
+
+        // arrays of messages cover consecutive IDs
+        assert_eq!(
+            t.update(
+                GroupData1::ID + 2,
+                false,
+                &[0xAA, 0x55, 0x01, 0x20, 0x00, 0x00, 0x00, 0x00]
+            ),
+            Some(TestMessageKind::GroupData1(2))
+        );
+        assert_eq_hex!(t.grouped[2].ValueA, 0x2001_55AA);
+        assert!(!t.grouped[0].received());
+        assert_eq!(t.update(GroupData1::ID + 3, false, &[0u8; 8]), None);
+
+        assert_eq!(Test::DBC_VERSION, "1");
+
+        // the first message declared for an ID takes precedence
+        assert_eq!(
+            t.update(1023, false, &[0u8; 8]),
+            Some(TestMessageKind::AlignedLE)
+        );
+    }
+
+    #[test]
+    fn shared_module() {
+        // the items generated for each struct are named after it
+        mod buses {
+            use dbc_data::DbcData;
+
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            #[dbc_stats]
+            #[dbc_reflect]
+            #[dbc_codegen_compat]
+            #[dbc_recorder = 2]
+            pub struct Front {
+                pub wheel: WheelSpeedA,
+            }
+
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            #[dbc_stats]
+            #[dbc_reflect]
+            #[dbc_codegen_compat]
+            #[dbc_recorder = 2]
+            pub struct Rear {
+                pub wheel: WheelSpeedB,
+            }
+        }
+        use buses::{Front, FrontMessageKind, Rear, RearMessageKind};
+
+        let mut front = Front::default();
+        let mut rear = Rear::default();
+        assert_eq!(
+            front.update(300, false, &[0x10, 0x27]),
+            Some(FrontMessageKind::WheelSpeedA)
+        );
+        assert_eq!(front.update(301, false, &[0x10, 0x27, 0x50]), None);
+        assert_eq!(
+            rear.update(301, false, &[0x10, 0x27, 0x50]),
+            Some(RearMessageKind::WheelSpeedB)
+        );
+        assert!(front.all_received() && rear.all_received());
+        assert_eq!(front.wheel.stats().decoded, 1);
+        assert_eq!(rear.wheel.stats().decoded, 1);
+    }
+
     #[test]
     fn try_from() {
         let data: [u8; 8] = [0x20, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
@@ -239,6 +538,2163 @@ mod test {
         assert!(misc.is_err());
         let sixty_four = SixtyFourBitLE::try_from(&data[0..8]);
         assert!(sixty_four.is_ok());
+
+        // built directly, as if decoded
+        assert!(MiscMessage::from_pdu(&[0x83]).is_err());
+        let misc = MiscMessage::from_pdu(&[0x83, 0x20]).unwrap();
+        let mut decoded = MiscMessage::new();
+        assert!(decoded.decode(&[0x83, 0x20]));
+        assert!(misc.Bool_A && misc.Bool_H);
+        assert_eq_float!(misc.Float_A, decoded.Float_A);
+        assert!(misc.received());
+    }
+
+    #[test]
+    fn decode_changes() {
+        let mut t = Test::default();
+
+        let changes = t.misc.decode_changes(&[0x82, 0x20]).unwrap();
+        assert!(changes.contains(
+            MiscMessageSignals::Bool_H | MiscMessageSignals::Float_A
+        ));
+        assert!(!changes.contains(MiscMessageSignals::Bool_A));
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes.names().collect::<Vec<_>>(), ["Bool_H", "Float_A"]);
+
+        let changes = t.misc.decode_changes(&[0x83, 0x20]).unwrap();
+        assert_eq!(changes, MiscMessageSignals::Bool_A);
+        assert!(t.misc.decode_changes(&[0x83, 0x20]).unwrap().is_empty());
+        assert!(t.misc.decode_changes(&[0x83]).is_none());
+    }
+
+    #[test]
+    fn diff() {
+        let mut t = Test::default();
+        let before = MiscMessage::default();
+        assert!(t.misc.diff(&before).is_empty());
+
+        t.misc.Float_A = 1.5;
+        t.misc.Bool_H = true;
+        let changes = t.misc.diff(&before);
+        assert_eq!(
+            changes,
+            MiscMessageSignals::Bool_H | MiscMessageSignals::Float_A
+        );
+        assert_eq!(changes.names().collect::<Vec<_>>(), ["Bool_H", "Float_A"]);
+        assert_eq!(before.diff(&t.misc), changes);
+    }
+
+    #[test]
+    fn csv() {
+        let mut t = Test::default();
+        assert_eq!(MiscMessage::csv_header(), "Bool_A,Bool_H,Float_A");
+
+        assert!(t.misc.decode(&[0x83, 0x20]));
+        let mut row = String::new();
+        t.misc.write_csv(&mut row).unwrap();
+        assert_eq!(row, "true,true,16.25");
+    }
+
+    #[test]
+    fn debug() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_debug]
+        struct Debugged {
+            #[dbc_signals = "Bool_A, Float_A"]
+            misc: MiscMessage,
+            wheel: WheelSpeedB,
+        }
+
+        let mut t = Debugged::default();
+        assert!(t.misc.decode(&[0x01, 0x20]));
+        assert_eq!(
+            format!("{:?}", t.misc),
+            "MiscMessage { Bool_A: true (raw 0x1), Float_A: 16.25 (raw 0x20) }"
+        );
+        assert!(t.wheel.decode(&[0x75, 0x03, 0x64]));
+        assert_eq!(
+            format!("{:?}", t.wheel),
+            "WheelSpeedB { Speed: 88.5 m/s (raw 0x0375), Temp: 100 degC \
+             (raw 0x64) }"
+        );
+    }
+
+    #[test]
+    fn display() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_display]
+        struct Displayed {
+            wheel: WheelSpeedB,
+            extended: Extended1,
+        }
+
+        let mut t = Displayed::default();
+        assert!(t.wheel.decode(&[0x75, 0x03, 0x64]));
+        assert_eq!(
+            t.wheel.to_string(),
+            "WheelSpeedB{id=0x12D, Speed=88.5, Temp=100}"
+        );
+        assert_eq!(t.extended.to_string(), "Extended1{id=0x123456, DataA=0}");
+    }
+
+    #[test]
+    fn reflect() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_reflect]
+        struct Reflected {
+            wheel: WheelSpeedB,
+            aligned: AlignedBE,
+        }
+
+        let speed = &WheelSpeedB::SIGNALS[0];
+        assert_eq!(speed.name, "Speed");
+        assert_eq!((speed.start, speed.width), (0, 16));
+        assert!(!speed.big_endian && !speed.signed);
+        assert_eq_float!(speed.factor, 0.1);
+        assert_eq!(speed.unit, "m/s");
+        assert_eq_float!(WheelSpeedB::SIGNALS[1].offset, -40.0);
+        assert!(AlignedBE::SIGNALS.iter().all(|s| s.big_endian));
+
+        let mut wheel = WheelSpeedB::default();
+        assert!(wheel.set("Speed", 88.5));
+        assert!(wheel.set("Temp", 100.0));
+        assert!(!wheel.set("Gear", 3.0));
+        assert_eq_float!(wheel.get("Speed").unwrap(), 88.5);
+        assert_eq_float!(wheel.get("Temp").unwrap(), 100.0);
+        assert_eq!(wheel.get("Gear"), None);
+    }
+
+    #[test]
+    fn repr_c() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_repr_c]
+        struct Ffi {
+            wheel: WheelSpeedB,
+        }
+
+        assert_eq!(core::mem::offset_of!(WheelSpeedB, Speed), 0);
+        assert_eq!(core::mem::offset_of!(WheelSpeedB, Temp), 4);
+        assert_eq!(core::mem::offset_of!(WheelSpeedB, received), 5);
+    }
+
+    #[test]
+    fn lazy() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Lazy {
+            #[dbc_lazy]
+            wheel: WheelSpeedB,
+        }
+
+        let mut t = Lazy::default();
+        assert_eq!(
+            t.update(0x12D, false, &[0x75, 0x03, 0x8C]),
+            Some(LazyMessageKind::WheelSpeedB)
+        );
+        assert!(t.wheel.received());
+        assert_eq_float!(t.wheel.Speed(), 88.5);
+        assert_eq!(t.wheel.Temp(), 0x8C);
+
+        t.wheel.set_Temp(0x3C);
+        let mut pdu = [0u8; 3];
+        assert!(t.wheel.encode(&mut pdu));
+        assert_eq!(pdu, [0x75, 0x03, 0x3C]);
+        assert!(!t.wheel.decode(&[0x00]));
+
+        let wheel = WheelSpeedB::try_from(&[0x75, 0x03, 0x8C][..]).unwrap();
+        assert_eq!(wheel.Temp(), 0x8C);
+
+        let wheel = WheelSpeedB::new_with(88.5, 0x8C);
+        assert!(wheel.encode(&mut pdu));
+        assert_eq!(pdu, [0x75, 0x03, 0x8C]);
+    }
+
+    #[test]
+    fn dirty() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_reflect]
+        struct Tx {
+            #[dbc_dirty]
+            wheel: WheelSpeedB,
+        }
+
+        let mut t = Tx::default();
+        let mut pdu = [0u8; 3];
+        t.wheel.set_Speed(88.5);
+        assert_eq!(t.wheel.dirty().len(), 2);
+        assert!(t.wheel.encode_dirty(&mut pdu));
+        assert_eq!(pdu, [0x75, 0x03, 0x3C]);
+        assert!(t.wheel.dirty().is_empty());
+
+        // only the changed signal is written
+        pdu[0] = 0xFF;
+        t.wheel.set_Temp(0x50);
+        assert!(t.wheel.dirty().contains(WheelSpeedBSignals::Temp));
+        assert!(t.wheel.encode_dirty(&mut pdu));
+        assert_eq!(pdu, [0xFF, 0x03, 0x50]);
+        assert!(!t.wheel.encode_dirty(&mut [0u8; 2]));
+
+        // builders mark their signals as changed
+        let mut wheel = WheelSpeedB::new().with_Temp(0x51);
+        assert!(wheel.encode_dirty(&mut pdu));
+        assert!(wheel
+            .with_Temp(0x52)
+            .dirty()
+            .contains(WheelSpeedBSignals::Temp));
+
+        // as do signals set by name
+        assert!(t.wheel.set("Temp", 83.0));
+        assert_eq!(t.wheel.Temp(), 83);
+        assert!(t.wheel.dirty().contains(WheelSpeedBSignals::Temp));
+    }
+
+    #[test]
+    fn private() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Private {
+            #[dbc_private]
+            aligned: AlignedLE,
+            #[dbc_private]
+            #[dbc_dirty]
+            wheel: WheelSpeedB,
+        }
+
+        let mut t = Private::default();
+        assert!(t.aligned.decode(&[0xFD, 0x12, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(t.aligned.Signed8(), -3);
+        t.aligned.set_Unsigned8(0x34);
+        assert_eq!(t.aligned.Unsigned8(), 0x34);
+
+        let mut pdu = [0u8; 3];
+        assert!(t.wheel.encode_dirty(&mut pdu));
+        t.wheel.set_Temp(0x50);
+        assert!(t.wheel.dirty().contains(WheelSpeedBSignals::Temp));
+        assert_eq!(t.wheel.Temp(), 0x50);
+    }
+
+    #[test]
+    fn timeout() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Rx {
+            wheel: WheelSpeedB,
+            #[dbc_lazy]
+            wheel_a: WheelSpeedA,
+        }
+
+        assert_eq!(WheelSpeedB::TIMEOUT_TIME, 500);
+        let mut t = Rx::default();
+        assert!(t.wheel.decode(&[0x75, 0x03, 0x50]));
+        t.wheel.apply_timeout_defaults();
+        assert_eq_float!(t.wheel.Speed, 6553.5);
+        // signals without a timeout value are kept
+        assert_eq!(t.wheel.Temp, 0x50);
+
+        t.wheel_a.apply_timeout_defaults();
+        assert_eq_float!(t.wheel_a.Speed(), 655.35);
+    }
+
+    #[test]
+    fn mux_pages() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/mux.dbc"]
+        struct Bms {
+            cells: CellVoltages,
+        }
+
+        assert_eq!(CellVoltages::PAGES, [0, 1, 3]);
+        assert_eq!(CellVoltages::PAGE_3, 3);
+        let mut t = Bms::default();
+        assert!(t.cells.decode(&[0x01, 0xE4, 0x0C, 0, 0, 0, 0, 0]));
+        assert_eq!(t.cells.active_page(), Some(CellVoltages::PAGE_1));
+        assert_eq_float!(t.cells.Cell2, 3.3);
+        assert!(t.cells.decode(&[0x02, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(t.cells.active_page(), None);
+    }
+
+    #[test]
+    fn mux_array() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/mux.dbc"]
+        struct Bms {
+            #[dbc_mux_array]
+            cells: [CellVoltages; 4],
+        }
+
+        let mut t = Bms::default();
+        assert_eq!(
+            t.update(400, false, &[0x01, 0xE4, 0x0C, 0, 0, 0, 0, 0]),
+            Some(BmsMessageKind::CellVoltages(1))
+        );
+        assert_eq!(
+            t.update(400, false, &[0x03, 0, 0, 0x4C, 0x0D, 0, 0, 0]),
+            Some(BmsMessageKind::CellVoltages(3))
+        );
+        assert_eq_float!(t.cells[1].Cell2, 3.3);
+        assert_eq_float!(t.cells[3].Cell7, 3.404);
+        assert!(!t.cells[0].received());
+        assert_eq!(BmsMessageKind::CellVoltages(3).id(), 400);
+
+        // pages beyond the array are not decoded
+        assert_eq!(t.update(400, false, &[0x04, 0, 0, 0, 0, 0, 0, 0]), None);
+        assert_eq!(CellVoltages::index_of(&[0x02; 8]), Some(2));
+        assert_eq!(CellVoltages::index_of(&[0x02; 7]), None);
+    }
+
+    #[test]
+    fn index_signal() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/mux.dbc"]
+        struct Channels {
+            #[dbc_index_signal = "Index"]
+            channels: [ChannelStatus; 3],
+        }
+
+        let mut t = Channels::default();
+        assert_eq!(
+            t.update(401, false, &[0x02, 0xF4, 0x01, 0x01]),
+            Some(ChannelsMessageKind::ChannelStatus(2))
+        );
+        assert_eq_float!(t.channels[2].Current, 5.0);
+        assert!(t.channels[2].Fault);
+        assert!(!t.channels[0].received());
+        assert_eq!(t.update(401, false, &[0x03, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn builder() {
+        const FRAME: AlignedLE =
+            AlignedLE::new().with_Unsigned8(0x12).with_Signed8(-3);
+        let mut pdu = [0u8; 8];
+        assert!(FRAME.encode(&mut pdu));
+        assert_eq_hex!(pdu, [0xFD, 0x12, 0, 0, 0, 0, 0, 0]);
+
+        let misc = MiscMessage::new().with_Bool_A(true).with_Float_A(16.25);
+        assert!(misc.Bool_A);
+        assert_eq_float!(misc.Float_A, 16.25);
+
+        let misc = MiscMessage::new_with(true, false, 16.25);
+        assert!(misc.Bool_A && !misc.Bool_H);
+        assert_eq_float!(misc.Float_A, 16.25);
+    }
+
+    #[test]
+    fn outline() {
+        // outlined code decodes and encodes as the specialized code
+        macro_rules! check {
+            ($message:ident, $pdu:expr) => {
+                let pdu = $pdu;
+                let mut a = $message::default();
+                let mut b = outlined::$message::default();
+                assert!(a.decode(&pdu) && b.decode(&pdu));
+                let (mut csv_a, mut csv_b) = (String::new(), String::new());
+                a.write_csv(&mut csv_a).unwrap();
+                b.write_csv(&mut csv_b).unwrap();
+                assert_eq!(csv_a, csv_b);
+                let (mut pdu_a, mut pdu_b) = (pdu, pdu);
+                pdu_a.fill(0);
+                pdu_b.fill(0);
+                assert!(a.encode(&mut pdu_a) && b.encode(&mut pdu_b));
+                assert_eq!(pdu_a, pdu_b);
+            };
+        }
+        for pdu in [[0xA5, 0x5A, 0xF0, 0x0F, 0x81, 0x7E, 0xC3, 0x3C], [0xFF; 8]]
+        {
+            check!(AlignedBE, pdu);
+            check!(UnalignedUnsignedLE, pdu);
+            check!(UnalignedUnsignedBE, pdu);
+            check!(UnalignedSignedLE, pdu);
+            check!(UnalignedSignedBE, pdu);
+            check!(SixtyFourBitSigned, pdu);
+            check!(MiscMessage, [pdu[0], pdu[1]]);
+        }
+
+        let mut wheel = outlined::WheelSpeedB::default();
+        assert!(wheel.decode(&[0xE8, 0x03, 0x50]));
+        assert_eq_float!(wheel.Speed, 100.0);
+        assert_eq!(wheel.Temp, 0x50);
+        let mut pdu = [0u8; 3];
+        assert!(wheel.encode(&mut pdu));
+        assert_eq!(pdu, [0xE8, 0x03, 0x50]);
+    }
+
+    #[test]
+    fn skip_impls() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_skip_impls(TryFrom, Default)]
+        struct Minimal {
+            wheel: WheelSpeedB,
+        }
+
+        // these would conflict with generated implementations
+        impl Default for WheelSpeedB {
+            fn default() -> Self {
+                let mut wheel = Self::new();
+                wheel.Temp = 0;
+                wheel
+            }
+        }
+        impl TryFrom<&[u8]> for WheelSpeedB {
+            type Error = usize;
+            fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+                let mut wheel = Self::new();
+                wheel.decode(data).then_some(wheel).ok_or(data.len())
+            }
+        }
+
+        assert_eq!(WheelSpeedB::default().Temp, 0);
+        assert_eq!(WheelSpeedB::from_pdu(&[0, 0, 1]).unwrap().Temp, 1);
+        assert_eq!(WheelSpeedB::try_from(&[0u8; 2][..]).err(), Some(2));
+    }
+
+    #[test]
+    fn rounding() {
+        mod truncated {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            #[dbc_rounding = "truncate"]
+            pub struct Truncated {
+                wheel: WheelSpeedB,
+            }
+        }
+
+        mod ceiling {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            #[dbc_rounding = "ceil"]
+            pub struct Ceiling {
+                wheel: WheelSpeedB,
+            }
+        }
+
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Nearest {
+            wheel: WheelSpeedB,
+        }
+
+        let mut pdu = [0u8; 3];
+        let mut wheel = WheelSpeedB::new();
+        wheel.Speed = 88.46;
+        assert!(wheel.encode(&mut pdu));
+        assert_eq!(pdu[..2], [0x75, 0x03]);
+        wheel.Speed = 88.44;
+        assert!(wheel.encode(&mut pdu));
+        assert_eq!(pdu[..2], [0x74, 0x03]);
+
+        let mut wheel = truncated::WheelSpeedB::new();
+        wheel.Speed = 88.46;
+        assert!(wheel.encode(&mut pdu));
+        assert_eq!(pdu[..2], [0x74, 0x03]);
+
+        let mut wheel = ceiling::WheelSpeedB::new();
+        wheel.Speed = 88.41;
+        assert!(wheel.encode(&mut pdu));
+        assert_eq!(pdu[..2], [0x75, 0x03]);
+        wheel.Speed = -1.0;
+        assert!(wheel.encode(&mut pdu));
+        assert_eq!(pdu[..2], [0x00, 0x00]);
+    }
+
+    #[test]
+    fn decimal() {
+        mod exact {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            #[dbc_decimal]
+            pub struct Exact {
+                wheel: WheelSpeedB,
+            }
+        }
+
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Inexact {
+            wheel: WheelSpeedB,
+        }
+
+        let mut wheel = WheelSpeedB::new();
+        assert!(wheel.decode(&[0x0D, 0x00, 0x00]));
+        assert_ne!(wheel.Speed, 1.3);
+
+        let mut wheel = exact::WheelSpeedB::new();
+        assert!(wheel.decode(&[0x0D, 0x00, 0x00]));
+        assert_eq!(wheel.Speed, 1.3);
+        assert!(wheel.decode(&[0xFF, 0x00, 0x00]));
+        assert_eq!(wheel.Speed, 25.5);
+
+        let mut pdu = [0u8; 3];
+        wheel.Speed = 88.5;
+        assert!(wheel.encode(&mut pdu));
+        assert_eq!(pdu[..2], [0x75, 0x03]);
+    }
+
+    #[test]
+    fn decimal_small_factors() {
+        mod fixture {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_decimal]
+            #[dbc_inline = r#"
+VERSION ""
+
+BU_: Ecu1
+
+BO_ 1002 Position: 8 Ecu1
+ SG_ Latitude : 0|32@1+ (1e-07,0) [0|429.4967295] "deg" Vector__XXX
+ SG_ Gain : 32|16@1+ (1.0000001,0) [0|65535] "" Vector__XXX
+ SG_ Third : 48|16@1+ (0.333333333333,0) [0|21845] "" Vector__XXX
+"#]
+            pub struct Fixture {
+                pub position: Position,
+            }
+        }
+
+        let mut position = fixture::Position::default();
+        // 525000000, 10000 and 3
+        let pdu = [0x40, 0xDD, 0x4A, 0x1F, 0x10, 0x27, 0x03, 0x00];
+        assert!(position.decode(&pdu));
+        assert_eq!(position.Latitude, 52.5);
+        assert!(position.Gain > 10000.0);
+        // not a decimal, so scaled as without `#[dbc_decimal]`
+        assert_eq_float!(position.Third, 1.0);
+
+        let mut encoded = [0u8; 8];
+        assert!(position.encode(&mut encoded));
+        assert_eq!(encoded[..4], pdu[..4]);
+    }
+
+    #[test]
+    fn json() {
+        let mut t = Test::default();
+        assert!(t.misc.decode(&[0x83, 0x20]));
+        let mut json = String::new();
+        t.misc.write_json(&mut json).unwrap();
+        assert_eq!(json, r#"{"Bool_A":true,"Bool_H":true,"Float_A":16.25}"#);
+
+        t.misc.Float_A = f32::NAN;
+        json.clear();
+        t.misc.write_json(&mut json).unwrap();
+        assert!(json.ends_with(r#""Float_A":null}"#));
+    }
+
+    #[test]
+    fn decode_at() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_timestamp = "u32"]
+        struct Timed {
+            misc: MiscMessage,
+        }
+        let mut t = Timed::default();
+
+        assert!(t.misc.rx_time().is_none());
+        assert!(t.misc.is_stale(0, 100));
+        assert!(!t.misc.decode_at(&[0x82], 10));
+        assert!(t.misc.rx_time().is_none());
+
+        assert!(t.misc.decode_at(&[0x82, 0x20], 10));
+        assert_eq!(t.misc.rx_time(), Some(10));
+        assert!(!t.misc.is_stale(110, 100));
+        assert!(t.misc.is_stale(111, 100));
+
+        // tick counter wrapping
+        assert!(t.misc.decode_at(&[0x82, 0x20], u32::MAX - 5));
+        assert!(!t.misc.is_stale(50, 100));
+    }
+
+    #[test]
+    fn manifest() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_manifest = "manifest.json"]
+        enum Messages {
+            #[dbc_signals = "Bool_A, Float_A"]
+            MiscMessage,
+            SixtyFourBitBE,
+        }
+
+        let json = std::fs::read_to_string(concat!(
+            env!("CARGO_TARGET_TMPDIR"),
+            "/manifest.json"
+        ))
+        .unwrap();
+        assert!(json.starts_with(r#"{"dbc_file":"tests/test.dbc","#));
+        assert!(json.contains(
+            r#"{"name":"MiscMessage","id":8191,"extended":false,"dlc":2,"cycle_time":100,"cycle_time_fast":20,"repetitions":3,"signal_filter":["Bool_A","Float_A"],"#
+        ));
+        assert!(json.contains(
+            r#"{"name":"Float_A","field":"Float_A","type":"f32","start_bit":8,"width":8,"byte_order":"little_endian","signed":false,"factor":0.5,"offset":0.25,"min":0,"max":0,"unit":"","values":[{"value":3.14,"name":"Pi"},{"value":2.718,"name":"e"}]}"#
+        ));
+        assert!(!json.contains("Bool_B"));
+        assert!(json.contains(r#""signal_filter":null"#));
+    }
+
+    #[test]
+    fn c_header() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_c_header = "test_db.h"]
+        enum Messages {
+            #[dbc_signals = "Bool_A, Float_A"]
+            MiscMessage,
+        }
+
+        let header = std::fs::read_to_string(concat!(
+            env!("CARGO_TARGET_TMPDIR"),
+            "/test_db.h"
+        ))
+        .unwrap();
+        assert!(header.contains("#ifndef TEST_DB_H\n"));
+        assert!(header.contains("#define MISC_MESSAGE_ID 0x1FFFu\n"));
+        assert!(header.contains("#define MISC_MESSAGE_CYCLE_TIME 100u\n"));
+        assert!(header.contains("#define MISC_MESSAGE_REPETITIONS 3u\n"));
+        assert!(header.contains("    bool Bool_A;\n    float Float_A;\n"));
+        assert!(header.contains("#define MISC_MESSAGE_FLOAT_A_FACTOR 0.5\n"));
+        assert!(header.contains(
+            "#define MISC_MESSAGE_FLOAT_A_DECODE(raw) ((raw) * (0.5) + (0.25))"
+        ));
+        assert!(header.contains("#define MISC_MESSAGE_BOOL_A_ON 1\n"));
+        assert!(header.contains("#define MISC_MESSAGE_FLOAT_A_PI 3.14\n"));
+        assert!(!header.contains("BOOL_B"));
+    }
+
+    #[test]
+    fn message_name() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Renamed {
+            #[dbc_message = "MiscMessage"]
+            misc: Misc,
+        }
+
+        let mut r = Renamed::default();
+        assert_eq!(Misc::ID, 8191);
+        assert_eq!(
+            r.update(8191, false, &[0x01, 0x00]),
+            Some(RenamedMessageKind::Misc)
+        );
+        assert!(r.misc.Bool_A);
+    }
+
+    #[test]
+    fn signal_rename() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Renamed {
+            #[dbc_signals = "Bool_A as brake_active, Float_A as speed"]
+            misc: MiscMessage,
+        }
+
+        let mut r = Renamed::default();
+        assert!(r.misc.decode(&[0x01, 0x02]));
+        assert!(r.misc.brake_active);
+        assert_eq_float!(r.misc.speed, 1.25);
+    }
+
+    #[test]
+    fn signals_except() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Except {
+            #[dbc_signals_except = "Bool_B, Bool_H"]
+            misc: MiscMessage,
+        }
+
+        assert_eq!(MiscMessageSignals::NAMES, ["Bool_A", "Float_A"]);
+    }
+
+    #[test]
+    fn signal_patterns() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Patterns {
+            #[dbc_signals = "Bool_?, Float*"]
+            misc: MiscMessage,
+            #[dbc_signals_except = "*Signed*"]
+            aligned: AlignedLE,
+        }
+
+        assert_eq!(
+            MiscMessageSignals::NAMES,
+            ["Bool_A", "Bool_B", "Bool_H", "Float_A"]
+        );
+        assert_eq!(
+            AlignedLESignals::NAMES,
+            ["Unsigned32", "Unsigned16", "Unsigned8"]
+        );
+    }
+
+    #[test]
+    fn tx_node() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_tx_node = "Ecu2"]
+        struct Node {
+            misc: MiscMessage,
+        }
+
+        assert_eq!(SixtyFourBitLE::ID, 64);
+        assert_eq!(SixtyFourBitBE::ID, 65);
+        assert_eq!(SixtyFourBitSigned::ID, 66);
+        assert_eq!(WheelSpeedB::ID, 301);
+        // also sent by Ecu2 according to BO_TX_BU_
+        assert_eq!(WheelSpeedA::ID, 300);
+
+        let mut node = Node::default();
+        assert_eq!(
+            node.update(8191, false, &[0x01, 0x00]),
+            Some(NodeMessageKind::MiscMessage)
+        );
+        assert_eq!(node.update(64, false, &[0; 8]), None);
+    }
+
+    #[test]
+    fn derives() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_derive(Clone, Copy)]
+        struct Derives {
+            misc: MiscMessage,
+            #[dbc_derive(Debug, PartialEq)]
+            wheel: WheelSpeedA,
+        }
+
+        let d = Derives::default();
+        let misc = d.misc;
+        assert_eq!(misc.Bool_A, d.misc.Bool_A);
+        assert_eq!(d.wheel, WheelSpeedA::default());
+        assert!(format!("{:?}", d.wheel).starts_with("WheelSpeedA"));
+    }
+
+    #[test]
+    fn derive_paths() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_derive(core::fmt::Debug, ::core::clone::Clone, std::hash::Hash)]
+        struct Derives {
+            #[dbc_signals = "Bool_A"]
+            misc: MiscMessage,
+        }
+
+        let d = Derives::default();
+        assert!(format!("{:?}", d.misc.clone()).contains("Bool_A: false"));
+    }
+
+    #[test]
+    fn mode() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Modes {
+            #[dbc_mode = "rx"]
+            misc: MiscMessage,
+            #[dbc_mode = "tx"]
+            wheel: WheelSpeedA,
+        }
+
+        let mut modes = Modes::default();
+        assert_eq!(
+            modes.update(8191, false, &[0x01, 0x00]),
+            Some(ModesMessageKind::MiscMessage)
+        );
+        assert!(modes.misc.Bool_A);
+        assert_eq!(modes.update(300, false, &[0; 2]), None);
+
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            modes.encode_into(ModesMessageKind::MiscMessage, &mut buf),
+            None
+        );
+        assert_eq!(
+            modes.encode_into(ModesMessageKind::WheelSpeedA, &mut buf),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn cfg() {
+        mod cfg {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            pub struct Variant {
+                #[dbc_cfg(test)]
+                pub a: WheelSpeedA,
+                #[dbc_cfg(any())]
+                pub b: WheelSpeedB,
+            }
+        }
+        use cfg::{Variant, VariantMessageKind, WheelSpeedB};
+
+        let mut v = Variant::default();
+        assert_eq!(WheelSpeedB::STORAGE_BYTES, 0);
+        assert_eq!(v.update(301, false, &[0; 3]), None);
+        assert!(!v.all_received());
+        assert_eq!(
+            v.update(300, false, &[0x10, 0x27]),
+            Some(VariantMessageKind::WheelSpeedA)
+        );
+        assert!(v.all_received());
+    }
+
+    #[test]
+    fn extern_messages() {
+        mod split {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            pub struct Chassis {
+                pub wheel: WheelSpeedA,
+            }
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            pub struct Body {
+                #[dbc_extern]
+                pub wheel: WheelSpeedA,
+                pub misc: MiscMessage,
+            }
+        }
+
+        let mut body = split::Body::default();
+        assert_eq!(
+            body.update(300, false, &[0x10, 0x27]),
+            Some(split::BodyMessageKind::WheelSpeedA)
+        );
+        let wheel: &split::WheelSpeedA = &body.wheel;
+        assert_eq_float!(wheel.Speed, 100.0);
+        assert!(body.update(8191, false, &[0x83, 0x20]).is_some());
+        let misc: &split::MiscMessage = &body.misc;
+        assert!(misc.Bool_A);
+
+        let mut chassis = split::Chassis::default();
+        assert_eq!(
+            chassis.update(300, false, &[0x10, 0x27]),
+            Some(split::ChassisMessageKind::WheelSpeedA)
+        );
+    }
+
+    #[test]
+    fn modules() {
+        mod wrapped {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            #[dbc_module = "messages"]
+            #[dbc_message_modules]
+            pub struct Wrapped {
+                pub wheel: messages::WheelSpeedA,
+                misc: messages::MiscMessage,
+            }
+        }
+        use wrapped::messages::{self, wheel_speed_a};
+
+        let mut w = wrapped::Wrapped::default();
+        assert_eq!(
+            w.update(300, false, &[0x10, 0x27]),
+            Some(messages::WrappedMessageKind::WheelSpeedA)
+        );
+        assert_eq_float!(w.wheel.Speed, 100.0);
+        assert_eq!(wheel_speed_a::WheelSpeedASignals::NAMES, ["Speed"]);
+        assert_eq!(messages::misc_message::MiscMessage::ID, 8191);
+    }
+
+    #[test]
+    fn id_range() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_id_range = "0x80..0x82"]
+        enum Range {}
+
+        assert_eq!(GroupData1::ID, 128);
+        assert_eq!(GroupData2::ID, 129);
+    }
+
+    #[test]
+    fn rx_node() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_rx_node = "Ecu1"]
+        struct Node {
+            wheel: WheelSpeedB,
+        }
+
+        assert_eq!(WheelSpeedBSignals::NAMES, ["Speed"]);
+    }
+
+    #[test]
+    fn rename_all() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_rename_all = "snake_case"]
+        struct Renamed {
+            engine: EngineData,
+        }
+
+        let mut r = Renamed::default();
+        assert_eq!(EngineData::ID, 302);
+        assert!(r.update(302, false, &[0x12, 0x03]).is_some());
+        assert_eq!(r.engine.engine_speed, 0x12);
+        assert_eq!(r.engine.type_, 3);
+        // from the global value table of the signal's type
+        assert_eq!(r.engine.type_, EngineData::TYPE_ELECTRIC);
+        assert_eq!(EngineData::TYPE_PETROL, 1);
+        assert_eq!(r.engine.type_name(), Some("Electric"));
+        r.engine.type_ = 7;
+        assert_eq!(r.engine.type_name(), None);
+    }
+
+    #[test]
+    fn emit() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_emit = "generated.rs"]
+        enum Messages {
+            #[dbc_signals = "Bool_A"]
+            MiscMessage,
+        }
+
+        let code = std::fs::read_to_string(concat!(
+            env!("CARGO_TARGET_TMPDIR"),
+            "/generated.rs"
+        ))
+        .unwrap();
+        assert!(code.contains("pub struct MiscMessage {\n"));
+        assert!(code.contains("pub Bool_A: bool,"));
+        assert!(!code.contains("Float_A"));
+    }
+
+    #[cfg(feature = "critical-section")]
+    #[test]
+    fn critical_section() {
+        static MISC: MiscMessageGuarded = MiscMessageGuarded::new();
+
+        assert!(!MISC.with(|m| m.Bool_A));
+        assert!(MISC.decode(&[0x01, 0x80]));
+        assert!(!MISC.decode(&[0x01]));
+        assert!(MISC.with(|m| m.Bool_A));
+        MISC.with(|m| m.Bool_H = true);
+        assert!(MISC.with(|m| m.Bool_H));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn candump() {
+        let mut t = Test::default();
+        assert_eq!(
+            t.update_candump_line(
+                "(1700000000.000000) can0 040#1122334455667788"
+            ),
+            Some(TestMessageKind::SixtyFourBitLE)
+        );
+        assert_eq!(t.sixty_four_le.SixtyFour, 0x8877_6655_4433_2211);
+        assert!(t.update_candump_line("(0.000000) can0 7FF#00").is_none());
+        assert!(t.update_candump_line("can0 040#112").is_none());
+        assert!(TestLogFrame::from_candump_line("(0) can0 123#1é2").is_none());
+
+        let line = "(0.500000) vcan0 00123456#0100000000000000";
+        let frame = TestLogFrame::from_candump_line(line).unwrap();
+        assert!(frame.extended);
+        assert_eq!(frame.to_candump_line(0.5, "vcan0"), line);
+        let extended = Extended1::try_from(frame.data.as_slice()).unwrap();
+        assert_eq!(extended.DataA, 1);
+        assert_eq!(extended.to_candump_line(0.5, "vcan0"), line);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn asc() {
+        let log = "date Mon Oct 16 10:00:00.000 am 2026
+base hex  timestamps absolute
+Begin Triggerblock Mon Oct 16 10:00:00.000 am 2026
+   0.000000 Start of measurement
+   0.010000 1  040             Rx   d 8 11 22 33 44 55 66 77 88
+   0.020000 1  123456x         Rx   d 8 02 00 00 00 00 00 00 00
+   0.030000 1  040             Rx   r
+End TriggerBlock";
+        let mut t = Test::default();
+        let updated: Vec<_> =
+            log.lines().filter_map(|l| t.update_asc_line(l)).collect();
+        assert_eq!(
+            updated,
+            [TestMessageKind::SixtyFourBitLE, TestMessageKind::Extended1]
+        );
+        assert_eq!(t.sixty_four_le.SixtyFour, 0x8877_6655_4433_2211);
+        assert_eq!(t.extended.DataA, 2);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Fuzzed {
+            wheel: WheelSpeedB,
+        }
+
+        let bytes: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        let mut u = Unstructured::new(&bytes);
+        let mut pdu = [0u8; 3];
+        for _ in 0..64 {
+            // within the DBC ranges, and unchanged by encoding
+            let wheel = WheelSpeedB::arbitrary(&mut u).unwrap();
+            assert!((0.0..=6553.5).contains(&wheel.Speed));
+            assert!(wheel.encode(&mut pdu));
+            let mut decoded = WheelSpeedB::default();
+            assert!(decoded.decode(&pdu));
+            assert_eq_float!(decoded.Speed, wheel.Speed);
+            assert_eq!(decoded.Temp, wheel.Temp);
+
+            let misc = MiscMessage::arbitrary(&mut u).unwrap();
+            assert!([3.14, 2.718].contains(&misc.Float_A));
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn strategy() {
+        use proptest::{prop_assert, prop_assert_eq, test_runner::TestRunner};
+
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_derive(Debug)]
+        struct Props {
+            wheel: WheelSpeedB,
+            misc: MiscMessage,
+        }
+
+        let mut runner = TestRunner::default();
+        runner
+            .run(&WheelSpeedB::strategy(), |wheel| {
+                let mut pdu = [0u8; 3];
+                prop_assert!(wheel.encode(&mut pdu));
+                let mut decoded = WheelSpeedB::default();
+                prop_assert!(decoded.decode(&pdu));
+                prop_assert_eq!(decoded.Speed, wheel.Speed);
+                prop_assert_eq!(decoded.Temp, wheel.Temp);
+                Ok(())
+            })
+            .unwrap();
+        runner
+            .run(&MiscMessage::strategy(), |misc| {
+                prop_assert!([3.14, 2.718].contains(&misc.Float_A));
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[cfg(feature = "rand_core")]
+    #[test]
+    fn randomize() {
+        /// A xorshift generator, which is enough for a test
+        struct XorShift(u64);
+
+        impl rand_core::RngCore for XorShift {
+            fn next_u32(&mut self) -> u32 {
+                self.next_u64() as u32
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                self.0
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                rand_core::impls::fill_bytes_via_next(self, dest);
+            }
+
+            fn try_fill_bytes(
+                &mut self,
+                dest: &mut [u8],
+            ) -> Result<(), rand_core::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Simulated {
+            wheel: WheelSpeedB,
+            misc: MiscMessage,
+        }
+
+        let mut rng = XorShift(0x1234_5678_9ABC_DEF0);
+        let mut t = Simulated::default();
+        let mut speeds = vec![];
+        for _ in 0..32 {
+            t.wheel.randomize(&mut rng);
+            assert!((0.0..=6553.5).contains(&t.wheel.Speed));
+            speeds.push(t.wheel.Speed);
+            t.misc.randomize(&mut rng);
+            assert!([3.14, 2.718].contains(&t.misc.Float_A));
+        }
+        assert!(speeds.iter().any(|s| *s != speeds[0]));
+    }
+
+    #[cfg(feature = "defmt")]
+    #[test]
+    fn defmt() {
+        fn is_format<T: defmt::Format>(_: &T) {}
+        let t = Test::default();
+        is_format(&t.misc);
+        is_format(&t.sixty_four_le);
+    }
+
+    #[cfg(feature = "debug-bits")]
+    #[test]
+    fn debug_bits() {
+        let t = Test::default();
+
+        let set: Vec<usize> = t
+            .misc
+            .bits(&[0x82, 0x20])
+            .filter(|(_, v)| *v)
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(set, [1, 7, 13]);
+        assert_eq!(MiscMessage::bit_signal(0), Some("Bool_A"));
+        assert_eq!(MiscMessage::bit_signal(1), None); // not selected
+        assert_eq!(MiscMessage::bit_signal(15), Some("Float_A"));
+
+        // big-endian signals walk down each byte
+        assert_eq!(AlignedBE::bit_signal(16), Some("Unsigned16"));
+        assert_eq!(AlignedBE::bit_signal(31), Some("Unsigned16"));
+        assert_eq!(AlignedBE::bit_signal(39), Some("Unsigned32"));
+    }
+
+    #[test]
+    fn shared_database() {
+        #[derive(DbcData, Default)]
+        #[dbc_use = "TEST_DB"]
+        struct First {
+            misc: MiscMessage,
+        }
+
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_use = "TEST_DB"]
+        enum Second {
+            SixtyFourBitLE,
+        }
+
+        let mut t = First::default();
+        assert!(t.misc.decode(&[0x82, 0x20]));
+        assert_eq!(SixtyFourBitLE::ID, 64);
+        assert_eq!(TEST_DB, "tests/test.dbc");
+
+        let mut early = early::Early::default();
+        assert!(early.update(8191, false, &[0x82, 0x20]).is_some());
+        assert!(!early.misc.Bool_A);
+
+        let mut variant = renamed::Variant::default();
+        assert!(variant.update(8190, false, &[0x82, 0x20]).is_some());
+        assert_eq!(renamed::MiscMessage::ID, 8190);
+    }
+
+    #[test]
+    fn received_messages() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Gated {
+            #[dbc_mandatory]
+            misc: MiscMessage,
+            sixty_four: SixtyFourBitLE,
+            #[dbc_mandatory]
+            grouped: [GroupData1; 2],
+        }
+
+        let mut t = Gated::default();
+        assert!(t.received_messages().is_empty());
+        assert_eq!(
+            Gated::MANDATORY,
+            GatedMessageSet::MiscMessage | GatedMessageSet::GroupData1
+        );
+
+        assert!(t.update(64, false, &[0; 8]).is_some());
+        assert!(t.update(8191, false, &[0x01, 0x00]).is_some());
+        assert!(t.update(128, false, &[0; 8]).is_some());
+        assert!(!t.all_received()); // only one of the array received
+        assert_eq!(t.received_messages().len(), 2);
+
+        assert!(t.update(129, false, &[0; 8]).is_some());
+        assert!(t.all_received());
+        assert_eq!(
+            t.received_messages().names().collect::<Vec<_>>(),
+            ["MiscMessage", "SixtyFourBitLE", "GroupData1"]
+        );
+    }
+
+    #[test]
+    fn tx_schedule() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Scheduled {
+            misc: MiscMessage,
+            unscheduled: SixtyFourBitLE,
+            signed: SixtyFourBitSigned,
+        }
+
+        assert_eq!(
+            ScheduledTxScheduler::TX_SCHEDULE,
+            [
+                (ScheduledMessageKind::MiscMessage, 100, 0),
+                (ScheduledMessageKind::SixtyFourBitSigned, 2000, 50)
+            ]
+        );
+
+        let mut tx = ScheduledTxScheduler::new();
+        let mut due = |now| tx.due(now).collect::<Vec<_>>();
+        assert_eq!(due(0), [ScheduledMessageKind::MiscMessage]);
+        assert_eq!(due(50), [ScheduledMessageKind::SixtyFourBitSigned]);
+        assert!(due(99).is_empty());
+        assert_eq!(due(100), [ScheduledMessageKind::MiscMessage]);
+        // a late poll only sends each message once
+        assert_eq!(
+            due(2050),
+            [
+                ScheduledMessageKind::MiscMessage,
+                ScheduledMessageKind::SixtyFourBitSigned
+            ]
+        );
+        assert!(due(2100).is_empty());
+        assert_eq!(due(2150), [ScheduledMessageKind::MiscMessage]);
+
+        // restarting offsets the schedule, allowing for wrapping
+        tx.start(u32::MAX - 10);
+        assert_eq!(tx.due(u32::MAX - 10).count(), 1);
+        assert_eq!(tx.due(39).count(), 1);
+    }
+
+    #[test]
+    fn tx_manager() {
+        struct Driver {
+            time: u32,
+            frames: Vec<(u32, bool, Vec<u8>)>,
+        }
+
+        impl TransmittedTxManager for Driver {
+            fn now(&mut self) -> u32 {
+                self.time
+            }
+
+            fn send(&mut self, id: u32, extended: bool, data: &[u8]) {
+                self.frames.push((id, extended, data.to_vec()));
+            }
+        }
+
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Transmitted {
+            misc: MiscMessage,
+            event: SixtyFourBitLE,
+        }
+
+        assert_eq!(
+            TransmittedTxScheduler::TX_EVENTS,
+            [TransmittedMessageKind::SixtyFourBitLE]
+        );
+        assert_eq!(Transmitted::MAX_DLC, 8);
+
+        let mut t = Transmitted::default();
+        t.misc.Bool_A = true;
+        t.event.SixtyFour = 0x1234;
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            t.encode_into(TransmittedMessageKind::MiscMessage, &mut buf),
+            Some(2)
+        );
+        assert_eq!(buf[..2], [0x01, 0x00]);
+        assert_eq!(
+            t.encode_into(
+                TransmittedMessageKind::SixtyFourBitLE,
+                &mut buf[..4]
+            ),
+            None
+        );
+
+        let mut tx = TransmittedTxScheduler::new();
+        let mut driver = Driver {
+            time: 0,
+            frames: vec![],
+        };
+        assert_eq!(t.transmit(&mut tx, &mut driver), 1);
+        assert_eq!(
+            tx.next_deadline(TransmittedMessageKind::MiscMessage),
+            Some(100)
+        );
+        assert_eq!(
+            tx.next_deadline(TransmittedMessageKind::SixtyFourBitLE),
+            None
+        );
+
+        // events are only sent once triggered
+        driver.time = 10;
+        assert!(!tx.is_due(TransmittedMessageKind::SixtyFourBitLE, 10));
+        assert!(tx.trigger(TransmittedMessageKind::SixtyFourBitLE));
+        assert!(!tx.trigger(TransmittedMessageKind::MiscMessage));
+        assert!(tx.is_due(TransmittedMessageKind::SixtyFourBitLE, 10));
+        assert_eq!(t.transmit(&mut tx, &mut driver), 1);
+        assert_eq!(t.transmit(&mut tx, &mut driver), 0);
+
+        assert_eq!(
+            driver.frames,
+            [
+                (8191, false, vec![0x01, 0x00]),
+                (64, false, vec![0x34, 0x12, 0, 0, 0, 0, 0, 0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn stats() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_stats]
+        struct Counted {
+            misc: MiscMessage,
+        }
+
+        let mut t = Counted::default();
+        assert!(t.update(8191, false, &[0x01, 0x00]).is_some());
+        assert!(t.misc.decode(&[0x01, 0x00]));
+        assert!(!t.misc.decode(&[0x01]));
+        t.misc.record_crc_error();
+        t.misc.record_timeout();
+        t.misc.record_sent();
+        assert_eq!(
+            *t.misc.stats(),
+            CountedMessageStats {
+                decoded: 2,
+                length_errors: 1,
+                crc_errors: 1,
+                timeouts: 1,
+                sent: 1,
+            }
+        );
+
+        t.misc.reset_stats();
+        assert_eq!(*t.misc.stats(), CountedMessageStats::default());
+    }
+
+    #[test]
+    fn normalize() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Wheels {
+            #[dbc_normalize(unit = "km/h")]
+            a: WheelSpeedA,
+            #[dbc_normalize(unit = "km/h")]
+            b: WheelSpeedB,
+        }
+
+        let mut t = Wheels::default();
+        assert!(t.a.decode(&[0x10, 0x27]));
+        assert!(t.b.decode(&[0xE8, 0x03, 0x00]));
+        assert_eq_float!(t.a.Speed, 100.0);
+        assert_eq_float!(t.b.Speed, 100.0);
+        assert_eq_float!(t.a.Speed_normalized(), 100.0);
+        assert_eq_float!(t.b.Speed_normalized(), 360.0);
+    }
+
+    #[test]
+    fn latin1() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/latin1.dbc"]
+        struct Cabin {
+            #[dbc_normalize(unit = "°C")]
+            climate: Climate,
+        }
+
+        let mut t = Cabin::default();
+        assert!(t.climate.decode(&[212, 0]));
+        assert_eq_float!(t.climate.Temperature_normalized(), 100.0);
+    }
+
+    #[test]
+    fn env_vars() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_env_vars]
+        enum Hil {
+            MiscMessage,
+        }
+
+        assert_eq!(Hil::ENV_HIL_SPEED.name, "HilSpeed");
+        assert_eq!(Hil::ENV_HIL_SPEED.unit, "km/h");
+        assert_eq_float!(Hil::ENV_HIL_SPEED.max, 250.0);
+        assert_eq_float!(Hil::ENV_HIL_SPEED.initial, 50.0);
+        assert_eq!(Hil::ENV_HIL_MODE.id, 2);
+        assert_eq!(Hil::ENV_HIL_MODE.min..=Hil::ENV_HIL_MODE.max, 0..=3);
+        assert_eq!(Hil::ENV_HIL_MODE.initial, 1);
+    }
+
+    #[test]
+    fn attr_enums() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_attr_enums]
+        enum Attrs {
+            SixtyFourBitLE,
+        }
+
+        assert_eq!(SixtyFourBitLE::GEN_MSG_SEND_TYPE, GenMsgSendType::Event);
+        assert_ne!(GenMsgSendType::Cyclic, GenMsgSendType::IfActive);
+    }
+
+    #[cfg(feature = "arxml")]
+    #[test]
+    fn arxml() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/network.arxml"]
+        #[dbc_reflect]
+        struct Network {
+            doors: Doors,
+            lights: Lights,
+            status: Status,
+        }
+
+        // the I-signal's base type and the rational coefficients and
+        // unit of its COMPU-METHOD
+        let angle = &Doors::SIGNALS[2];
+        assert!(angle.signed && !angle.big_endian);
+        assert_eq_float!(angle.factor, 0.5);
+        assert_eq!(angle.unit, "deg");
+        // a TEXTTABLE reached through the system signal
+        assert_eq!(Doors::STATE_UNLOCKED, 2);
+        let mut t = Network::default();
+        assert!(t.doors.decode(&[0x21, 0xF6]));
+        assert_eq!(t.doors.State, Doors::STATE_UNLOCKED);
+        assert_eq_float!(t.doors.Angle, -5.0);
+
+        // MOST-SIGNIFICANT-BYTE-FIRST packing, from the MSB position
+        assert!(Lights::EXTENDED);
+        assert_eq!(Lights::ID, 0x18FF_0010);
+        let level = &Lights::SIGNALS[0];
+        assert!(level.big_endian);
+        assert_eq!((level.start, level.width), (15, 12));
+        assert!(t.lights.decode(&[0x00, 0xAB, 0xC0]));
+        assert_eq!(t.lights.Level, 0xABC);
+
+        // a hexadecimal IDENTIFIER, a REPEATING-TIME period and an
+        // I-PDU placed within its frame by START-POSITION
+        assert_eq!(Status::ID, 0x1A0);
+        assert_eq!(Status::CYCLE_TIME, 50);
+        let temp = &Status::SIGNALS[0];
+        assert_eq!(temp.start, 16);
+        assert_eq_float!(temp.offset, -40.0);
+        assert_eq!(temp.unit, "degC");
+        assert!(t.status.decode(&[0x00, 0x00, 0xA0, 0x00]));
+        assert_eq_float!(t.status.Temp, 40.0);
+    }
+
+    #[cfg(feature = "dbf")]
+    #[test]
+    fn dbf() {
+        mod dash {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/network.dbf"]
+            #[dbc_tx_node = "Dash"]
+            #[dbc_reflect]
+            pub struct Network {
+                pub doors: Doors,
+                pub lights: Lights,
+            }
+        }
+        use dash::{Doors, Lights, Status};
+
+        // byte numbers count from 1, and `B` and `I` signals are
+        // booleans and signed
+        let signals = Doors::SIGNALS;
+        assert_eq!((signals[0].start, signals[0].width), (0, 1));
+        assert_eq!((signals[2].start, signals[2].width), (8, 8));
+        assert!(signals[2].signed);
+        assert_eq!(signals[2].unit, "deg");
+        let mut t = dash::Network::default();
+        assert!(t.doors.decode(&[0x21, 0xF6]));
+        assert!(t.doors.DriverOpen);
+        assert_eq!(t.doors.State, Doors::STATE_UNLOCKED);
+        assert_eq_float!(t.doors.Angle, -5.0);
+
+        // Motorola signals are given by their least significant bit
+        assert!(Lights::EXTENDED);
+        let level = &Lights::SIGNALS[0];
+        assert!(level.big_endian);
+        assert_eq!(level.start, 15);
+        assert!(t.lights.decode(&[0x00, 0xAB, 0xC0]));
+        assert_eq!(t.lights.Level, 0xABC);
+
+        // generated as transmitted by Dash
+        assert_eq!(Status::ID, 416);
+        assert_eq_float!(Status::SIGNALS[0].offset, -40.0);
+        let mut status = Status::new();
+        assert!(status.decode(&[0xA0]));
+        assert_eq_float!(status.Temp, 40.0);
+    }
+
+    #[cfg(feature = "ldf")]
+    #[test]
+    fn ldf() {
+        mod body {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/network.ldf"]
+            #[dbc_rx_node = "Body"]
+            #[dbc_reflect]
+            pub struct Network {
+                pub lights: Lights,
+            }
+        }
+
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/network.ldf"]
+        #[dbc_reflect]
+        struct Network {
+            doors: Doors,
+            lights: Lights,
+        }
+
+        assert_eq!(Network::DBC_VERSION, "2.1");
+        // LIN frame IDs are standard IDs
+        assert_eq!(Doors::ID, 0x20);
+        assert!(!Doors::EXTENDED);
+        // the physical_value encoding gives the scaling of an unsigned
+        // signal, and initial values its start value
+        let angle = &Doors::SIGNALS[2];
+        assert!(!angle.signed);
+        assert_eq_float!(angle.factor, 0.5);
+        assert_eq_float!(angle.offset, -64.0);
+        assert_eq!(angle.unit, "deg");
+        let doors = Doors::new();
+        assert_eq!(doors.State, Doors::STATE_LOCKED);
+        assert_eq_float!(doors.Angle, 0.0);
+        let mut t = Network::default();
+        assert!(t.doors.decode(&[0x21, 0x76]));
+        assert_eq!(t.doors.State, Doors::STATE_UNLOCKED);
+        assert_eq_float!(t.doors.Angle, -5.0);
+
+        // signals are sent least significant bit first, and a byte
+        // array's initial values are not a start value
+        assert_eq!(Lights::DLC, 4);
+        let level = &Lights::SIGNALS[0];
+        assert!(!level.big_endian);
+        assert_eq!((level.start, level.width), (4, 12));
+        assert_eq!(Lights::new().Serial, 0);
+        assert!(t.lights.decode(&[0xC0, 0xAB, 0x34, 0x12]));
+        assert_eq!(t.lights.Level, 0xABC);
+        assert_eq!(t.lights.Serial, 0x1234);
+
+        // the subscribers of each signal are its receivers
+        assert_eq!(body::Lights::SIGNALS.len(), 2);
+    }
+
+    #[cfg(feature = "kcd")]
+    #[test]
+    fn kcd() {
+        mod dash {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/network.kcd"]
+            #[dbc_tx_node = "Dash"]
+            #[dbc_rx_node = "Dash"]
+            #[dbc_reflect]
+            pub struct Network {
+                pub doors: Doors,
+            }
+        }
+
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/network.kcd"]
+        #[dbc_reflect]
+        struct Network {
+            doors: Doors,
+            lights: Lights,
+            status: Status,
+        }
+
+        assert_eq!(Network::DBC_VERSION, "1.2");
+        assert_eq!(Doors::ID, 0x120);
+        assert_eq!(Doors::CYCLE_TIME, 100);
+        // signals are one bit unless given a length, and are scaled by
+        // their <Value>
+        assert_eq!(Doors::SIGNALS[0].width, 1);
+        let angle = &Doors::SIGNALS[2];
+        assert!(angle.signed);
+        assert_eq_float!(angle.factor, 0.5);
+        assert_eq!(angle.unit, "deg");
+        let mut t = Network::default();
+        assert!(t.doors.decode(&[0x21, 0xF6]));
+        assert_eq!(t.doors.State, Doors::STATE_UNLOCKED);
+        assert_eq_float!(t.doors.Angle, -5.0);
+
+        // big-endian offsets count from the most significant bit, and
+        // the length is that needed by the signals unless given
+        assert!(Lights::EXTENDED);
+        assert_eq!(Lights::DLC, 3);
+        let level = &Lights::SIGNALS[0];
+        assert!(level.big_endian);
+        assert_eq!(level.start, 15);
+        assert!(t.lights.decode(&[0x00, 0xAB, 0xC0]));
+        assert_eq!(t.lights.Level, 0xABC);
+
+        assert_eq!(Status::DLC, 1);
+        assert_eq_float!(Status::SIGNALS[0].offset, -40.0);
+        assert!(t.status.decode(&[0xA0]));
+        assert_eq_float!(t.status.Temp, 40.0);
+
+        // producers and consumers are named by their node IDs
+        assert_eq!(dash::Doors::SIGNALS.len(), 1);
+        assert_eq!(dash::Doors::SIGNALS[0].name, "State");
+        assert_eq!(dash::Status::ID, 416);
+    }
+
+    #[cfg(feature = "sym")]
+    #[test]
+    fn sym() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/bench.sym"]
+        #[dbc_reflect]
+        struct Bench {
+            doors: Doors,
+            lights: Lights,
+            status: Status,
+        }
+
+        // IDs with an `h` suffix are hexadecimal, `bit` signals are
+        // booleans, and enums may span several lines
+        assert_eq!(Doors::ID, 0x120);
+        assert_eq!(Doors::CYCLE_TIME, 100);
+        assert_eq!(Doors::SIGNALS[0].width, 1);
+        assert_eq!(Doors::STATE_LOCKED, 1);
+        assert_eq!(Doors::STATE_UNLOCKED, 2);
+        let mut t = Bench::default();
+        assert!(t.doors.decode(&[0x21, 0xF6]));
+        assert!(t.doors.DriverOpen);
+        assert_eq!(t.doors.State, Doors::STATE_UNLOCKED);
+        assert_eq_float!(t.doors.Angle, -5.0);
+
+        // a signal of the {SIGNALS} section, Motorola with `-m`
+        assert!(Lights::EXTENDED);
+        assert_eq!(Lights::DLC, 3);
+        let level = &Lights::SIGNALS[0];
+        assert!(level.big_endian);
+        assert_eq!((level.start, level.width), (15, 12));
+        assert!(t.lights.decode(&[0x00, 0xAB, 0xC0]));
+        assert_eq!(t.lights.Level, 0xABC);
+
+        // a quoted unit, an offset, and a comment after the flags
+        let temp = &Status::SIGNALS[0];
+        assert_eq!(temp.unit, "deg C");
+        assert_eq_float!(temp.factor, 0.5);
+        assert_eq_float!(temp.offset, -40.0);
+        assert!(t.status.decode(&[0xA0]));
+        assert_eq_float!(t.status.Temp, 40.0);
+    }
+
+    #[test]
+    fn snapshot() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_snapshot]
+        struct Shared {
+            misc: MiscMessage,
+        }
+
+        static MISC: MiscMessageShared = MiscMessageShared::new();
+        assert!(!MISC.snapshot().Bool_A);
+        assert!(!MISC.store(&[0x01]));
+        assert!(MISC.store(&[0x01, 0x80]));
+        let misc = MISC.snapshot();
+        assert!(misc.Bool_A);
+        assert_eq_float!(misc.Float_A, 64.25);
+
+        let writer = std::thread::spawn(|| {
+            for n in 0..10_000u16 {
+                let v = (n % 2) as u8;
+                assert!(MISC.store(&[v, v]));
+            }
+        });
+        // the two bytes are always written together
+        while !writer.is_finished() {
+            if let Some(misc) = MISC.try_snapshot() {
+                assert_eq!(misc.Bool_A, misc.Float_A != 0.25);
+            }
+        }
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn recorder() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_recorder = 2]
+        enum Recorded {
+            MiscMessage,
+            Extended1,
+        }
+
+        let mut r = RecordedFrameRecorder::new();
+        assert!(r.is_empty());
+        assert!(r.record(8191, false, &[0x01, 0x80], 10));
+        assert!(!r.record(1023, false, &[0; 8], 20)); // not derived
+        assert!(r.record(0x0012_3456, true, &[1, 2, 3, 4, 5, 6, 7, 8], 30));
+        assert!(r.record(8191, false, &[0x00, 0x00], 40));
+        assert_eq!(r.len(), 2);
+        assert_eq!(r.iter().map(|f| f.time).collect::<Vec<_>>(), [30, 40]);
+
+        let mut s = String::new();
+        r.dump(&mut s).unwrap();
+        assert_eq!(
+            s,
+            "30 00123456x Extended1 [8] 01 02 03 04 05 06 07 08\n\
+             40 1FFF  MiscMessage [2] 00 00\n"
+        );
+    }
+
+    #[test]
+    fn atomic() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Atomics {
+            #[dbc_atomic]
+            misc: MiscMessage,
+            #[dbc_atomic]
+            sixty_four: SixtyFourBitLE,
+        }
+
+        let misc = MiscMessageAtomic::default();
+        assert!(misc.decode(&[0x01, 0x80]));
+        assert!(!misc.decode(&[0x01]));
+        assert!(misc.Bool_A());
+        assert_eq_float!(misc.Float_A(), 64.25);
+        misc.set_Bool_B(true);
+        let m = misc.load();
+        assert!(m.Bool_A && m.Bool_B);
+        assert_eq_float!(m.Float_A, 64.25);
+
+        // 64-bit signals are not held
+        assert!(SixtyFourBitLEAtomic::default().decode(&[0; 8]));
+    }
+
+    #[test]
+    fn multiple_files() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_file = "tests/example.dbc"]
+        enum Merged {
+            MiscMessage,
+            SixtyFourBitLE,
+        }
+
+        assert_eq!(MiscMessage::ID, 8191);
+        assert_eq!(SixtyFourBitLE::ID, 64);
+    }
+
+    #[test]
+    fn file_list() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file("tests/test.dbc", "tests/example.dbc")]
+        enum Merged {
+            MiscMessage,
+            SixtyFourBitLE,
+        }
+
+        assert_eq!(MiscMessage::ID, 8191);
+        assert_eq!(SixtyFourBitLE::ID, 64);
+    }
+
+    #[test]
+    fn field_files() {
+        mod gateway {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            pub struct Gateway {
+                pub speed: WheelSpeedA,
+                #[dbc_file = "tests/variant.dbc"]
+                pub misc: MiscMessage,
+            }
+        }
+        use gateway::{Gateway, GatewayMessageKind};
+
+        assert_eq!(gateway::MiscMessage::ID, 8190);
+        let mut g = Gateway::default();
+        assert_eq!(
+            g.update(8190, false, &[0x02, 0]),
+            Some(GatewayMessageKind::MiscMessage)
+        );
+        assert!(g.misc.Bool_A);
+        assert_eq!(
+            g.update(300, false, &[0; 2]),
+            Some(GatewayMessageKind::WheelSpeedA)
+        );
+    }
+
+    #[test]
+    fn inline() {
+        mod fixture {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_inline = r#"
+VERSION ""
+
+BU_: Ecu1
+
+BO_ 1000 Inline: 2 Ecu1
+ SG_ Level : 0|12@1+ (0.5,0) [0|2047.5] "" Vector__XXX
+"#]
+            pub struct Fixture {
+                pub inline: Inline,
+            }
+        }
+
+        let mut fixture = fixture::Fixture::default();
+        assert!(fixture.update(1000, false, &[0x10, 0x00]).is_some());
+        assert_eq_float!(fixture.inline.Level, 8.0);
+    }
+
+    #[test]
+    fn keywords() {
+        mod fixture {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData, Default)]
+            #[dbc_inline = r#"
+VERSION ""
+
+BU_: Ecu1
+
+BO_ 1001 Keywords: 1 Ecu1
+ SG_ type : 0|4@1+ (1,0) [0|15] "" Vector__XXX
+ SG_ loop : 4|4@1+ (1,0) [0|15] "" Vector__XXX
+"#]
+            pub struct Fixture {
+                pub keywords: Keywords,
+            }
+        }
+
+        let mut fixture = fixture::Fixture::default();
+        assert!(fixture.update(1001, false, &[0x21]).is_some());
+        assert_eq!(fixture.keywords.type_, 1);
+        assert_eq!(fixture.keywords.loop_, 2);
+    }
+
+    #[test]
+    fn messages_macro() {
+        mod only {
+            dbc_data::dbc_messages!("tests/variant.dbc", [MiscMessage]);
+        }
+
+        let mut misc = only::MiscMessage::default();
+        assert_eq!(only::MiscMessage::ID, 8190);
+        assert!(misc.decode(&[0x02, 0x00]));
+        assert!(misc.Bool_A);
+    }
+
+    #[test]
+    fn tuple_struct() {
+        mod tuple {
+            use dbc_data::DbcData;
+
+            #[derive(DbcData, Default)]
+            #[dbc_file = "tests/test.dbc"]
+            pub struct Bus(pub WheelSpeedA, pub [GroupData1; 2]);
+        }
+
+        let mut bus = tuple::Bus::default();
+        assert_eq!(
+            bus.update(300, false, &[0x10, 0x27]),
+            Some(tuple::BusMessageKind::WheelSpeedA)
+        );
+        assert_eq_float!(bus.0.Speed, 100.0);
+        assert_eq!(
+            bus.update(129, false, &[0; 8]),
+            Some(tuple::BusMessageKind::GroupData1(1))
+        );
+        assert!(bus.1[1].received());
+    }
+
+    #[test]
+    fn unit_struct() {
+        mod unit {
+            use dbc_data::{dbc_messages, DbcData};
+
+            #[allow(dead_code)]
+            #[derive(DbcData)]
+            #[dbc_file = "tests/test.dbc"]
+            #[dbc_messages = "WheelSpeedA, ENGINE_DATA"]
+            pub struct Types;
+
+            dbc_messages!("tests/variant.dbc", [MiscMessage]);
+        }
+
+        assert_eq!(unit::WheelSpeedA::ID, 300);
+        assert_eq!(unit::ENGINE_DATA::DLC, 2);
+        assert_eq!(unit::MiscMessage::ID, 8190);
+    }
+
+    #[test]
+    fn conflict_first() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_file = "tests/variant.dbc"]
+        #[dbc_conflict = "first"]
+        enum Merged {
+            MiscMessage,
+        }
+
+        assert_eq!(MiscMessage::ID, 8191);
+    }
+
+    #[test]
+    fn conflict_last() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_file = "tests/variant.dbc"]
+        #[dbc_conflict = "last"]
+        enum Merged {
+            MiscMessage,
+        }
+
+        assert_eq!(MiscMessage::ID, 8190);
+    }
+
+    #[test]
+    fn const_new() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_timestamp = "u32"]
+        struct Statics {
+            a: WheelSpeedA,
+            b: WheelSpeedB,
+        }
+
+        static A: WheelSpeedA = WheelSpeedA::new();
+        static B: WheelSpeedB = WheelSpeedB::new();
+
+        // start values are raw, and scaled as when decoded
+        assert_eq_float!(A.Speed, 50.0);
+        assert_eq!(B.Temp, 60);
+        assert_eq_float!(B.Speed, 0.0);
+        assert!(!A.received());
+        assert_eq!(A.rx_time(), None);
+        assert_eq_float!(WheelSpeedA::default().Speed, 50.0);
+    }
+
+    #[test]
+    fn storage_bytes() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_max_ram = 64]
+        struct Budget {
+            a: WheelSpeedA,
+            b: WheelSpeedB,
+        }
+
+        assert!(WheelSpeedA::STORAGE_BYTES >= 4);
+        assert!(
+            Budget::STORAGE_BYTES
+                >= WheelSpeedA::STORAGE_BYTES + WheelSpeedB::STORAGE_BYTES
+        );
+        assert!(Budget::STORAGE_BYTES <= 64);
+    }
+
+    #[test]
+    fn env_path() {
+        #[allow(dead_code)]
+        #[derive(DbcData)]
+        #[dbc_file = "${CARGO_MANIFEST_DIR}/tests/test.dbc"]
+        struct Env {
+            a: WheelSpeedA,
+        }
+
+        mod unbraced {
+            use dbc_data::DbcData;
+
+            #[allow(dead_code)]
+            #[derive(DbcData)]
+            #[dbc_file = "$CARGO_MANIFEST_DIR/tests/test.dbc"]
+            pub struct Env {
+                pub a: WheelSpeedA,
+            }
+        }
+
+        assert_eq!(WheelSpeedA::ID, 300);
+        assert_eq!(unbraced::WheelSpeedA::ID, 300);
+    }
+
+    #[test]
+    fn variants() {
+        let mut a = Harness::new(Variant::A);
+        let mut b = Harness::new(Variant::B);
+        assert_eq!(a.variant(), Variant::A);
+        assert_eq!(b.variant(), Variant::B);
+
+        // the same message is at different IDs and bit positions
+        assert!(a.update(8191, false, &[0x01, 0x00]));
+        assert!(!a.update(8190, false, &[0x02, 0x00]));
+        assert!(!b.update(8191, false, &[0x01, 0x00]));
+        assert!(b.update(8190, false, &[0x02, 0x00]));
+        match (&a, &b) {
+            (Harness::A(a), Harness::B(b)) => {
+                assert!(a.misc.Bool_A);
+                assert!(b.misc.Bool_A);
+            }
+            _ => panic!("unexpected variants"),
+        }
+    }
+
+    #[test]
+    fn codegen_compat() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_codegen_compat]
+        struct Compat {
+            misc: MiscMessage,
+            sixty_four: SixtyFourBitLE,
+        }
+
+        let mut t = Compat::default();
+        t.misc.set_bool_a(true);
+        t.misc.set_float_a(64.25);
+        assert_eq!(t.misc.raw(), [0x01, 0x80]);
+        assert!(t.misc.set_raw(&[0x00, 0x80]).is_ok());
+        assert!(!t.misc.bool_a());
+        assert_eq!(
+            t.misc.set_raw(&[0x00]),
+            Err(CompatCanError::InvalidPayloadSize)
+        );
+
+        t.sixty_four.set_sixty_four(0x0123_4567_89AB_CDEF);
+        let pdu = t.sixty_four.raw();
+        match CompatMessages::from_can_message(SixtyFourBitLe::ID, &pdu) {
+            Ok(CompatMessages::SixtyFourBitLE(m)) => {
+                assert_eq!(m.sixty_four(), 0x0123_4567_89AB_CDEF);
+            }
+            _ => panic!("expected SixtyFourBitLE"),
+        }
+        assert_eq!(
+            CompatMessages::from_can_message(0x7FF, &pdu).err(),
+            Some(CompatCanError::UnknownMessageId(0x7FF))
+        );
+    }
+
+    #[test]
+    fn codegen_compat_dirty() {
+        mod fixture {
+            use dbc_data::DbcData;
+
+            #[derive(DbcData, Default)]
+            #[dbc_inline = r#"
+VERSION ""
+
+BU_: Ecu1
+
+BO_ 1002 Snake: 2 Ecu1
+ SG_ speed : 0|8@1+ (1,0) [0|255] "" Vector__XXX
+ SG_ Gear : 8|4@1+ (1,0) [0|15] "" Vector__XXX
+"#]
+            #[dbc_codegen_compat]
+            pub struct Fixture {
+                #[dbc_dirty]
+                pub snake: Snake,
+            }
+        }
+        use fixture::SnakeSignals;
+
+        // `speed()` and `set_speed()` are those of the private field
+        let mut fixture = fixture::Fixture::default();
+        let mut pdu = [0u8; 2];
+        assert!(fixture.snake.encode_dirty(&mut pdu));
+        fixture.snake.set_speed(10);
+        fixture.snake.set_gear(3);
+        assert_eq!((fixture.snake.speed(), fixture.snake.gear()), (10, 3));
+        assert_eq!(
+            fixture.snake.dirty(),
+            SnakeSignals::speed | SnakeSignals::Gear
+        );
     }
 
     #[test]